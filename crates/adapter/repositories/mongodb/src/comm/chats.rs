@@ -3,17 +3,18 @@ use futures::stream::BoxStream;
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Chat, ChatState, Message},
+        comm::{Chat, ChatHandler, ChatState, Message},
     },
     traits::Key,
 };
 use kernel_repositories::{
-    comm::{ChatsRepo, InsertChat},
+    comm::{ChatsRepo, InsertChat, MessageChange},
     error::{RepoError, RepoResult},
     traits::{ChildRepo, InsertRepo, StatsPair, StatsRepo},
 };
 use mongodb::{
     bson::{doc, Document},
+    change_stream::event::{ChangeStreamEvent, OperationType},
     options::{ChangeStreamOptions, FindOptions, FullDocumentType},
 };
 use tokio_stream::StreamExt;
@@ -29,12 +30,12 @@ impl ChatsRepo for MongoDbRepo<Chat> {
     async fn watch(
         &self,
         id: &Key<Chat>,
-    ) -> RepoResult<BoxStream<'_, RepoResult<Message>>> {
+    ) -> RepoResult<BoxStream<'_, RepoResult<MessageChange>>> {
         let filter = doc! {
             "$match": {
                 "$and": [
                     { "fullDocument.chat_id": id.value_ref() },
-                    { "operationType": "insert" }
+                    { "operationType": { "$in": ["insert", "update"] } }
                 ]
             }
         };
@@ -45,18 +46,143 @@ impl ChatsRepo for MongoDbRepo<Chat> {
     async fn watch_all_of(
         &self,
         user_id: &Key<User>,
-    ) -> RepoResult<BoxStream<'static, RepoResult<Message>>> {
+    ) -> RepoResult<BoxStream<'static, RepoResult<MessageChange>>> {
         let filter = doc! {
             "$match": {
                 "$and": [
                     { "fullDocument.user_id": user_id.value_ref() },
-                    { "operationType": "insert" }
+                    { "operationType": { "$in": ["insert", "update"] } }
                 ]
             }
         };
 
         self.watch_messages(filter).await
     }
+
+    async fn set_handler(
+        &self,
+        id: &Key<Chat>,
+        handler: ChatHandler,
+        at: DateTime<Utc>,
+    ) -> RepoResult<bool> {
+        let human_active_at = match handler {
+            | ChatHandler::Bot => None,
+            | ChatHandler::Human => Some(at),
+        };
+
+        // only matched if the handler actually changes, so that watchers are
+        // not notified of mere activity refreshes
+        let ret = self
+            .collection()
+            .update_one(
+                doc! {
+                    ENTITY_ID_FIELD: id.value_ref(),
+                    "handler": { "$ne": handler.to_string() }
+                },
+                doc! {
+                    "$set": {
+                        "handler": handler.to_string(),
+                        "human_active_at": human_active_at,
+                        "updated_at": Utc::now(),
+                    }
+                },
+                None,
+            )
+            .await
+            .map_err(map_mongo_error)?;
+
+        if ret.matched_count == 1 {
+            return Ok(true);
+        }
+
+        let filter = doc! { ENTITY_ID_FIELD: id.value_ref() };
+        let matched = match handler {
+            | ChatHandler::Bot => self
+                .collection()
+                .count_documents(filter, None)
+                .await
+                .map_err(map_mongo_error)?,
+            | ChatHandler::Human => {
+                self.collection()
+                    .update_one(
+                        filter,
+                        doc! { "$set": { "human_active_at": at } },
+                        None,
+                    )
+                    .await
+                    .map_err(map_mongo_error)?
+                    .matched_count
+            }
+        };
+
+        if matched != 1 {
+            return Err(RepoError::NotFound);
+        }
+
+        Ok(false)
+    }
+
+    async fn resume_idle_handoffs(
+        &self,
+        idle_since: DateTime<Utc>,
+    ) -> RepoResult<u64> {
+        let ret = self
+            .collection()
+            .update_many(
+                doc! {
+                    "handler": ChatHandler::Human.to_string(),
+                    "human_active_at": { "$lte": idle_since },
+                },
+                doc! {
+                    "$set": {
+                        "handler": ChatHandler::Bot.to_string(),
+                        "human_active_at": None::<DateTime<Utc>>,
+                        "updated_at": Utc::now(),
+                    }
+                },
+                None,
+            )
+            .await
+            .map_err(map_mongo_error)?;
+
+        Ok(ret.modified_count)
+    }
+
+    async fn watch_handlers_of(
+        &self,
+        user_id: &Key<User>,
+    ) -> RepoResult<BoxStream<'static, RepoResult<Chat>>> {
+        let filter = doc! {
+            "$match": {
+                "$and": [
+                    { "fullDocument.user_id": user_id.value_ref() },
+                    { "operationType": "update" },
+                    {
+                        "updateDescription.updatedFields.handler": {
+                            "$exists": true
+                        }
+                    }
+                ]
+            }
+        };
+
+        let opts = ChangeStreamOptions::builder()
+            .full_document(Some(FullDocumentType::UpdateLookup))
+            .build();
+
+        Ok(futures::StreamExt::boxed(futures::StreamExt::filter_map(
+            self.collection()
+                .watch(vec![filter], opts)
+                .await
+                .map_err(map_mongo_error)?,
+            |e| async move {
+                match e {
+                    | Ok(event) => event.full_document.map(Ok),
+                    | Err(err) => Some(Err(map_mongo_error(err))),
+                }
+            },
+        )))
+    }
 }
 
 #[async_trait::async_trait]
@@ -67,6 +193,8 @@ impl InsertRepo<InsertChat> for MongoDbRepo<Chat> {
             label: model.label,
             state: model.state,
             user_id: model.user_id,
+            handler: ChatHandler::Bot,
+            human_active_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -163,9 +291,10 @@ impl MongoDbRepo<Chat> {
     async fn watch_messages<F: Into<Document>>(
         &self,
         filter: F,
-    ) -> RepoResult<BoxStream<'static, RepoResult<Message>>> {
+    ) -> RepoResult<BoxStream<'static, RepoResult<MessageChange>>> {
+        // updates are looked up to match against the full document
         let opts = ChangeStreamOptions::builder()
-            .full_document(Some(FullDocumentType::Required))
+            .full_document(Some(FullDocumentType::UpdateLookup))
             .build();
 
         Ok(futures::StreamExt::boxed(futures::StreamExt::filter_map(
@@ -176,10 +305,7 @@ impl MongoDbRepo<Chat> {
                 .map_err(map_mongo_error)?,
             |e| async move {
                 match e {
-                    | Ok(event) => match event.full_document {
-                        | Some(doc) => Some(Ok(doc)),
-                        | None => None,
-                    },
+                    | Ok(event) => message_change_of(event).map(Ok),
                     | Err(err) => Some(Err(map_mongo_error(err))),
                 }
             },
@@ -187,6 +313,30 @@ impl MongoDbRepo<Chat> {
     }
 }
 
+fn message_change_of(
+    event: ChangeStreamEvent<Message>,
+) -> Option<MessageChange> {
+    let message = event.full_document?;
+
+    match event.operation_type {
+        | OperationType::Insert => Some(MessageChange::Created(message)),
+        | OperationType::Update => {
+            let fields = event.update_description?.updated_fields;
+
+            if fields.contains_key("deleted_at") {
+                Some(MessageChange::Deleted(message))
+            } else if fields.contains_key("text") {
+                Some(MessageChange::Edited(message))
+            } else if fields.contains_key("status") {
+                Some(MessageChange::StatusChanged(message))
+            } else {
+                None
+            }
+        }
+        | _ => None,
+    }
+}
+
 impl CollectionEntity for Chat {
     fn name() -> &'static str {
         "chats"
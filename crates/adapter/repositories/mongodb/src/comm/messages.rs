@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use kernel_entities::{
-    entities::comm::{Chat, Message},
+    entities::{
+        comm::{Chat, Message, MessageStatus},
+        link::Instance,
+    },
     traits::Key,
 };
 use kernel_repositories::{
@@ -8,35 +11,136 @@ use kernel_repositories::{
     error::{RepoError, RepoResult},
     traits::{ChildRepo, InsertRepo, Repo},
 };
-use mongodb::{bson::doc, options::FindOptions};
+use mongodb::{
+    bson::doc,
+    options::{
+        FindOneAndUpdateOptions,
+        FindOptions,
+        IndexOptions,
+        ReturnDocument,
+        UpdateModifications,
+    },
+    Collection,
+};
 use tokio_stream::StreamExt;
 
 use crate::{
     repo::{MongoDbRepo, ENTITY_CREATED_AT_FIELD, ENTITY_ID_FIELD},
     traits::collection_entity::CollectionEntity,
-    util::error::map_mongo_error,
+    util::{error::map_mongo_error, index::create_index},
 };
 
 #[async_trait::async_trait]
 impl MessagesRepo for MongoDbRepo<Message> {
+    async fn get_by_platform_id(
+        &self,
+        instance_id: &Key<Instance>,
+        platform_message_id: &str,
+    ) -> RepoResult<Message> {
+        self.find_one(
+            doc! {
+                "instance_id": instance_id.value_ref(),
+                "platform_message_id": platform_message_id,
+            },
+            None,
+        )
+        .await
+    }
+
     async fn update_text(
         &self,
         id: &Key<Message>,
         new_text: Option<String>,
     ) -> RepoResult<Message> {
-        let message = self.get(id).await?;
+        // previous texts are kept in `changes` as an edit history, which is
+        // appended to in the same pipeline, so concurrent edits keep all of
+        // them
+        let update = vec![doc! {
+            "$set": {
+                "changes": {
+                    "$cond": {
+                        "if": { "$eq": [{ "$type": "$text" }, "string"] },
+                        "then": {
+                            "$concatArrays": [
+                                { "$ifNull": ["$changes", []] },
+                                ["$text"],
+                            ]
+                        },
+                        "else": "$changes",
+                    }
+                },
+                "text": { "$literal": new_text },
+                "updated_at": Utc::now(),
+            }
+        }];
 
-        self.collection().update_one(
-            doc! {"id": message.id.to_string()},
-            doc! {"$set": {
+        self.update_one_returning(id, update).await
+    }
+
+    async fn mark_deleted(
+        &self,
+        id: &Key<Message>,
+        deleted_at: DateTime<Utc>,
+    ) -> RepoResult<Message> {
+        self.update_one_returning(
+            id,
+            doc! {
+                "$set": {
+                    "deleted_at": deleted_at,
                     "updated_at": Utc::now(),
-                    format!("changes.{}.content", message.changes.len()): new_text,
                 }
             },
-            None,
-        ).await.map_err(map_mongo_error)?;
+        )
+        .await
+    }
 
-        Ok(message)
+    async fn update_status(
+        &self,
+        id: &Key<Message>,
+        status: MessageStatus,
+        platform_message_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    ) -> RepoResult<Message> {
+        let mut fields = doc! {
+            "status": status.to_string(),
+            "updated_at": Utc::now(),
+        };
+
+        match status {
+            | MessageStatus::Delivered => {
+                fields.insert("delivered_at", timestamp);
+            }
+            | MessageStatus::Seen => {
+                fields.insert("seen_at", timestamp);
+            }
+            | _ => {}
+        }
+
+        if let Some(platform_message_id) = platform_message_id {
+            fields.insert("platform_message_id", platform_message_id);
+        }
+
+        self.update_one_returning(id, doc! { "$set": fields }).await
+    }
+}
+
+impl MongoDbRepo<Message> {
+    async fn update_one_returning(
+        &self,
+        id: &Key<Message>,
+        update: impl Into<UpdateModifications>,
+    ) -> RepoResult<Message> {
+        self.collection()
+            .find_one_and_update(
+                doc! {ENTITY_ID_FIELD: id.value_ref()},
+                update,
+                FindOneAndUpdateOptions::builder()
+                    .return_document(Some(ReturnDocument::After))
+                    .build(),
+            )
+            .await
+            .map_err(map_mongo_error)?
+            .ok_or(RepoError::NotFound)
     }
 }
 
@@ -47,13 +151,19 @@ impl InsertRepo<InsertMessage> for MongoDbRepo<Message> {
             id: uuid::Uuid::new_v4().into(),
             text: model.text,
             changes: Vec::new(),
-            attachments: Vec::new(),
+            attachments: model.attachments,
             direction: model.direction,
+            status: model.status,
             delivered_at: model.delivered_at,
             seen_at: None,
             user_id: model.user_id,
             chat_id: model.chat_id,
             instance_id: model.instance_id,
+            channel_id: Some(model.channel_id),
+            platform_message_id: model.platform_message_id,
+            reply_to: model.reply_to,
+            callback_data: model.callback_data,
+            idempotency_key: model.idempotency_key,
             deleted_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -116,8 +226,38 @@ impl ChildRepo<Chat> for MongoDbRepo<Message> {
     }
 }
 
+#[async_trait::async_trait]
 impl CollectionEntity for Message {
     fn name() -> &'static str {
         "messages"
     }
+
+    async fn initialize_collection(
+        collection: &Collection<Self>,
+    ) -> RepoResult<()> {
+        // platform message ids are used to look up edited & deleted messages
+        create_index(
+            collection,
+            doc! {"instance_id": 1, "platform_message_id": 1},
+            None,
+        )
+        .await?;
+
+        // rejects messages of already processed updates; only keyed messages
+        // are indexed, as outgoing ones have no key
+        create_index(
+            collection,
+            doc! {"idempotency_key": 1},
+            Some(
+                IndexOptions::builder()
+                    .name(Some("idempotency_key_unique".to_owned()))
+                    .unique(Some(true))
+                    .partial_filter_expression(Some(doc! {
+                        "idempotency_key": { "$type": "string" }
+                    }))
+                    .build(),
+            ),
+        )
+        .await
+    }
 }
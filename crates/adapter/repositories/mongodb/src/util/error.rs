@@ -1,8 +1,15 @@
 use kernel_repositories::error::RepoError;
-use mongodb::error::ErrorKind;
+use mongodb::error::{ErrorKind, WriteFailure};
+
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
 
 pub fn map_mongo_error(err: mongodb::error::Error) -> RepoError {
     match *err.kind {
+        | ErrorKind::Write(WriteFailure::WriteError(write_err))
+            if write_err.code == DUPLICATE_KEY_ERROR_CODE =>
+        {
+            RepoError::AlreadyExists
+        }
         | ErrorKind::InvalidArgument { message, .. }
         | ErrorKind::InvalidResponse { message, .. } => {
             RepoError::InvalidParameter(message)
@@ -0,0 +1,2 @@
+ALTER TABLE channels ADD COLUMN phone_number_id VARCHAR NULL;
+ALTER TABLE channels ADD COLUMN webhook_secret VARCHAR NULL;
@@ -0,0 +1 @@
+ALTER TABLE channels ADD COLUMN use_webhook BOOLEAN DEFAULT FALSE NOT NULL;
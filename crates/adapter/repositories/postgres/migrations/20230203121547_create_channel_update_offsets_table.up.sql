@@ -0,0 +1,12 @@
+CREATE TABLE channel_update_offsets
+(
+    channel_id UUID NOT NULL PRIMARY KEY,
+
+    update_offset BIGINT NOT NULL,
+
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    CONSTRAINT channel_fk FOREIGN KEY (channel_id)
+                          REFERENCES channels(id)
+                          ON DELETE CASCADE
+);
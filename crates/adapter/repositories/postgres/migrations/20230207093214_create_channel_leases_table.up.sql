@@ -0,0 +1,13 @@
+CREATE TABLE channel_leases
+(
+    channel_id UUID NOT NULL PRIMARY KEY,
+
+    node_id TEXT NOT NULL,
+
+    acquired_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+
+    CONSTRAINT channel_fk FOREIGN KEY (channel_id)
+                          REFERENCES channels(id)
+                          ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+DROP INDEX instances_platform_group_id_idx;
+DROP INDEX instances_chat_id_idx;
+
+ALTER TABLE instances DROP COLUMN platform_group_id;
+ALTER TABLE instances ADD CONSTRAINT chat_fk UNIQUE (chat_id);
+
+ALTER TABLE channels DROP COLUMN group_mode;
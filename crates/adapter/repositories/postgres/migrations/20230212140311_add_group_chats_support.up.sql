@@ -0,0 +1,8 @@
+ALTER TABLE channels ADD COLUMN group_mode INTEGER DEFAULT 1 NOT NULL;
+
+ALTER TABLE instances DROP CONSTRAINT chat_fk;
+ALTER TABLE instances ADD COLUMN platform_group_id BIGINT NULL;
+
+CREATE INDEX instances_chat_id_idx ON instances USING btree (chat_id);
+CREATE INDEX instances_platform_group_id_idx ON instances
+    USING btree (channel_id, platform_group_id);
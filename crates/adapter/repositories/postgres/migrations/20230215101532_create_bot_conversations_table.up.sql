@@ -0,0 +1,22 @@
+CREATE TABLE bot_conversations
+(
+    bot_id UUID NOT NULL,
+    instance_id UUID NOT NULL,
+    menu_id UUID NOT NULL,
+
+    data JSONB DEFAULT '{}'::jsonb NOT NULL,
+
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    PRIMARY KEY (bot_id, instance_id),
+
+    CONSTRAINT bot_fk FOREIGN KEY (bot_id)
+                      REFERENCES bots(id)
+                      ON DELETE CASCADE,
+    CONSTRAINT instance_fk FOREIGN KEY (instance_id)
+                           REFERENCES instances(id)
+                           ON DELETE CASCADE,
+    CONSTRAINT menu_fk FOREIGN KEY (menu_id)
+                       REFERENCES menus(id)
+                       ON DELETE CASCADE
+);
@@ -0,0 +1,20 @@
+CREATE TABLE bot_channels
+(
+    id UUID NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+
+    bot_id UUID NOT NULL,
+    channel_id UUID NOT NULL,
+    priority INTEGER DEFAULT 0 NOT NULL,
+
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    CONSTRAINT bot_channels_unique UNIQUE (bot_id, channel_id),
+
+    CONSTRAINT bot_fk FOREIGN KEY (bot_id)
+                      REFERENCES bots(id)
+                      ON DELETE CASCADE,
+    CONSTRAINT channel_fk FOREIGN KEY (channel_id)
+                          REFERENCES channels(id)
+                          ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE menus DROP COLUMN hands_off;
@@ -0,0 +1 @@
+ALTER TABLE menus ADD COLUMN hands_off BOOLEAN DEFAULT FALSE NOT NULL;
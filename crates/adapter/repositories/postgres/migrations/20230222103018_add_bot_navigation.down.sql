@@ -0,0 +1,7 @@
+ALTER TABLE bot_conversations DROP COLUMN unmatched_count;
+
+ALTER TABLE bots DROP COLUMN reprompt_after;
+ALTER TABLE bots DROP COLUMN help_trigger;
+ALTER TABLE bots DROP COLUMN home_trigger;
+ALTER TABLE bots DROP COLUMN back_trigger;
+ALTER TABLE bots DROP COLUMN fallback_reply;
@@ -0,0 +1,8 @@
+ALTER TABLE bots ADD COLUMN fallback_reply VARCHAR NULL;
+ALTER TABLE bots ADD COLUMN back_trigger VARCHAR NULL DEFAULT 'back';
+ALTER TABLE bots ADD COLUMN home_trigger VARCHAR NULL DEFAULT 'home';
+ALTER TABLE bots ADD COLUMN help_trigger VARCHAR NULL DEFAULT 'help';
+ALTER TABLE bots ADD COLUMN reprompt_after INTEGER NULL;
+
+ALTER TABLE bot_conversations
+    ADD COLUMN unmatched_count INTEGER DEFAULT 0 NOT NULL;
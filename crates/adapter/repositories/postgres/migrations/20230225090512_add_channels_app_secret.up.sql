@@ -0,0 +1 @@
+ALTER TABLE channels ADD COLUMN app_secret VARCHAR NULL;
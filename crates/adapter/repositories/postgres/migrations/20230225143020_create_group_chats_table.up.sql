@@ -0,0 +1,22 @@
+CREATE TABLE group_chats
+(
+    channel_id UUID NOT NULL,
+    platform_group_id BIGINT NOT NULL,
+    chat_id UUID NOT NULL,
+
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    CONSTRAINT group_chats_pk PRIMARY KEY (channel_id, platform_group_id),
+
+    CONSTRAINT channel_fk FOREIGN KEY (channel_id)
+                          REFERENCES channels(id)
+                          ON DELETE CASCADE
+);
+
+-- groups already joined keep the chat of their first participant
+INSERT INTO group_chats (channel_id, platform_group_id, chat_id)
+SELECT DISTINCT ON (channel_id, platform_group_id)
+       channel_id, platform_group_id, chat_id
+FROM instances
+WHERE platform_group_id IS NOT NULL
+ORDER BY channel_id, platform_group_id, created_at;
@@ -0,0 +1 @@
+ALTER TABLE menus DROP COLUMN content_format;
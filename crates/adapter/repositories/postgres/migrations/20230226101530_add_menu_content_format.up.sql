@@ -0,0 +1 @@
+ALTER TABLE menus ADD COLUMN content_format INTEGER DEFAULT 0 NOT NULL;
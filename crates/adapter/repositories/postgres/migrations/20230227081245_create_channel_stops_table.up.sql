@@ -0,0 +1,10 @@
+CREATE TABLE channel_stops
+(
+    channel_id UUID NOT NULL PRIMARY KEY,
+
+    stopped_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+
+    CONSTRAINT channel_fk FOREIGN KEY (channel_id)
+                          REFERENCES channels(id)
+                          ON DELETE CASCADE
+);
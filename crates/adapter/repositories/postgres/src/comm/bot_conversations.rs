@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use kernel_entities::{
+    entities::{comm::Bot, link::Instance},
+    traits::Key,
+};
+use kernel_repositories::{
+    comm::{BotConversation, BotConversationsRepo},
+    error::{RepoError, RepoResult},
+};
+
+use crate::{database::SqlxPool, util::error::map_sqlx_error};
+
+pub(crate) struct SqlxBotConversationsRepo(pub SqlxPool);
+
+#[async_trait::async_trait]
+impl BotConversationsRepo for SqlxBotConversationsRepo {
+    async fn get_of(
+        &self,
+        bot_id: &Key<Bot>,
+        instance_id: &Key<Instance>,
+    ) -> RepoResult<Option<BotConversation>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT menu_id, data, unmatched_count, updated_at
+            FROM bot_conversations
+            WHERE bot_id = $1 AND instance_id = $2
+            "#,
+            bot_id.value_ref(),
+            instance_id.value_ref()
+        )
+        .fetch_optional(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let data = serde_json::from_value(row.data)
+            .map_err(|err| RepoError::Deserialization(err.to_string()))?;
+
+        Ok(Some(BotConversation {
+            bot_id: bot_id.clone(),
+            instance_id: instance_id.clone(),
+            menu_id: row.menu_id.into(),
+            data,
+            unmatched_count: row.unmatched_count,
+            updated_at: row.updated_at,
+        }))
+    }
+
+    async fn save(
+        &self,
+        conversation: &BotConversation,
+        loaded_at: &DateTime<Utc>,
+    ) -> RepoResult<bool> {
+        let data = serde_json::to_value(&conversation.data)
+            .map_err(|err| RepoError::Serialization(err.to_string()))?;
+
+        let ret = sqlx::query!(
+            r#"
+            INSERT INTO bot_conversations
+                (bot_id, instance_id, menu_id, data, unmatched_count,
+                 updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (bot_id, instance_id)
+            DO UPDATE SET
+                menu_id = EXCLUDED.menu_id,
+                data = EXCLUDED.data,
+                unmatched_count = EXCLUDED.unmatched_count,
+                updated_at = EXCLUDED.updated_at
+            WHERE bot_conversations.updated_at = $7
+            "#,
+            conversation.bot_id.value_ref(),
+            conversation.instance_id.value_ref(),
+            conversation.menu_id.value_ref(),
+            data,
+            conversation.unmatched_count,
+            conversation.updated_at,
+            loaded_at
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(ret.rows_affected() == 1)
+    }
+
+    async fn remove_of(
+        &self,
+        bot_id: &Key<Bot>,
+        instance_id: &Key<Instance>,
+    ) -> RepoResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM bot_conversations
+            WHERE bot_id = $1 AND instance_id = $2
+            "#,
+            bot_id.value_ref(),
+            instance_id.value_ref()
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
+}
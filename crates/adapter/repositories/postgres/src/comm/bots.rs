@@ -1,15 +1,19 @@
 use chrono::{DateTime, Utc};
 use futures::{stream::BoxStream, StreamExt, TryStreamExt};
 use kernel_entities::{
-    entities::{auth::User, comm::Bot},
+    entities::{
+        auth::User,
+        comm::{Bot, BotChannel},
+        link::Channel,
+    },
     traits::Key,
 };
 use kernel_repositories::{
-    comm::{BotsRepo, InsertBot},
-    error::RepoResult,
+    comm::{BotsRepo, InsertBot, UpdateBot},
+    error::{RepoError, RepoResult},
     traits::*,
 };
-use ormx::{Delete, Table};
+use ormx::{Delete, Patch, Table};
 use proc_macros::Repo;
 
 use crate::{
@@ -39,6 +43,132 @@ impl BotsRepo for SqlxBotsRepo {
         .map_err(map_sqlx_error)
         .boxed()
     }
+
+    fn stream_active_of(
+        &self,
+        user_id: Key<User>,
+    ) -> BoxStream<'_, RepoResult<Bot>> {
+        sqlx::query_as!(
+            models::BotModel,
+            r#"
+            SELECT * FROM bots
+            WHERE user_id = $1 AND is_active = TRUE
+            ORDER BY created_at
+            "#,
+            user_id.value()
+        )
+        .fetch(self.0.get())
+        .map_ok(Into::into)
+        .map_err(map_sqlx_error)
+        .boxed()
+    }
+
+    async fn get_channels_of(
+        &self,
+        bot_id: &Key<Bot>,
+    ) -> RepoResult<Vec<BotChannel>> {
+        sqlx_vec_ok!(
+            sqlx::query_as!(
+                models::BotChannelModel,
+                r#"
+                SELECT * FROM bot_channels
+                WHERE bot_id = $1
+                ORDER BY priority, created_at
+                "#,
+                bot_id.value_ref()
+            )
+            .fetch_all(self.0.get())
+            .await
+        )
+    }
+
+    async fn bind_channel(
+        &self,
+        bot_id: &Key<Bot>,
+        channel_id: &Key<Channel>,
+        priority: i32,
+    ) -> RepoResult<BotChannel> {
+        sqlx_ok!(
+            sqlx::query_as!(
+                models::BotChannelModel,
+                r#"
+                INSERT INTO bot_channels (bot_id, channel_id, priority)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (bot_id, channel_id)
+                DO UPDATE SET
+                    priority = EXCLUDED.priority,
+                    updated_at = NOW()
+                RETURNING *
+                "#,
+                bot_id.value_ref(),
+                channel_id.value_ref(),
+                priority
+            )
+            .fetch_one(self.0.get())
+            .await
+        )
+    }
+
+    async fn unbind_channel(
+        &self,
+        bot_id: &Key<Bot>,
+        channel_id: &Key<Channel>,
+    ) -> RepoResult<()> {
+        let ret = sqlx::query!(
+            r#"
+            DELETE FROM bot_channels
+            WHERE bot_id = $1 AND channel_id = $2
+            "#,
+            bot_id.value_ref(),
+            channel_id.value_ref()
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        if ret.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn update(&self, id: &Key<Bot>, model: UpdateBot) -> RepoResult<()> {
+        models::UpdateBotModel {
+            name: model.name,
+            is_active: model.is_active,
+            fallback_reply: model.fallback_reply,
+            back_trigger: model.back_trigger,
+            home_trigger: model.home_trigger,
+            help_trigger: model.help_trigger,
+            reprompt_after: model.reprompt_after,
+            updated_at: Utc::now(),
+        }
+        .patch_row(self.0.get(), id.value())
+        .await
+        .map_err(map_sqlx_error)
+    }
+
+    async fn set_active_of(
+        &self,
+        user_id: &Key<User>,
+        is_active: bool,
+    ) -> RepoResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE bots SET is_active = $1, updated_at = $2
+            WHERE user_id = $3 AND is_active != $1
+            "#,
+            is_active,
+            Utc::now(),
+            user_id.value_ref()
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -132,7 +262,10 @@ impl StatsRepo<User> for SqlxBotsRepo {
 mod models {
     use chrono::{DateTime, Utc};
     use derive_more::{From, Into};
-    use kernel_entities::{entities::comm::Bot, traits::KeyType};
+    use kernel_entities::{
+        entities::comm::{Bot, BotChannel},
+        traits::KeyType,
+    };
     use kernel_repositories::comm::InsertBot;
 
     use crate::generate_mapping;
@@ -147,21 +280,55 @@ mod models {
         pub is_active: bool,
         #[ormx(get_many)]
         pub user_id: KeyType,
+        pub fallback_reply: Option<String>,
+        pub back_trigger: Option<String>,
+        pub home_trigger: Option<String>,
+        pub help_trigger: Option<String>,
+        pub reprompt_after: Option<i32>,
         #[ormx(default)]
         pub created_at: DateTime<Utc>,
         #[ormx(default, set)]
         pub updated_at: DateTime<Utc>,
     }
 
+    #[derive(ormx::Patch)]
+    #[ormx(table_name = "bots", table = BotModel, id = "id")]
+    pub struct UpdateBotModel {
+        pub name: String,
+        pub is_active: bool,
+        pub fallback_reply: Option<String>,
+        pub back_trigger: Option<String>,
+        pub home_trigger: Option<String>,
+        pub help_trigger: Option<String>,
+        pub reprompt_after: Option<i32>,
+        pub updated_at: DateTime<Utc>,
+    }
+
     impl From<InsertBot> for InsertBotModel {
         fn from(val: InsertBot) -> Self {
             Self {
                 name: val.name,
                 is_active: val.is_active,
                 user_id: val.user_id.value(),
+                fallback_reply: val.fallback_reply,
+                back_trigger: val.back_trigger,
+                home_trigger: val.home_trigger,
+                help_trigger: val.help_trigger,
+                reprompt_after: val.reprompt_after,
             }
         }
     }
 
-    generate_mapping!(Bot, BotModel, 6);
+    #[derive(Clone, Debug, From, Into)]
+    pub struct BotChannelModel {
+        pub id: KeyType,
+        pub bot_id: KeyType,
+        pub channel_id: KeyType,
+        pub priority: i32,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    generate_mapping!(Bot, BotModel, 11);
+    generate_mapping!(BotChannel, BotChannelModel, 6);
 }
@@ -151,6 +151,8 @@ mod models {
         pub parent_menu_id: KeyType,
         #[ormx(get_many)]
         pub bot_id: KeyType,
+        pub hands_off: bool,
+        pub content_format: i32,
         #[ormx(default)]
         pub created_at: DateTime<Utc>,
         #[ormx(default, set)]
@@ -173,9 +175,11 @@ mod models {
                     .map(|v| v.value())
                     .unwrap_or(id),
                 bot_id: val.bot_id.value(),
+                hands_off: val.hands_off,
+                content_format: val.content_format.repr(),
             }
         }
     }
 
-    generate_mapping!(Menu, MenuModel, 10);
+    generate_mapping!(Menu, MenuModel, 12);
 }
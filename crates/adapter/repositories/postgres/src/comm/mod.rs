@@ -1,20 +1,30 @@
+mod bot_conversations;
 mod bots;
 mod menus;
 
-use kernel_repositories::comm::{BotsRepo, CommDataStore, MenusRepo};
+use kernel_repositories::comm::{
+    BotConversationsRepo,
+    BotsRepo,
+    CommDataStore,
+    MenusRepo,
+};
 
 use crate::database::SqlxPool;
 
 pub(crate) struct SqlxCommDataStore {
     bots: bots::SqlxBotsRepo,
     menus: menus::SqlxMenusRepo,
+    bot_conversations: bot_conversations::SqlxBotConversationsRepo,
 }
 
 impl SqlxCommDataStore {
     pub(crate) fn new(pool: SqlxPool) -> Self {
         Self {
             bots: bots::SqlxBotsRepo(pool.clone()),
-            menus: menus::SqlxMenusRepo(pool),
+            menus: menus::SqlxMenusRepo(pool.clone()),
+            bot_conversations: bot_conversations::SqlxBotConversationsRepo(
+                pool,
+            ),
         }
     }
 }
@@ -27,4 +37,8 @@ impl CommDataStore for SqlxCommDataStore {
     fn menus(&self) -> &dyn MenusRepo {
         &self.menus
     }
+
+    fn bot_conversations(&self) -> &dyn BotConversationsRepo {
+        &self.bot_conversations
+    }
 }
@@ -5,6 +5,7 @@ use database::SqlxPool;
 use kernel_repositories::{
     auth::AuthDataStore, comm::CommDataStore, link::LinkDataStore, *,
 };
+use kernel_services::crypto::secrets::SecretsService;
 use link::SqlxLinkDataStore;
 
 mod auth;
@@ -45,6 +46,7 @@ impl DataStore for SqlxDataStore {
 
 pub async fn create_datastore(
     conf: config::DataConfig,
+    secrets: Arc<dyn SecretsService>,
 ) -> anyhow::Result<Arc<dyn DataStore>> {
     tracing::debug!(
         "openning database connection to: {}",
@@ -58,7 +60,7 @@ pub async fn create_datastore(
 
     Ok(Arc::new(SqlxDataStore {
         auth: SqlxAuthDataStore::new(pool.clone()),
-        link: SqlxLinkDataStore::new(pool.clone()),
+        link: SqlxLinkDataStore::new(pool.clone(), secrets),
         comm: SqlxCommDataStore::new(pool.clone()),
         pool,
     }))
@@ -1,61 +1,148 @@
-use async_stream::stream;
+use std::{sync::Arc, time::Duration};
+
 use chrono::{DateTime, Utc};
-use futures::{stream::BoxStream, TryStreamExt};
+use futures::{stream::BoxStream, Stream, StreamExt};
 use kernel_entities::{
     entities::{auth::User, link::*},
     traits::Key,
 };
-use kernel_repositories::{error::RepoResult, link::*, traits::*};
-use ormx::{Delete, Patch, Table};
-use proc_macros::Repo;
-
-use crate::{
-    database::SqlxPool,
-    sqlx_ok,
-    sqlx_stream_ok,
-    sqlx_vec_ok,
-    util::error::map_sqlx_error,
+use kernel_repositories::{
+    error::{RepoError, RepoResult},
+    link::*,
+    traits::*,
 };
+use kernel_services::crypto::secrets::SecretsService;
+use ormx::{Delete, Patch, Table};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{database::SqlxPool, util::error::map_sqlx_error};
 
-#[derive(Repo)]
-#[repo(
-    table = "channels",
-    read(entity = "Channel", model = "models::ChannelModel"),
-    insert(entity = "InsertChannel", model = "models::InsertChannelModel")
-)]
-pub(crate) struct SqlxChannelsRepo(pub SqlxPool);
+/// Channels repo that keeps the api keys, webhook secrets and app secrets of
+/// channels encrypted at rest, and decrypts them transparently when reading
+/// channels.
+pub(crate) struct SqlxChannelsRepo(pub SqlxPool, pub Arc<dyn SecretsService>);
 
 #[async_trait::async_trait]
-impl ChannelsRepo for SqlxChannelsRepo {
-    fn stream_active(&self) -> BoxStream<'_, RepoResult<Channel>> {
-        sqlx_stream_ok!(sqlx::query_as!(
+impl Repo for SqlxChannelsRepo {
+    type Entity = Channel;
+
+    async fn get(&self, id: &Key<Channel>) -> RepoResult<Channel> {
+        let model = models::ChannelModel::get(self.0.get(), id.value())
+            .await
+            .map_err(map_sqlx_error)?;
+
+        self.open(model)
+    }
+
+    async fn get_paginated(
+        &self,
+        before: &DateTime<Utc>,
+        limit: usize,
+    ) -> RepoResult<Vec<Channel>> {
+        sqlx::query_as!(
             models::ChannelModel,
             r#"
             SELECT * FROM channels
-            WHERE is_active = TRUE AND
-                  COALESCE(valid_until, 'infinity') > now()
-            ORDER BY created_at
-            "#
+            WHERE created_at < $1
+            ORDER BY created_at DESC
+            LIMIT $2"#,
+            before,
+            limit as i64,
+        )
+        .fetch_all(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?
+        .into_iter()
+        .map(|model| self.open(model))
+        .collect()
+    }
+
+    async fn exists(&self, id: &Key<Channel>) -> RepoResult<bool> {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM channels WHERE id = $1)"#,
+            id.value_ref(),
+        )
+        .fetch_one(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(exists.unwrap_or(false))
+    }
+
+    async fn remove(&self, id: &Key<Channel>) -> RepoResult<()> {
+        models::ChannelModel::delete_row(self.0.get(), id.value())
+            .await
+            .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl InsertRepo<InsertChannel> for SqlxChannelsRepo {
+    async fn create(&self, insert: InsertChannel) -> RepoResult<Channel> {
+        let insert = InsertChannel {
+            api_key: self.seal(&insert.api_key)?,
+            webhook_secret: self.seal_opt(insert.webhook_secret.as_deref())?,
+            app_secret: self.seal_opt(insert.app_secret.as_deref())?,
+            ..insert
+        };
+
+        let model = models::ChannelModel::insert(
+            self.0.acquire().await?.as_mut(),
+            insert.into(),
+        )
+        .await
+        .map_err(map_sqlx_error)?;
+
+        self.open(model)
+    }
+}
+
+#[async_trait::async_trait]
+impl ChannelsRepo for SqlxChannelsRepo {
+    fn stream_active(&self) -> BoxStream<'_, RepoResult<Channel>> {
+        self.open_stream(
+            sqlx::query_as!(
+                models::ChannelModel,
+                r#"
+                SELECT * FROM channels
+                WHERE is_active = TRUE AND
+                      COALESCE(valid_until, 'infinity') > now() AND
+                      NOT EXISTS (
+                          SELECT 1 FROM channel_stops
+                          WHERE channel_id = channels.id
+                      )
+                ORDER BY created_at
+                "#
+            )
+            .fetch(self.0.get()),
         )
-        .fetch(self.0.get()))
     }
 
     fn stream_active_of(
         &self,
         user_id: Key<User>,
     ) -> BoxStream<'_, RepoResult<Channel>> {
-        sqlx_stream_ok!(sqlx::query_as!(
-            models::ChannelModel,
-            r#"
-                SELECT * FROM channels
-                WHERE user_id = $1 AND
-                      is_active = TRUE AND
-                      COALESCE(valid_until, 'infinity') > now()
-                ORDER BY created_at
-            "#,
-            user_id.value(),
+        self.open_stream(
+            sqlx::query_as!(
+                models::ChannelModel,
+                r#"
+                    SELECT * FROM channels
+                    WHERE user_id = $1 AND
+                          is_active = TRUE AND
+                          COALESCE(valid_until, 'infinity') > now() AND
+                          NOT EXISTS (
+                              SELECT 1 FROM channel_stops
+                              WHERE channel_id = channels.id
+                          )
+                    ORDER BY created_at
+                "#,
+                user_id.value(),
+            )
+            .fetch(self.0.get()),
         )
-        .fetch(self.0.get()))
     }
 
     async fn update(
@@ -65,7 +152,12 @@ impl ChannelsRepo for SqlxChannelsRepo {
     ) -> RepoResult<()> {
         models::UpdateChannelModel {
             name: model.name,
-            api_key: model.api_key,
+            api_key: self.seal(&model.api_key)?,
+            phone_number_id: model.phone_number_id,
+            webhook_secret: self.seal_opt(model.webhook_secret.as_deref())?,
+            app_secret: self.seal_opt(model.app_secret.as_deref())?,
+            use_webhook: model.use_webhook,
+            group_mode: model.group_mode.into(),
             valid_until: model.valid_until,
             is_active: model.is_active,
             updated_at: Utc::now(),
@@ -74,6 +166,219 @@ impl ChannelsRepo for SqlxChannelsRepo {
         .await
         .map_err(map_sqlx_error)
     }
+
+    async fn deactivate(&self, id: &Key<Channel>) -> RepoResult<()> {
+        sqlx::query!(
+            r#"UPDATE channels SET is_active = FALSE, updated_at = $1 WHERE id = $2"#,
+            Utc::now(),
+            id.value_ref()
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn set_stopped(
+        &self,
+        id: &Key<Channel>,
+        stopped: bool,
+    ) -> RepoResult<()> {
+        if stopped {
+            sqlx::query!(
+                r#"
+                INSERT INTO channel_stops (channel_id) VALUES ($1)
+                ON CONFLICT (channel_id) DO NOTHING
+                "#,
+                id.value_ref()
+            )
+            .execute(self.0.get())
+            .await
+            .map_err(map_sqlx_error)?;
+        } else {
+            sqlx::query!(
+                r#"DELETE FROM channel_stops WHERE channel_id = $1"#,
+                id.value_ref()
+            )
+            .execute(self.0.get())
+            .await
+            .map_err(map_sqlx_error)?;
+        }
+
+        Ok(())
+    }
+
+    async fn is_stopped(&self, id: &Key<Channel>) -> RepoResult<bool> {
+        let stopped = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM channel_stops WHERE channel_id = $1)"#,
+            id.value_ref(),
+        )
+        .fetch_one(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(stopped.unwrap_or(false))
+    }
+
+    async fn get_update_offset(
+        &self,
+        id: &Key<Channel>,
+    ) -> RepoResult<Option<i64>> {
+        sqlx::query_scalar!(
+            r#"SELECT update_offset FROM channel_update_offsets WHERE channel_id = $1"#,
+            id.value_ref()
+        )
+        .fetch_optional(self.0.get())
+        .await
+        .map_err(map_sqlx_error)
+    }
+
+    async fn set_update_offset(
+        &self,
+        id: &Key<Channel>,
+        offset: i64,
+    ) -> RepoResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO channel_update_offsets (channel_id, update_offset)
+            VALUES ($1, $2)
+            ON CONFLICT (channel_id)
+            DO UPDATE SET update_offset = $2, updated_at = NOW()
+            "#,
+            id.value_ref(),
+            offset
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn acquire_lease(
+        &self,
+        id: &Key<Channel>,
+        node_id: &str,
+        ttl: Duration,
+    ) -> RepoResult<bool> {
+        let owner = sqlx::query_scalar!(
+            r#"
+            INSERT INTO channel_leases (channel_id, node_id, expires_at)
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))
+            ON CONFLICT (channel_id)
+            DO UPDATE SET
+                node_id = EXCLUDED.node_id,
+                acquired_at = CASE
+                    WHEN channel_leases.node_id = EXCLUDED.node_id
+                    THEN channel_leases.acquired_at
+                    ELSE NOW()
+                END,
+                expires_at = EXCLUDED.expires_at
+            WHERE channel_leases.node_id = EXCLUDED.node_id
+               OR channel_leases.expires_at < NOW()
+            RETURNING node_id
+            "#,
+            id.value_ref(),
+            node_id,
+            ttl.as_secs_f64()
+        )
+        .fetch_optional(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(owner.is_some())
+    }
+
+    async fn release_lease(
+        &self,
+        id: &Key<Channel>,
+        node_id: &str,
+    ) -> RepoResult<()> {
+        sqlx::query!(
+            r#"DELETE FROM channel_leases WHERE channel_id = $1 AND node_id = $2"#,
+            id.value_ref(),
+            node_id
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn get_lease(
+        &self,
+        id: &Key<Channel>,
+    ) -> RepoResult<Option<ChannelLease>> {
+        sqlx::query_as!(
+            ChannelLease,
+            r#"
+            SELECT node_id, acquired_at, expires_at FROM channel_leases
+            WHERE channel_id = $1 AND expires_at > NOW()
+            "#,
+            id.value_ref()
+        )
+        .fetch_optional(self.0.get())
+        .await
+        .map_err(map_sqlx_error)
+    }
+
+    async fn reencrypt_secrets(&self) -> RepoResult<usize> {
+        let rows = sqlx::query!(
+            r#"SELECT id, api_key, webhook_secret, app_secret FROM channels"#
+        )
+        .fetch_all(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        let mut count = 0;
+
+        for row in rows {
+            let api_key = self.reseal(&row.id, "api key", &row.api_key);
+            let webhook_secret = row
+                .webhook_secret
+                .as_deref()
+                .map(|secret| self.reseal(&row.id, "webhook secret", secret));
+            let app_secret = row
+                .app_secret
+                .as_deref()
+                .map(|secret| self.reseal(&row.id, "app secret", secret));
+
+            if api_key == row.api_key
+                && webhook_secret == row.webhook_secret
+                && app_secret == row.app_secret
+            {
+                continue;
+            }
+
+            // channels that were updated meanwhile are already up to date
+            sqlx::query!(
+                r#"
+                UPDATE channels
+                SET api_key = $1, webhook_secret = $2, app_secret = $3
+                WHERE id = $4 AND
+                      api_key = $5 AND
+                      webhook_secret IS NOT DISTINCT FROM $6 AND
+                      app_secret IS NOT DISTINCT FROM $7
+                "#,
+                api_key,
+                webhook_secret,
+                app_secret,
+                row.id,
+                row.api_key,
+                row.webhook_secret,
+                row.app_secret
+            )
+            .execute(self.0.get())
+            .await
+            .map_err(map_sqlx_error)?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
 }
 
 #[async_trait::async_trait]
@@ -84,22 +389,24 @@ impl ChildRepo<User> for SqlxChannelsRepo {
         before: &DateTime<Utc>,
         limit: usize,
     ) -> RepoResult<Vec<Self::Entity>> {
-        sqlx_vec_ok!(
-            sqlx::query_as!(
-                models::ChannelModel,
-                r#"
-                SELECT * FROM channels
-                WHERE user_id = $1 AND created_at < $2
-                ORDER BY created_at
-                LIMIT $3
-                "#,
-                user_id.value_ref(),
-                before,
-                limit as i64
-            )
-            .fetch_all(self.0.get())
-            .await
+        sqlx::query_as!(
+            models::ChannelModel,
+            r#"
+            SELECT * FROM channels
+            WHERE user_id = $1 AND created_at < $2
+            ORDER BY created_at
+            LIMIT $3
+            "#,
+            user_id.value_ref(),
+            before,
+            limit as i64
         )
+        .fetch_all(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?
+        .into_iter()
+        .map(|model| self.open(model))
+        .collect()
     }
 
     async fn get_of(
@@ -107,16 +414,17 @@ impl ChildRepo<User> for SqlxChannelsRepo {
         user_id: &Key<User>,
         id: &Key<Self::Entity>,
     ) -> RepoResult<Self::Entity> {
-        sqlx_ok!(
-            sqlx::query_as!(
-                models::ChannelModel,
-                r#"SELECT * FROM channels WHERE id = $1 AND user_id = $2"#,
-                id.value_ref(),
-                user_id.value_ref()
-            )
-            .fetch_one(self.0.get())
-            .await
+        let model = sqlx::query_as!(
+            models::ChannelModel,
+            r#"SELECT * FROM channels WHERE id = $1 AND user_id = $2"#,
+            id.value_ref(),
+            user_id.value_ref()
         )
+        .fetch_one(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        self.open(model)
     }
 
     async fn remove_of(
@@ -164,6 +472,71 @@ impl StatsRepo<User> for SqlxChannelsRepo {
     }
 }
 
+impl SqlxChannelsRepo {
+    fn open(&self, model: models::ChannelModel) -> RepoResult<Channel> {
+        let mut channel: Channel = model.into();
+        channel.api_key = self.unseal(&channel.api_key)?;
+        channel.webhook_secret = channel
+            .webhook_secret
+            .map(|secret| self.unseal(&secret))
+            .transpose()?;
+        channel.app_secret = channel
+            .app_secret
+            .map(|secret| self.unseal(&secret))
+            .transpose()?;
+
+        Ok(channel)
+    }
+
+    fn open_stream<'a, S>(
+        &'a self,
+        cursor: S,
+    ) -> BoxStream<'a, RepoResult<Channel>>
+    where
+        S: Stream<Item = Result<models::ChannelModel, sqlx::Error>> + Send + 'a,
+    {
+        cursor
+            .map(move |model| self.open(model.map_err(map_sqlx_error)?))
+            .boxed()
+    }
+
+    // failing to seal or unseal a secret is not transient, e.g. when its
+    // master key is missing, so it is not reported as a `RepoError::Data`
+    fn seal(&self, plain: &str) -> RepoResult<String> {
+        self.1
+            .encrypt(plain)
+            .map_err(|err| RepoError::Serialization(err.to_string()))
+    }
+
+    fn seal_opt(&self, plain: Option<&str>) -> RepoResult<Option<String>> {
+        plain.map(|plain| self.seal(plain)).transpose()
+    }
+
+    fn unseal(&self, sealed: &str) -> RepoResult<String> {
+        self.1
+            .decrypt(sealed)
+            .map_err(|err| RepoError::Deserialization(err.to_string()))
+    }
+
+    /// Re-encrypts a secret of a channel with the current master key, if it
+    /// is not already. Secrets that cannot be decrypted, such as ones whose
+    /// key was removed, are kept as is, so that the others are still
+    /// re-encrypted.
+    fn reseal(&self, id: &Uuid, name: &str, sealed: &str) -> String {
+        if !self.1.is_stale(sealed) {
+            return sealed.to_owned();
+        }
+
+        match self.unseal(sealed).and_then(|plain| self.seal(&plain)) {
+            | Ok(resealed) => resealed,
+            | Err(err) => {
+                warn!("could not re-encrypt {name} of channel #{id}: {err}");
+                sealed.to_owned()
+            }
+        }
+    }
+}
+
 mod models {
     use chrono::{DateTime, Utc};
     use derive_more::{From, Into};
@@ -181,6 +554,11 @@ mod models {
         pub name: String,
         pub platform: i32,
         pub api_key: String,
+        pub phone_number_id: Option<String>,
+        pub webhook_secret: Option<String>,
+        pub app_secret: Option<String>,
+        pub use_webhook: bool,
+        pub group_mode: i32,
         pub valid_until: Option<DateTime<Utc>>,
         #[ormx(set)]
         pub is_active: bool,
@@ -198,6 +576,11 @@ mod models {
     pub struct UpdateChannelModel {
         pub name: String,
         pub api_key: String,
+        pub phone_number_id: Option<String>,
+        pub webhook_secret: Option<String>,
+        pub app_secret: Option<String>,
+        pub use_webhook: bool,
+        pub group_mode: i32,
         pub valid_until: Option<DateTime<Utc>>,
         pub is_active: bool,
         pub updated_at: DateTime<Utc>,
@@ -210,11 +593,16 @@ mod models {
                 name: val.name,
                 platform: val.platform.into(),
                 api_key: val.api_key,
+                phone_number_id: val.phone_number_id,
+                webhook_secret: val.webhook_secret,
+                app_secret: val.app_secret,
+                use_webhook: val.use_webhook,
+                group_mode: val.group_mode.into(),
                 valid_until: val.valid_until,
                 is_active: val.is_active,
             }
         }
     }
 
-    generate_mapping!(Channel, ChannelModel, 10);
+    generate_mapping!(Channel, ChannelModel, 15);
 }
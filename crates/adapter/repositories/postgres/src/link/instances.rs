@@ -40,21 +40,145 @@ impl InstancesRepo for SqlxInstancesRepo {
     async fn get_by_platform_identifier(
         &self,
         channel_id: &Key<Channel>,
+        group_id: Option<i64>,
         identifier: i64,
     ) -> RepoResult<Instance> {
         sqlx_ok!(
             sqlx::query_as!(
                 models::InstanceModel,
                 r#"SELECT * FROM instances
-                   WHERE channel_id = $1 AND platform_identifier = $2"#,
+                   WHERE channel_id = $1 AND platform_identifier = $2 AND
+                         platform_group_id IS NOT DISTINCT FROM $3"#,
                 channel_id.value_ref(),
-                identifier
+                identifier,
+                group_id,
             )
             .fetch_one(self.0.get())
             .await
         )
     }
 
+    async fn get_group_chat(
+        &self,
+        channel_id: &Key<Channel>,
+        group_id: i64,
+    ) -> RepoResult<Key<Chat>> {
+        let chat_id = sqlx::query_scalar!(
+            r#"
+            SELECT chat_id FROM group_chats
+            WHERE channel_id = $1 AND platform_group_id = $2"#,
+            channel_id.value_ref(),
+            group_id,
+        )
+        .fetch_one(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(Key::new(chat_id))
+    }
+
+    async fn set_group_chat(
+        &self,
+        channel_id: &Key<Channel>,
+        group_id: i64,
+        chat_id: &Key<Chat>,
+    ) -> RepoResult<Key<Chat>> {
+        // the no-op update makes the existing row returned on conflicts
+        let chat_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO group_chats (channel_id, platform_group_id, chat_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (channel_id, platform_group_id)
+            DO UPDATE SET chat_id = group_chats.chat_id
+            RETURNING chat_id
+            "#,
+            channel_id.value_ref(),
+            group_id,
+            chat_id.value_ref(),
+        )
+        .fetch_one(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(Key::new(chat_id))
+    }
+
+    async fn get_count_of(
+        &self,
+        channel_id: &Key<Channel>,
+    ) -> RepoResult<usize> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(id) FROM instances
+            WHERE channel_id = $1"#,
+            channel_id.value_ref(),
+        )
+        .fetch_one(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(count.unwrap_or(0) as usize)
+    }
+
+    async fn create_within_quota(
+        &self,
+        model: InsertInstance,
+        max_instances: i64,
+    ) -> RepoResult<Option<Instance>> {
+        let mut tx = self.0.get().begin().await.map_err(map_sqlx_error)?;
+
+        // locking the channel makes concurrent creations wait for each other,
+        // so that each of them counts the instances created by the others
+        sqlx::query!(
+            "SELECT id FROM channels WHERE id = $1 FOR UPDATE",
+            model.channel_id.value_ref(),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(id) FROM instances
+            WHERE channel_id = $1"#,
+            model.channel_id.value_ref(),
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        if count.unwrap_or(0) >= max_instances {
+            return Ok(None);
+        }
+
+        let instance = sqlx::query_as!(
+            models::InstanceModel,
+            r#"
+            INSERT INTO instances (
+                platform_identifier, platform_group_id, username,
+                display_name, phone_number, last_active, chat_id, channel_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+            model.platform_identifier,
+            model.platform_group_id,
+            model.username,
+            model.display_name,
+            model.phone_number,
+            model.last_active,
+            model.chat_id.value_ref(),
+            model.channel_id.value_ref(),
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        tx.commit().await.map_err(map_sqlx_error)?;
+
+        Ok(Some(instance.into()))
+    }
+
     async fn get_of_user(
         &self,
         user_id: &Key<User>,
@@ -115,6 +239,34 @@ impl InstancesRepo for SqlxInstancesRepo {
         .await
         .map_err(map_sqlx_error)
     }
+
+    async fn refresh(
+        &self,
+        id: &Key<Instance>,
+        model: RefreshInstance,
+    ) -> RepoResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE instances SET
+                username = COALESCE($1, username),
+                display_name = COALESCE($2, display_name),
+                phone_number = COALESCE($3, phone_number),
+                last_active = GREATEST(last_active, $4),
+                updated_at = $5
+            WHERE id = $6"#,
+            model.username,
+            model.display_name,
+            model.phone_number,
+            model.last_active,
+            Utc::now(),
+            id.value_ref(),
+        )
+        .execute(self.0.get())
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
 }
 
 mod models {
@@ -132,13 +284,14 @@ mod models {
         #[ormx(default)]
         pub id: KeyType,
         pub platform_identifier: i64,
-        #[ormx(default, set)]
+        pub platform_group_id: Option<i64>,
+        #[ormx(set)]
         pub username: Option<String>,
-        #[ormx(default, set)]
+        #[ormx(set)]
         pub display_name: Option<String>,
-        #[ormx(default, set)]
+        #[ormx(set)]
         pub phone_number: Option<String>,
-        #[ormx(default, set)]
+        #[ormx(set)]
         pub last_active: Option<DateTime<Utc>>,
         #[ormx(get_many = get_by_chat)]
         pub chat_id: KeyType,
@@ -162,11 +315,16 @@ mod models {
         fn from(val: InsertInstance) -> Self {
             InsertInstanceModel {
                 platform_identifier: val.platform_identifier,
+                platform_group_id: val.platform_group_id,
+                username: val.username,
+                display_name: val.display_name,
+                phone_number: val.phone_number,
+                last_active: val.last_active,
                 chat_id: val.chat_id.value(),
                 channel_id: val.channel_id.value(),
             }
         }
     }
 
-    generate_mapping!(Instance, InstanceModel, 10);
+    generate_mapping!(Instance, InstanceModel, 11);
 }
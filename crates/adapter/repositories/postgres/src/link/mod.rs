@@ -1,7 +1,10 @@
 mod channels;
 mod instances;
 
+use std::sync::Arc;
+
 use kernel_repositories::link::*;
+use kernel_services::crypto::secrets::SecretsService;
 
 use crate::database::SqlxPool;
 
@@ -11,9 +14,12 @@ pub(crate) struct SqlxLinkDataStore {
 }
 
 impl SqlxLinkDataStore {
-    pub(crate) fn new(pool: SqlxPool) -> Self {
+    pub(crate) fn new(
+        pool: SqlxPool,
+        secrets: Arc<dyn SecretsService>,
+    ) -> Self {
         Self {
-            channels: channels::SqlxChannelsRepo(pool.clone()),
+            channels: channels::SqlxChannelsRepo(pool.clone(), secrets),
             instances: instances::SqlxInstancesRepo(pool),
         }
     }
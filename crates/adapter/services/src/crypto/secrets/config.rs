@@ -0,0 +1,14 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use validator::Validate;
+
+pub const SECRETS_CONFIG_SECTION: &str = "secrets";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SecretsConfig {
+    #[validate(length(min = 1))]
+    pub current_key: String,
+    #[validate(length(min = 1))]
+    pub keys: HashMap<String, String>,
+}
@@ -0,0 +1,276 @@
+mod config;
+
+use std::{collections::HashMap, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm,
+    Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use kernel_services::{
+    config::ConfigService,
+    crypto::secrets::SecretsService,
+    error::{AppResult, CryptoError},
+    Service,
+};
+
+pub use self::config::*;
+
+const SEALED_PREFIX: &str = "aes256gcm";
+const NONCE_SIZE: usize = 12;
+
+/// Encrypts secrets with AES-256-GCM, as
+/// `aes256gcm:<key id>:<base64 of nonce + ciphertext>`.
+///
+/// The id of the master key is kept along with each secret, so that keys can
+/// be rotated by adding a new key and making it the current one, while the
+/// old keys are kept until all secrets are re-encrypted.
+pub struct AesGcmSecretsService {
+    current_key: String,
+    keys: HashMap<String, Aes256Gcm>,
+}
+
+impl AesGcmSecretsService {
+    pub fn create<C: ConfigService>(config: Arc<C>) -> AppResult<Self> {
+        let conf: SecretsConfig = config.get_section(SECRETS_CONFIG_SECTION)?;
+
+        let keys = conf
+            .keys
+            .iter()
+            .map(|(id, key)| Ok((id.clone(), cipher_of(id, key)?)))
+            .collect::<AppResult<HashMap<_, _>>>()?;
+
+        if !keys.contains_key(&conf.current_key) {
+            return Err(CryptoError::UnknownKey(conf.current_key).into());
+        }
+
+        Ok(Self {
+            current_key: conf.current_key,
+            keys,
+        })
+    }
+}
+
+impl SecretsService for AesGcmSecretsService {
+    fn encrypt(&self, plain: &str) -> AppResult<String> {
+        let cipher = &self.keys[&self.current_key];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plain.as_bytes())
+            .map_err(|err| CryptoError::Cipher(err.to_string()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+
+        Ok(format!(
+            "{SEALED_PREFIX}:{}:{}",
+            self.current_key,
+            STANDARD.encode(payload)
+        ))
+    }
+
+    fn decrypt(&self, sealed: &str) -> AppResult<String> {
+        let Some((key_id, payload)) = parse_sealed(sealed) else {
+            return Ok(sealed.to_owned());
+        };
+
+        let Some(cipher) = self.keys.get(key_id) else {
+            return Err(CryptoError::UnknownKey(key_id.to_owned()).into());
+        };
+
+        let payload = STANDARD
+            .decode(payload)
+            .map_err(|err| CryptoError::Encoding(err.to_string()))?;
+
+        if payload.len() < NONCE_SIZE {
+            return Err(CryptoError::InputTooShort.into());
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_SIZE);
+        let plain = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|err| CryptoError::Cipher(err.to_string()))?;
+
+        Ok(String::from_utf8(plain)
+            .map_err(|err| CryptoError::Encoding(err.to_string()))?)
+    }
+
+    fn is_stale(&self, sealed: &str) -> bool {
+        parse_sealed(sealed)
+            .map_or(true, |(key_id, _)| key_id != self.current_key)
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for AesGcmSecretsService {
+    async fn initialize(self: Arc<Self>) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+fn parse_sealed(sealed: &str) -> Option<(&str, &str)> {
+    sealed
+        .strip_prefix(SEALED_PREFIX)?
+        .strip_prefix(':')?
+        .split_once(':')
+}
+
+fn cipher_of(id: &str, key: &str) -> AppResult<Aes256Gcm> {
+    if id.contains(':') {
+        return Err(CryptoError::Format(format!(
+            "master key id `{id}` must not contain `:`"
+        ))
+        .into());
+    }
+
+    let key = STANDARD.decode(key).map_err(|err| {
+        CryptoError::Encoding(format!("master key `{id}`: {err}"))
+    })?;
+
+    // the sample configurations come with an all-zero placeholder key, which
+    // is only tolerated by debug builds
+    if key.iter().all(|b| *b == 0) {
+        if !cfg!(debug_assertions) {
+            return Err(CryptoError::Format(format!(
+                "master key `{id}` is the placeholder key, replace it with a \
+                 generated key"
+            ))
+            .into());
+        }
+
+        warn!(
+            "master key `{id}` is the placeholder key, do not use it in \
+             production"
+        );
+    }
+
+    Ok(Aes256Gcm::new_from_slice(&key).map_err(|_| {
+        CryptoError::Format(format!("master key `{id}` must be 32 bytes long"))
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel_services::error::AppError;
+
+    use super::*;
+
+    const OLD_KEY: (&str, [u8; 32]) = ("2023-01", [1; 32]);
+    const NEW_KEY: (&str, [u8; 32]) = ("2023-06", [2; 32]);
+
+    fn service_of(
+        current_key: &str,
+        keys: &[(&str, [u8; 32])],
+    ) -> AesGcmSecretsService {
+        let keys = keys
+            .iter()
+            .map(|(id, key)| {
+                (
+                    id.to_string(),
+                    cipher_of(id, &STANDARD.encode(key)).unwrap(),
+                )
+            })
+            .collect();
+
+        AesGcmSecretsService {
+            current_key: current_key.to_owned(),
+            keys,
+        }
+    }
+
+    #[test]
+    fn decrypts_encrypted_secrets() {
+        let secrets = service_of(OLD_KEY.0, &[OLD_KEY]);
+
+        let sealed = secrets.encrypt("123456:ABC-DEF").unwrap();
+
+        assert!(sealed.starts_with("aes256gcm:2023-01:"), "{sealed}");
+        assert!(!sealed.contains("123456:ABC-DEF"));
+        assert_eq!(secrets.decrypt(&sealed).unwrap(), "123456:ABC-DEF");
+    }
+
+    #[test]
+    fn encrypts_with_random_nonces() {
+        let secrets = service_of(OLD_KEY.0, &[OLD_KEY]);
+
+        let first = secrets.encrypt("secret").unwrap();
+        let second = secrets.encrypt("secret").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn reads_plain_secrets_as_is() {
+        let secrets = service_of(OLD_KEY.0, &[OLD_KEY]);
+
+        // e.g. secrets stored before encryption was introduced
+        assert_eq!(secrets.decrypt("plain").unwrap(), "plain");
+        assert!(secrets.is_stale("plain"));
+    }
+
+    #[test]
+    fn decrypts_secrets_of_rotated_keys() {
+        let old = service_of(OLD_KEY.0, &[OLD_KEY]);
+        let sealed = old.encrypt("secret").unwrap();
+
+        let rotated = service_of(NEW_KEY.0, &[OLD_KEY, NEW_KEY]);
+        assert_eq!(rotated.decrypt(&sealed).unwrap(), "secret");
+
+        let resealed = rotated.encrypt("secret").unwrap();
+        assert!(resealed.starts_with("aes256gcm:2023-06:"), "{resealed}");
+        assert_eq!(rotated.decrypt(&resealed).unwrap(), "secret");
+    }
+
+    #[test]
+    fn only_secrets_of_other_keys_are_stale() {
+        let old = service_of(OLD_KEY.0, &[OLD_KEY]);
+        let rotated = service_of(NEW_KEY.0, &[OLD_KEY, NEW_KEY]);
+
+        let sealed = old.encrypt("secret").unwrap();
+        let resealed = rotated.encrypt("secret").unwrap();
+
+        assert!(!old.is_stale(&sealed));
+        assert!(rotated.is_stale(&sealed));
+        assert!(!rotated.is_stale(&resealed));
+    }
+
+    #[test]
+    fn fails_decrypting_secrets_of_removed_keys() {
+        let old = service_of(OLD_KEY.0, &[OLD_KEY]);
+        let sealed = old.encrypt("secret").unwrap();
+
+        let rotated = service_of(NEW_KEY.0, &[NEW_KEY]);
+        let err = rotated.decrypt(&sealed).unwrap_err();
+
+        let AppError::Crypto(CryptoError::UnknownKey(id)) = &err else {
+            panic!("expected an unknown key error, got {err}");
+        };
+
+        assert_eq!(id, OLD_KEY.0);
+    }
+
+    #[test]
+    fn fails_decrypting_tampered_secrets() {
+        let secrets = service_of(OLD_KEY.0, &[OLD_KEY]);
+        let sealed = secrets.encrypt("secret").unwrap();
+
+        let (prefix, payload) = sealed.rsplit_once(':').unwrap();
+        let mut payload = STANDARD.decode(payload).unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let tampered = format!("{prefix}:{}", STANDARD.encode(payload));
+
+        assert!(secrets.decrypt(&tampered).is_err());
+        assert!(secrets.decrypt("aes256gcm:2023-01:AAAA").is_err());
+        assert!(secrets.decrypt("aes256gcm:2023-01:not base64").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_master_keys() {
+        let key = STANDARD.encode([1u8; 32]);
+
+        assert!(cipher_of("2023:01", &key).is_err());
+        assert!(cipher_of("2023-01", &STANDARD.encode([1u8; 16])).is_err());
+        assert!(cipher_of("2023-01", "not base64").is_err());
+    }
+}
@@ -5,3 +5,4 @@ pub mod config;
 pub mod crypto;
 pub mod entropy;
 pub mod link;
+pub mod storage;
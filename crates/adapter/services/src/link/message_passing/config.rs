@@ -7,6 +7,19 @@ use validator::Validate;
 pub(super) const MESSAGE_QUEUE_CONFIG_SECTION: &str = "message_queue";
 
 into_fn!(default_require_ack: const bool => true);
+into_fn!(default_max_attempts: const u32 => 5);
+into_fn!(default_initial_backoff_ms: const u64 => 1000);
+into_fn!(default_max_backoff_ms: const u64 => 60 * 1000);
+into_fn!(default_backoff_multiplier: const u32 => 2);
+
+#[derive(Debug, Deserialize, Validate)]
+pub(super) struct MessagePassingConfig {
+    #[validate(custom = "supported_message_queue_protocol")]
+    pub protocol: String,
+    #[validate]
+    #[serde(default)]
+    pub retry: RetryConfig,
+}
 
 #[derive(Debug, Deserialize, Validate)]
 pub(super) struct MessageQueueConfig {
@@ -30,6 +43,46 @@ pub(super) struct PoolConfig {
     pub max_lifetime_ms: Option<u64>,
 }
 
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub(super) struct RetryConfig {
+    #[validate(range(min = 1, max = 64))]
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[validate(range(min = 1))]
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[validate(range(min = 1))]
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[validate(range(min = 1, max = 16))]
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: u32,
+}
+
+impl RetryConfig {
+    /// Delay before redelivering a message that has already been attempted
+    /// `attempts` times.
+    pub(super) fn backoff_of(&self, attempts: u32) -> u64 {
+        let factor = (self.backoff_multiplier as u64)
+            .saturating_pow(attempts.saturating_sub(1));
+
+        self.initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            backoff_multiplier: default_backoff_multiplier(),
+        }
+    }
+}
+
 impl MessageQueueConfig {
     pub(super) fn get_connection_string(&self) -> Result<String> {
         self.do_get_connection_string::<false>()
@@ -0,0 +1,89 @@
+mod topic;
+
+use std::{collections::HashMap, sync::Arc};
+
+use kernel_services::{
+    error::AppResult,
+    link::message_passing::{MessagePassingService, TopicReader, TopicWriter},
+    Service,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::RwLock;
+
+use self::topic::{InMemoryTopic, InMemoryTopicWrapper};
+use crate::link::message_passing::config::RetryConfig;
+
+/// A message passing service that keeps its topics in the process memory.
+/// Messages do not survive restarts, and are only shared between the
+/// components of a single node.
+#[derive(Default)]
+pub struct InMemoryMessagePassingService {
+    retry: RetryConfig,
+    topics: RwLock<HashMap<String, Arc<InMemoryTopic>>>,
+}
+
+#[async_trait::async_trait]
+impl MessagePassingService for InMemoryMessagePassingService {
+    async fn get_topic_writer<T>(
+        &self,
+        name: &str,
+    ) -> AppResult<Arc<dyn TopicWriter<T>>>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        Ok(self.get_topic_wrapper(name).await)
+    }
+
+    async fn get_topic_reader<T>(
+        &self,
+        name: &str,
+    ) -> AppResult<Arc<dyn TopicReader<T>>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        Ok(self.get_topic_wrapper(name).await)
+    }
+}
+
+impl InMemoryMessagePassingService {
+    pub(super) fn new(retry: RetryConfig) -> Self {
+        Self {
+            retry,
+            topics: Default::default(),
+        }
+    }
+
+    async fn get_topic_wrapper<T>(
+        &self,
+        name: &str,
+    ) -> Arc<InMemoryTopicWrapper<T>>
+    where
+        T: Send + Sync,
+    {
+        if let Some(topic) = self.topics.read().await.get(name) {
+            return InMemoryTopicWrapper::new_arc(topic.clone());
+        };
+
+        let topic = self
+            .topics
+            .write()
+            .await
+            .entry(name.to_owned())
+            .or_insert_with(|| {
+                Arc::new(InMemoryTopic::new(
+                    name.to_owned(),
+                    self.retry.clone(),
+                ))
+            })
+            .clone();
+
+        InMemoryTopicWrapper::new_arc(topic)
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for InMemoryMessagePassingService {
+    async fn initialize(self: Arc<Self>) -> AppResult<()> {
+        Ok(())
+    }
+}
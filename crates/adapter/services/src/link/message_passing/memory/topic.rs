@@ -0,0 +1,720 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::{
+    stream::{self, BoxStream},
+    Stream,
+    StreamExt,
+};
+use kernel_services::{
+    error::AppResult,
+    link::message_passing::{
+        DeadLetter,
+        MessageConfirmation,
+        ScopedTopicReader,
+        ScopedTopicWriter,
+        TopicReader,
+        TopicWriter,
+    },
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Mutex,
+    RwLock,
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::link::message_passing::{
+    config::RetryConfig,
+    util::{deserialize, map_ipc_error, map_params_error},
+};
+
+#[derive(Clone, Debug)]
+struct InMemoryMessage {
+    data: Arc<[u8]>,
+    attempts: u32,
+    dead_lettered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct InMemoryQueue {
+    tx: UnboundedSender<InMemoryMessage>,
+    rx: Mutex<UnboundedReceiver<InMemoryMessage>>,
+    dead_letters: Mutex<VecDeque<InMemoryMessage>>,
+}
+
+#[derive(Debug)]
+pub(super) struct InMemoryTopic {
+    name: String,
+    retry: RetryConfig,
+    queues: RwLock<HashMap<String, Arc<InMemoryQueue>>>,
+    mirrors: RwLock<Vec<(String, UnboundedSender<InMemoryMessage>)>>,
+}
+
+#[derive(Debug)]
+pub(super) struct InMemoryTopicWrapper<T> {
+    inner: Arc<InMemoryTopic>,
+    _phantom: PhantomData<T>,
+}
+
+#[derive(Debug)]
+pub(super) struct ScopedInMemoryTopicWrapper<T> {
+    key: String,
+    inner: Arc<InMemoryTopic>,
+    _phantom: PhantomData<T>,
+}
+
+impl InMemoryTopic {
+    pub(super) fn new(name: String, retry: RetryConfig) -> Self {
+        Self {
+            name,
+            retry,
+            queues: Default::default(),
+            mirrors: Default::default(),
+        }
+    }
+
+    async fn do_publish<T: Serialize>(
+        &self,
+        key: &str,
+        body: &T,
+    ) -> AppResult<()> {
+        let buf = rmp_serde::to_vec(body).map_err(map_params_error)?;
+        let message = InMemoryMessage::new(buf.into());
+
+        let mut routed = false;
+
+        for (pattern, queue) in self.queues.read().await.iter() {
+            if matches_key(pattern, key) {
+                queue.push(message.clone())?;
+                routed = true;
+            }
+        }
+
+        // like on exchanges, messages that match no queue are dropped, as
+        // keeping them in a queue nobody reads would grow without bounds
+        if !routed {
+            trace!("dropping unroutable message `{}.{key}`", self.name);
+        }
+
+        self.do_broadcast(key, message).await;
+
+        Ok(())
+    }
+
+    async fn do_broadcast(&self, key: &str, message: InMemoryMessage) {
+        let mut mirrors = self.mirrors.write().await;
+        mirrors.retain(|(_, tx)| !tx.is_closed());
+
+        for (pattern, tx) in mirrors.iter() {
+            if matches_key(pattern, key) {
+                tx.send(message.clone()).ok();
+            }
+        }
+    }
+
+    async fn do_subscribe(
+        &self,
+        key: &str,
+    ) -> impl Stream<Item = (Arc<InMemoryQueue>, InMemoryMessage)> {
+        let queue = self.ensure_queue_created(key).await;
+
+        stream::unfold(queue, |queue| async move {
+            let message = queue.rx.lock().await.recv().await?;
+
+            Some(((queue.clone(), message), queue))
+        })
+    }
+
+    async fn do_mirror(
+        &self,
+        key: &str,
+    ) -> impl Stream<Item = InMemoryMessage> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        self.mirrors.write().await.push((key.to_owned(), tx));
+
+        UnboundedReceiverStream::new(rx)
+    }
+
+    async fn do_list_dead_letters<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>> {
+        let queue = self.ensure_queue_created(key).await;
+        let dead_letters = queue.dead_letters.lock().await;
+
+        Ok(dead_letters
+            .iter()
+            .take(limit)
+            .filter_map(|m| match deserialize::<T>(&m.data) {
+                | Ok(body) => Some(DeadLetter {
+                    body,
+                    attempts: m.attempts,
+                    dead_lettered_at: m.dead_lettered_at,
+                }),
+                | Err(err) => {
+                    warn!("skipping malformed dead letter: {err}");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn do_replay_dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<usize> {
+        let queue = self.ensure_queue_created(key).await;
+        let mut dead_letters = queue.dead_letters.lock().await;
+
+        let count = limit.min(dead_letters.len());
+
+        for message in dead_letters.drain(..count) {
+            queue.push(InMemoryMessage::new(message.data))?;
+        }
+
+        Ok(count)
+    }
+
+    async fn do_purge_dead_letters(&self, key: &str) -> AppResult<usize> {
+        let queue = self.ensure_queue_created(key).await;
+        let mut dead_letters = queue.dead_letters.lock().await;
+
+        let count = dead_letters.len();
+        dead_letters.clear();
+
+        Ok(count)
+    }
+
+    async fn ensure_queue_created(&self, key: &str) -> Arc<InMemoryQueue> {
+        if let Some(queue) = self.queues.read().await.get(key) {
+            return queue.clone();
+        }
+
+        self.queues
+            .write()
+            .await
+            .entry(key.to_owned())
+            .or_insert_with(|| {
+                debug!("creating in-memory queue `{}.{key}`", self.name);
+
+                Arc::new(InMemoryQueue::new())
+            })
+            .clone()
+    }
+}
+
+impl InMemoryQueue {
+    fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        Self {
+            tx,
+            rx: Mutex::new(rx),
+            dead_letters: Default::default(),
+        }
+    }
+
+    fn push(&self, message: InMemoryMessage) -> AppResult<()> {
+        self.tx.send(message).map_err(map_ipc_error)
+    }
+}
+
+impl InMemoryMessage {
+    fn new(data: Arc<[u8]>) -> Self {
+        Self {
+            data,
+            attempts: 0,
+            dead_lettered_at: None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> TopicWriter<T> for InMemoryTopicWrapper<T>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    async fn publish(&self, key: &str, body: &T) -> AppResult<()> {
+        self.inner.do_publish(key, body).await
+    }
+
+    async fn publish_confirmed(&self, key: &str, body: &T) -> AppResult<()> {
+        self.inner.do_publish(key, body).await
+    }
+
+    async fn broadcast(&self, key: &str, body: &T) -> AppResult<()> {
+        let buf = rmp_serde::to_vec(body).map_err(map_params_error)?;
+
+        self.inner
+            .do_broadcast(key, InMemoryMessage::new(buf.into()))
+            .await;
+
+        Ok(())
+    }
+
+    fn scoped(&self, key: &str) -> Arc<dyn ScopedTopicWriter<T>> {
+        Arc::new(ScopedInMemoryTopicWrapper {
+            key: key.to_owned(),
+            inner: self.inner.clone(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ScopedTopicWriter<T> for ScopedInMemoryTopicWrapper<T>
+where
+    T: Serialize + Send + Sync,
+{
+    async fn publish(&self, body: &T) -> AppResult<()> {
+        self.inner.do_publish(&self.key, body).await
+    }
+
+    async fn publish_confirmed(&self, body: &T) -> AppResult<()> {
+        self.inner.do_publish(&self.key, body).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> TopicReader<T> for InMemoryTopicWrapper<T>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    async fn subscribe(
+        &self,
+        key: &str,
+    ) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_subscribe(key)
+            .await
+            .map(|(_, m)| deserialize::<T>(&m.data))
+            .boxed())
+    }
+
+    async fn subscribe_manual(
+        &self,
+        key: &str,
+    ) -> AppResult<BoxStream<'_, AppResult<(T, Arc<dyn MessageConfirmation>)>>>
+    {
+        Ok(self
+            .inner
+            .do_subscribe(key)
+            .await
+            .map(|(queue, m)| {
+                Ok((
+                    deserialize::<T>(&m.data)?,
+                    InMemoryMessageConfirmation::new_arc(&self.inner, queue, m),
+                ))
+            })
+            .boxed())
+    }
+
+    async fn mirror(
+        &self,
+        key: &str,
+    ) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_mirror(key)
+            .await
+            .map(|m| deserialize::<T>(&m.data))
+            .boxed())
+    }
+
+    async fn dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>> {
+        self.inner.do_list_dead_letters(key, limit).await
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<usize> {
+        self.inner.do_replay_dead_letters(key, limit).await
+    }
+
+    async fn purge_dead_letters(&self, key: &str) -> AppResult<usize> {
+        self.inner.do_purge_dead_letters(key).await
+    }
+
+    fn scoped(&self, key: &str) -> Arc<dyn ScopedTopicReader<T>> {
+        Arc::new(ScopedInMemoryTopicWrapper {
+            key: key.to_owned(),
+            inner: self.inner.clone(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ScopedTopicReader<T> for ScopedInMemoryTopicWrapper<T>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    async fn subscribe(&self) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_subscribe(&self.key)
+            .await
+            .map(|(_, m)| deserialize::<T>(&m.data))
+            .boxed())
+    }
+
+    async fn subscribe_manual(
+        &self,
+    ) -> AppResult<BoxStream<'_, AppResult<(T, Arc<dyn MessageConfirmation>)>>>
+    {
+        Ok(self
+            .inner
+            .do_subscribe(&self.key)
+            .await
+            .map(|(queue, m)| {
+                Ok((
+                    deserialize::<T>(&m.data)?,
+                    InMemoryMessageConfirmation::new_arc(&self.inner, queue, m),
+                ))
+            })
+            .boxed())
+    }
+
+    async fn mirror(&self) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_mirror(&self.key)
+            .await
+            .map(|m| deserialize::<T>(&m.data))
+            .boxed())
+    }
+
+    async fn dead_letters(
+        &self,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>> {
+        self.inner.do_list_dead_letters(&self.key, limit).await
+    }
+
+    async fn replay_dead_letters(&self, limit: usize) -> AppResult<usize> {
+        self.inner.do_replay_dead_letters(&self.key, limit).await
+    }
+
+    async fn purge_dead_letters(&self) -> AppResult<usize> {
+        self.inner.do_purge_dead_letters(&self.key).await
+    }
+}
+
+impl<T> InMemoryTopicWrapper<T>
+where
+    T: Send + Sync,
+{
+    pub(super) fn new_arc(value: Arc<InMemoryTopic>) -> Arc<Self> {
+        Arc::new(InMemoryTopicWrapper {
+            inner: value,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+struct InMemoryMessageConfirmation {
+    topic: Arc<InMemoryTopic>,
+    queue: Arc<InMemoryQueue>,
+    message: InMemoryMessage,
+}
+
+impl InMemoryMessageConfirmation {
+    fn new_arc(
+        topic: &Arc<InMemoryTopic>,
+        queue: Arc<InMemoryQueue>,
+        message: InMemoryMessage,
+    ) -> Arc<dyn MessageConfirmation> {
+        Arc::new(Self {
+            topic: topic.clone(),
+            queue,
+            message,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageConfirmation for InMemoryMessageConfirmation {
+    async fn ack(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn nack(&self, requeue: bool) -> AppResult<()> {
+        let attempts = self.message.attempts + 1;
+        let message = InMemoryMessage {
+            attempts,
+            ..self.message.clone()
+        };
+
+        if requeue && attempts < self.topic.retry.max_attempts {
+            let backoff = self.topic.retry.backoff_of(attempts);
+            let queue = self.queue.clone();
+
+            debug!(
+                "retrying message of `{}` in {backoff}ms (#{attempts})",
+                self.topic.name
+            );
+
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+
+                if let Err(err) = queue.push(message) {
+                    error!("could not requeue in-memory message: {err:#?}");
+                }
+            });
+        } else {
+            warn!(
+                "dead-lettering message of `{}` after {attempts} attempts",
+                self.topic.name
+            );
+
+            self.queue
+                .dead_letters
+                .lock()
+                .await
+                .push_back(InMemoryMessage {
+                    dead_lettered_at: Some(Utc::now()),
+                    ..message
+                });
+        }
+
+        Ok(())
+    }
+
+    fn is_last_attempt(&self) -> bool {
+        self.message.attempts + 1 >= self.topic.retry.max_attempts
+    }
+}
+
+/// Matches routing keys the way AMQP topic exchanges do: keys are made of
+/// dot-delimited words, `*` matches exactly one word, and `#` matches zero or
+/// more words.
+fn matches_key(pattern: &str, key: &str) -> bool {
+    fn matches(pattern: &[&str], key: &[&str]) -> bool {
+        match (pattern.split_first(), key.split_first()) {
+            | (None, None) => true,
+            | (Some((&"#", rest)), _) => {
+                matches(rest, key)
+                    || (!key.is_empty() && matches(pattern, &key[1..]))
+            }
+            | (Some((&"*", rest)), Some((_, key_rest))) => {
+                matches(rest, key_rest)
+            }
+            | (Some((word, rest)), Some((key_word, key_rest))) => {
+                word == key_word && matches(rest, key_rest)
+            }
+            | _ => false,
+        }
+    }
+
+    let pattern = pattern.split('.').collect::<Vec<_>>();
+    let key = key.split('.').collect::<Vec<_>>();
+
+    matches(&pattern, &key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIET_PERIOD: Duration = Duration::from_millis(50);
+
+    fn topic_of(max_attempts: u32) -> Arc<InMemoryTopicWrapper<String>> {
+        let retry = RetryConfig {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            backoff_multiplier: 1,
+        };
+
+        InMemoryTopicWrapper::new_arc(Arc::new(InMemoryTopic::new(
+            "test".into(),
+            retry,
+        )))
+    }
+
+    /// Waits for the next item of a stream, failing if there is none soon.
+    async fn next_of<S: Stream + Unpin>(stream: &mut S) -> S::Item {
+        tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .expect("no item was received")
+            .expect("the stream ended")
+    }
+
+    /// Whether a stream has no item for a while.
+    async fn is_idle<S: Stream + Unpin>(stream: &mut S) -> bool {
+        tokio::time::timeout(QUIET_PERIOD, stream.next())
+            .await
+            .is_err()
+    }
+
+    #[tokio::test]
+    async fn acked_messages_are_not_redelivered() {
+        let topic = topic_of(3);
+        let mut messages = topic.subscribe_manual("a.in").await.unwrap();
+
+        topic.publish("a.in", &"hello".to_owned()).await.unwrap();
+
+        let (body, confirm) = next_of(&mut messages).await.unwrap();
+        assert_eq!(body, "hello");
+
+        confirm.ack().await.unwrap();
+
+        assert!(is_idle(&mut messages).await);
+        assert!(topic.dead_letters("a.in", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn requeued_messages_are_dead_lettered_after_max_attempts() {
+        let topic = topic_of(3);
+        let mut messages = topic.subscribe_manual("a.in").await.unwrap();
+
+        topic.publish("a.in", &"hello".to_owned()).await.unwrap();
+
+        for attempt in 1..=3 {
+            let (body, confirm) = next_of(&mut messages).await.unwrap();
+
+            assert_eq!(body, "hello");
+            assert_eq!(confirm.is_last_attempt(), attempt == 3, "#{attempt}");
+
+            confirm.nack(true).await.unwrap();
+        }
+
+        assert!(is_idle(&mut messages).await);
+
+        let dead_letters = topic.dead_letters("a.in", 10).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].body, "hello");
+        assert_eq!(dead_letters[0].attempts, 3);
+        assert!(dead_letters[0].dead_lettered_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn rejected_messages_are_dead_lettered_at_once() {
+        let topic = topic_of(3);
+        let mut messages = topic.subscribe_manual("a.in").await.unwrap();
+
+        topic.publish("a.in", &"hello".to_owned()).await.unwrap();
+
+        let (_, confirm) = next_of(&mut messages).await.unwrap();
+        confirm.nack(false).await.unwrap();
+
+        assert!(is_idle(&mut messages).await);
+
+        let dead_letters = topic.dead_letters("a.in", 10).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn replayed_dead_letters_are_redelivered() {
+        let topic = topic_of(1);
+        let mut messages = topic.subscribe_manual("a.in").await.unwrap();
+
+        topic.publish("a.in", &"hello".to_owned()).await.unwrap();
+
+        let (_, confirm) = next_of(&mut messages).await.unwrap();
+        confirm.nack(true).await.unwrap();
+
+        assert_eq!(topic.replay_dead_letters("a.in", 10).await.unwrap(), 1);
+
+        let (body, confirm) = next_of(&mut messages).await.unwrap();
+        assert_eq!(body, "hello");
+        // replayed messages get all their attempts again
+        assert!(confirm.is_last_attempt());
+        assert!(topic.dead_letters("a.in", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mirrors_receive_copies_of_matching_messages() {
+        let topic = topic_of(3);
+        let mut queue = topic.subscribe("a.in").await.unwrap();
+        let mut exact = topic.mirror("a.in").await.unwrap();
+        let mut any_in = topic.mirror("*.in").await.unwrap();
+        let mut other = topic.mirror("b.#").await.unwrap();
+
+        topic.publish("a.in", &"hello".to_owned()).await.unwrap();
+
+        assert_eq!(next_of(&mut queue).await.unwrap(), "hello");
+        assert_eq!(next_of(&mut exact).await.unwrap(), "hello");
+        assert_eq!(next_of(&mut any_in).await.unwrap(), "hello");
+        assert!(is_idle(&mut other).await);
+    }
+
+    #[tokio::test]
+    async fn broadcasts_only_reach_mirrors() {
+        let topic = topic_of(3);
+        let mut queue = topic.subscribe("a.in").await.unwrap();
+        let mut first = topic.mirror("a.in").await.unwrap();
+        let mut second = topic.mirror("a.in").await.unwrap();
+
+        topic.broadcast("a.in", &"hello".to_owned()).await.unwrap();
+
+        assert_eq!(next_of(&mut first).await.unwrap(), "hello");
+        assert_eq!(next_of(&mut second).await.unwrap(), "hello");
+        assert!(is_idle(&mut queue).await);
+    }
+
+    #[tokio::test]
+    async fn dropped_mirrors_are_removed() {
+        let topic = topic_of(3);
+        let mirror = topic.mirror("a.in").await.unwrap();
+
+        drop(mirror);
+        topic.publish("a.in", &"hello".to_owned()).await.unwrap();
+
+        assert!(topic.inner.mirrors.read().await.is_empty());
+    }
+
+    #[test]
+    fn matches_exact_keys() {
+        assert!(matches_key("a.b.c", "a.b.c"));
+        assert!(!matches_key("a.b.c", "a.b.d"));
+        assert!(!matches_key("a.b", "a.b.c"));
+        assert!(!matches_key("a.b.c", "a.b"));
+    }
+
+    #[test]
+    fn matches_one_word_with_star() {
+        assert!(matches_key("*.b.in", "a.b.in"));
+        assert!(matches_key("*.*.in", "a.b.in"));
+        assert!(!matches_key("*.in", "a.b.in"));
+        assert!(!matches_key("a.*", "a"));
+        assert!(!matches_key("a.*.in", "a.b.c.in"));
+    }
+
+    #[test]
+    fn matches_any_words_with_hash() {
+        assert!(matches_key("#", "a.b.c"));
+        assert!(matches_key("a.#", "a"));
+        assert!(matches_key("a.#", "a.b.c"));
+        assert!(matches_key("#.in", "a.b.in"));
+        assert!(matches_key("a.#.in", "a.in"));
+        assert!(matches_key("a.#.c.in", "a.b.b.c.in"));
+        assert!(!matches_key("a.#", "b.c"));
+        assert!(!matches_key("#.in", "a.b.out"));
+    }
+
+    #[test]
+    fn matches_mixed_wildcards() {
+        assert!(matches_key("*.#", "a"));
+        assert!(matches_key("#.*.in", "a.b.in"));
+        assert!(!matches_key("*.#.in", "in"));
+    }
+}
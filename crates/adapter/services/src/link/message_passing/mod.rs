@@ -1,32 +1,34 @@
 mod config;
-mod topic;
+mod memory;
+mod rabbitmq;
 mod util;
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::sync::Arc;
 
-use deadpool_lapin::{Config, Pool, Runtime};
 use kernel_services::{
     config::ConfigService,
     error::AppResult,
-    link::message_passing::{MessagePassingService, TopicReader, TopicWriter},
+    link::{
+        error::LinkError,
+        message_passing::{MessagePassingService, TopicReader, TopicWriter},
+    },
     Service,
 };
 use serde::{de::DeserializeOwned, Serialize};
-use tokio::sync::RwLock;
 
-use self::{
-    config::{MessageQueueConfig, MESSAGE_QUEUE_CONFIG_SECTION},
-    topic::{RabbitMqTopic, RabbitMqTopicWrapper},
+use self::config::{MessagePassingConfig, MESSAGE_QUEUE_CONFIG_SECTION};
+pub use self::{
+    memory::InMemoryMessagePassingService,
+    rabbitmq::RabbitMqMessagePassingService,
 };
-use crate::link::message_passing::util::map_ipc_error;
 
-pub struct RabbitMqMessagePassingService {
-    pool: Pool,
-    topics: RwLock<HashMap<String, Arc<RabbitMqTopic>>>,
+pub enum MessagePassingServiceImpl {
+    RabbitMq(RabbitMqMessagePassingService),
+    InMemory(InMemoryMessagePassingService),
 }
 
 #[async_trait::async_trait]
-impl MessagePassingService for RabbitMqMessagePassingService {
+impl MessagePassingService for MessagePassingServiceImpl {
     async fn get_topic_writer<T>(
         &self,
         name: &str,
@@ -34,7 +36,10 @@ impl MessagePassingService for RabbitMqMessagePassingService {
     where
         T: Serialize + Send + Sync + 'static,
     {
-        Ok(self.get_topic_wrapper(name).await?)
+        match self {
+            | Self::RabbitMq(svc) => svc.get_topic_writer(name).await,
+            | Self::InMemory(svc) => svc.get_topic_writer(name).await,
+        }
     }
 
     async fn get_topic_reader<T>(
@@ -44,69 +49,40 @@ impl MessagePassingService for RabbitMqMessagePassingService {
     where
         T: DeserializeOwned + Send + Sync + 'static,
     {
-        Ok(self.get_topic_wrapper(name).await?)
+        match self {
+            | Self::RabbitMq(svc) => svc.get_topic_reader(name).await,
+            | Self::InMemory(svc) => svc.get_topic_reader(name).await,
+        }
     }
 }
 
-impl RabbitMqMessagePassingService {
+impl MessagePassingServiceImpl {
     pub async fn create<C: ConfigService>(config: Arc<C>) -> AppResult<Self> {
-        let conf: MessageQueueConfig =
+        let conf: MessagePassingConfig =
             config.get_section(MESSAGE_QUEUE_CONFIG_SECTION)?;
 
-        debug!(
-            "openning rabbitmq connection to: `{}`",
-            conf.get_concealed_connection_string()?
-        );
-
-        let mut builder = Config {
-            url: Some(conf.get_connection_string()?),
-            ..Default::default()
-        }
-        .builder(Some(Runtime::Tokio1));
-
-        if let Some(max) = conf.pool.max_connections {
-            builder = builder.max_size(max);
+        match conf.protocol.as_str() {
+            | "amqp" => Ok(Self::RabbitMq(
+                RabbitMqMessagePassingService::create(config, conf.retry)
+                    .await?,
+            )),
+            | "memory" => {
+                debug!("using in-process message passing");
+
+                Ok(Self::InMemory(InMemoryMessagePassingService::new(
+                    conf.retry,
+                )))
+            }
+            | protocol => Err(LinkError::MessagePassing(anyhow::anyhow!(
+                "unsupported message queue protocol: {protocol}"
+            ))
+            .into()),
         }
-
-        if let Some(timeout) = conf.pool.max_lifetime_ms {
-            builder =
-                builder.recycle_timeout(Some(Duration::from_millis(timeout)));
-        }
-
-        let pool = builder.build().map_err(map_ipc_error)?;
-
-        Ok(Self {
-            pool,
-            topics: Default::default(),
-        })
-    }
-
-    async fn get_topic_wrapper<T>(
-        &self,
-        name: &str,
-    ) -> AppResult<Arc<RabbitMqTopicWrapper<T>>>
-    where
-        T: Send + Sync,
-    {
-        if let Some(topic) = self.topics.read().await.get(name) {
-            return Ok(RabbitMqTopicWrapper::new_arc(topic.clone()));
-        };
-
-        let topic = Arc::new(
-            RabbitMqTopic::create(name.to_owned(), self.pool.clone()).await?,
-        );
-
-        self.topics
-            .write()
-            .await
-            .insert(name.to_owned(), topic.clone());
-
-        Ok(RabbitMqTopicWrapper::new_arc(topic))
     }
 }
 
 #[async_trait::async_trait]
-impl Service for RabbitMqMessagePassingService {
+impl Service for MessagePassingServiceImpl {
     async fn initialize(self: Arc<Self>) -> AppResult<()> {
         Ok(())
     }
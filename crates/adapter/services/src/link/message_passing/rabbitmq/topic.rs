@@ -0,0 +1,795 @@
+use std::{
+    collections::HashSet,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use deadpool_lapin::{Object, Pool};
+use futures::{stream::BoxStream, StreamExt};
+use kernel_services::{
+    error::AppResult,
+    link::message_passing::{
+        DeadLetter,
+        MessageConfirmation,
+        ScopedTopicReader,
+        ScopedTopicWriter,
+        TopicReader,
+        TopicWriter,
+    },
+};
+use lapin::{
+    acker::Acker,
+    message::Delivery,
+    options::{
+        BasicConsumeOptions,
+        BasicGetOptions,
+        BasicNackOptions,
+        ExchangeDeclareOptions,
+        QueueDeclareOptions,
+    },
+    publisher_confirm::PublisherConfirm,
+    types::{AMQPValue, FieldTable, ShortString},
+    BasicProperties,
+    Channel,
+    ExchangeKind,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::RwLock;
+
+use crate::link::message_passing::{
+    config::RetryConfig,
+    util::{deserialize, map_ipc_error, map_params_error},
+};
+
+const ATTEMPTS_HEADER: &str = "x-asma-attempts";
+
+#[derive(Debug)]
+pub(super) struct RabbitMqTopic {
+    name: String,
+    pool: Pool,
+    retry: RetryConfig,
+    queues: RwLock<HashSet<String>>,
+    current_consumer_id: AtomicU32,
+}
+
+#[derive(Debug)]
+pub(super) struct RabbitMqTopicWrapper<T> {
+    inner: Arc<RabbitMqTopic>,
+    _phantom: PhantomData<T>,
+}
+
+#[derive(Debug)]
+pub(super) struct ScopedRabbitMqTopicWrapper<T> {
+    key: String,
+    inner: Arc<RabbitMqTopic>,
+    _phantom: PhantomData<T>,
+}
+
+impl RabbitMqTopic {
+    pub(super) async fn create(
+        name: String,
+        pool: Pool,
+        retry: RetryConfig,
+    ) -> AppResult<Self> {
+        let (_, ch) = Self::acquire_channel(&pool).await?;
+
+        let declare_opts = ExchangeDeclareOptions {
+            durable: true,
+            auto_delete: false,
+            ..Default::default()
+        };
+
+        ch.exchange_declare(
+            &name,
+            ExchangeKind::Topic,
+            declare_opts,
+            Default::default(),
+        )
+        .await
+        .map_err(map_ipc_error)?;
+
+        ch.exchange_declare(
+            &dead_letter_exchange_of(&name),
+            ExchangeKind::Direct,
+            declare_opts,
+            Default::default(),
+        )
+        .await
+        .map_err(map_ipc_error)?;
+
+        Ok(Self {
+            name,
+            pool,
+            retry,
+            queues: Default::default(),
+            current_consumer_id: 0.into(),
+        })
+    }
+
+    async fn do_publish<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        body: &T,
+    ) -> AppResult<PublisherConfirm> {
+        let buf = rmp_serde::to_vec(body).map_err(map_params_error)?;
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+
+        let queue = self.ensure_queue_created(key, false, &ch).await?;
+
+        let confirm = ch
+            .basic_publish(
+                &self.name,
+                &queue,
+                Default::default(),
+                &buf,
+                Default::default(),
+            )
+            .await
+            .map_err(map_ipc_error)?;
+
+        Ok(confirm)
+    }
+
+    async fn do_broadcast<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        body: &T,
+    ) -> AppResult<()> {
+        let buf = rmp_serde::to_vec(body).map_err(map_params_error)?;
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+
+        // no queue is created for the key, so only the bound mirrors get it
+        ch.basic_publish(
+            &self.name,
+            &format!("{}.{key}", self.name),
+            Default::default(),
+            &buf,
+            Default::default(),
+        )
+        .await
+        .map_err(map_ipc_error)?
+        .await
+        .map_err(map_ipc_error)?;
+
+        Ok(())
+    }
+
+    async fn do_subscribe<T, F: Fn(&str, Delivery) -> AppResult<T>>(
+        &self,
+        key: &str,
+        mirror: bool,
+        manual_ack: bool,
+        mapper: F,
+    ) -> AppResult<impl tokio_stream::Stream<Item = AppResult<T>>> {
+        let id = self
+            .current_consumer_id
+            .fetch_add(1, Ordering::AcqRel)
+            .to_string();
+
+        let opts = BasicConsumeOptions {
+            exclusive: mirror,
+            no_ack: !manual_ack,
+            ..Default::default()
+        };
+
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+        let queue = self.ensure_queue_created(key, mirror, &ch).await?;
+
+        if manual_ack && !mirror {
+            self.ensure_dead_letter_queue_created(&queue, &ch).await?;
+        }
+
+        Ok(ch
+            .basic_consume(&queue, &id, opts, Default::default())
+            .await
+            .map_err(map_ipc_error)?
+            .map(move |i| match i {
+                | Ok(i) => mapper(&queue, i),
+                | Err(err) => Err(map_ipc_error(err)),
+            }))
+    }
+
+    async fn do_retry(
+        &self,
+        queue: &str,
+        data: &[u8],
+        attempts: u32,
+    ) -> AppResult<()> {
+        let backoff = self.retry.backoff_of(attempts);
+        let props =
+            BasicProperties::default().with_headers(attempts_headers(attempts));
+
+        debug!("retrying message of `{queue}` in {backoff}ms (#{attempts})");
+
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+        let retry_queue =
+            self.ensure_retry_queue_created(queue, backoff, &ch).await?;
+
+        ch.basic_publish("", &retry_queue, Default::default(), data, props)
+            .await
+            .map_err(map_ipc_error)?
+            .await
+            .map_err(map_ipc_error)?;
+
+        Ok(())
+    }
+
+    async fn do_dead_letter(
+        &self,
+        queue: &str,
+        data: &[u8],
+        attempts: u32,
+    ) -> AppResult<()> {
+        let props = BasicProperties::default()
+            .with_headers(attempts_headers(attempts))
+            .with_timestamp(Utc::now().timestamp() as u64);
+
+        warn!("dead-lettering message of `{queue}` after {attempts} attempts");
+
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+
+        ch.basic_publish(
+            &dead_letter_exchange_of(&self.name),
+            queue,
+            Default::default(),
+            data,
+            props,
+        )
+        .await
+        .map_err(map_ipc_error)?
+        .await
+        .map_err(map_ipc_error)?;
+
+        Ok(())
+    }
+
+    async fn do_get_dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<(Channel, String, Vec<Delivery>)> {
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+        let queue = self.ensure_queue_created(key, false, &ch).await?;
+        let dead_queue =
+            self.ensure_dead_letter_queue_created(&queue, &ch).await?;
+
+        let mut deliveries = Vec::new();
+
+        while deliveries.len() < limit {
+            let message = ch
+                .basic_get(&dead_queue, BasicGetOptions { no_ack: false })
+                .await
+                .map_err(map_ipc_error)?;
+
+            match message {
+                | Some(message) => deliveries.push(message.delivery),
+                | None => break,
+            }
+        }
+
+        Ok((ch, queue, deliveries))
+    }
+
+    async fn do_list_dead_letters<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>> {
+        let (_, _, deliveries) = self.do_get_dead_letters(key, limit).await?;
+
+        // put the messages back, as they are only being inspected
+        if let Some(last) = deliveries.last() {
+            last.acker
+                .nack(BasicNackOptions {
+                    multiple: true,
+                    requeue: true,
+                })
+                .await
+                .map_err(map_ipc_error)?;
+        }
+
+        Ok(deliveries
+            .into_iter()
+            .filter_map(|d| match deserialize::<T>(&d.data) {
+                | Ok(body) => Some(DeadLetter {
+                    body,
+                    attempts: attempts_of(&d),
+                    dead_lettered_at: dead_lettered_at_of(&d),
+                }),
+                | Err(err) => {
+                    warn!("skipping malformed dead letter: {err}");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn do_replay_dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<usize> {
+        let (ch, queue, deliveries) =
+            self.do_get_dead_letters(key, limit).await?;
+
+        for delivery in deliveries.iter() {
+            ch.basic_publish(
+                "",
+                &queue,
+                Default::default(),
+                &delivery.data,
+                Default::default(),
+            )
+            .await
+            .map_err(map_ipc_error)?
+            .await
+            .map_err(map_ipc_error)?;
+
+            delivery
+                .acker
+                .ack(Default::default())
+                .await
+                .map_err(map_ipc_error)?;
+        }
+
+        Ok(deliveries.len())
+    }
+
+    async fn do_purge_dead_letters(&self, key: &str) -> AppResult<usize> {
+        let (_, ch) = Self::acquire_channel(&self.pool).await?;
+        let queue = self.ensure_queue_created(key, false, &ch).await?;
+        let dead_queue =
+            self.ensure_dead_letter_queue_created(&queue, &ch).await?;
+
+        let count = ch
+            .queue_purge(&dead_queue, Default::default())
+            .await
+            .map_err(map_ipc_error)?;
+
+        Ok(count as usize)
+    }
+
+    /// Declares the queue retries of `queue` wait in for `backoff` ms, before
+    /// being routed back to it, returning its name. Each backoff has its own
+    /// queue, as messages only expire once they reach the head of theirs.
+    async fn ensure_retry_queue_created(
+        &self,
+        queue: &str,
+        backoff: u64,
+        ch: &Channel,
+    ) -> AppResult<String> {
+        let retry_queue = retry_queue_of(queue, backoff);
+
+        let mut queues = self.queues.write().await;
+
+        if queues.contains(&retry_queue) {
+            return Ok(retry_queue);
+        }
+
+        let declare_opts = QueueDeclareOptions {
+            durable: true,
+            ..Default::default()
+        };
+
+        let mut retry_args = FieldTable::default();
+        retry_args.insert(
+            "x-dead-letter-exchange".into(),
+            AMQPValue::LongString("".into()),
+        );
+        retry_args.insert(
+            "x-dead-letter-routing-key".into(),
+            AMQPValue::LongString(queue.into()),
+        );
+        retry_args.insert(
+            "x-message-ttl".into(),
+            AMQPValue::LongLongInt(backoff as i64),
+        );
+
+        ch.queue_declare(&retry_queue, declare_opts, retry_args)
+            .await
+            .map_err(map_ipc_error)?;
+
+        queues.insert(retry_queue.clone());
+
+        Ok(retry_queue)
+    }
+
+    /// Declares the queue the dead letters of `queue` are routed to,
+    /// returning its name.
+    async fn ensure_dead_letter_queue_created(
+        &self,
+        queue: &str,
+        ch: &Channel,
+    ) -> AppResult<String> {
+        let dead_queue = format!("{queue}.dead");
+
+        let mut queues = self.queues.write().await;
+
+        if queues.contains(&dead_queue) {
+            return Ok(dead_queue);
+        }
+
+        let declare_opts = QueueDeclareOptions {
+            durable: true,
+            ..Default::default()
+        };
+
+        ch.queue_declare(&dead_queue, declare_opts, Default::default())
+            .await
+            .map_err(map_ipc_error)?;
+
+        ch.queue_bind(
+            &dead_queue,
+            &dead_letter_exchange_of(&self.name),
+            queue,
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .map_err(map_ipc_error)?;
+
+        queues.insert(dead_queue.clone());
+
+        Ok(dead_queue)
+    }
+
+    async fn ensure_queue_created<'a>(
+        &'a self,
+        key: &str,
+        temporary: bool,
+        ch: &Channel,
+    ) -> AppResult<String> {
+        let key = format!("{}.{key}", self.name);
+
+        async fn create_queue(
+            topic_name: &str,
+            key: &str,
+            temporary: bool,
+            ch: &Channel,
+        ) -> AppResult<String> {
+            let declare_opts = QueueDeclareOptions {
+                durable: !temporary,
+                auto_delete: temporary,
+                ..Default::default()
+            };
+            // temporary queues are named by the broker, and bound to the key
+            let name = if temporary { "" } else { key };
+
+            let queue = ch
+                .queue_declare(name, declare_opts, Default::default())
+                .await
+                .map_err(map_ipc_error)?;
+
+            ch.queue_bind(
+                queue.name().as_str(),
+                topic_name,
+                key,
+                Default::default(),
+                Default::default(),
+            )
+            .await
+            .map_err(map_ipc_error)?;
+
+            Ok(queue.name().to_string())
+        }
+
+        if temporary {
+            return create_queue(&self.name, &key, true, ch).await;
+        }
+
+        let mut queues = self.queues.write().await;
+
+        if !queues.contains(&key) {
+            queues.insert(create_queue(&self.name, &key, false, ch).await?);
+        }
+
+        Ok(key)
+    }
+
+    async fn acquire_channel(pool: &Pool) -> AppResult<(Object, Channel)> {
+        let conn = pool.get().await.map_err(map_ipc_error)?;
+        let chan = conn.create_channel().await.map_err(map_ipc_error)?;
+
+        Ok((conn, chan))
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> TopicWriter<T> for RabbitMqTopicWrapper<T>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    async fn publish(&self, key: &str, body: &T) -> AppResult<()> {
+        self.inner.do_publish(key, body).await?;
+
+        Ok(())
+    }
+
+    async fn publish_confirmed(&self, key: &str, body: &T) -> AppResult<()> {
+        self.inner
+            .do_publish(key, body)
+            .await?
+            .await
+            .map_err(map_ipc_error)?;
+
+        Ok(())
+    }
+
+    async fn broadcast(&self, key: &str, body: &T) -> AppResult<()> {
+        self.inner.do_broadcast(key, body).await
+    }
+
+    fn scoped(&self, key: &str) -> Arc<dyn ScopedTopicWriter<T>> {
+        Arc::new(ScopedRabbitMqTopicWrapper {
+            key: key.to_owned(),
+            inner: self.inner.clone(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ScopedTopicWriter<T> for ScopedRabbitMqTopicWrapper<T>
+where
+    T: Serialize + Send + Sync,
+{
+    async fn publish(&self, body: &T) -> AppResult<()> {
+        self.inner.do_publish(&self.key, body).await?;
+
+        Ok(())
+    }
+
+    async fn publish_confirmed(&self, body: &T) -> AppResult<()> {
+        self.inner
+            .do_publish(&self.key, body)
+            .await?
+            .await
+            .map_err(map_ipc_error)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> TopicReader<T> for RabbitMqTopicWrapper<T>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    async fn subscribe(
+        &self,
+        key: &str,
+    ) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_subscribe(key, false, false, |_, i| deserialize::<T>(&i.data))
+            .await?
+            .boxed())
+    }
+
+    async fn subscribe_manual(
+        &self,
+        key: &str,
+    ) -> AppResult<BoxStream<'_, AppResult<(T, Arc<dyn MessageConfirmation>)>>>
+    {
+        Ok(self
+            .inner
+            .do_subscribe(key, false, true, |queue, i| {
+                Ok((
+                    deserialize::<T>(&i.data)?,
+                    RabbitMQMessageConfirmation::new_arc(&self.inner, queue, i),
+                ))
+            })
+            .await?
+            .boxed())
+    }
+
+    async fn mirror(
+        &self,
+        key: &str,
+    ) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_subscribe(key, true, false, |_, i| deserialize::<T>(&i.data))
+            .await?
+            .boxed())
+    }
+
+    async fn dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>> {
+        self.inner.do_list_dead_letters(key, limit).await
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<usize> {
+        self.inner.do_replay_dead_letters(key, limit).await
+    }
+
+    async fn purge_dead_letters(&self, key: &str) -> AppResult<usize> {
+        self.inner.do_purge_dead_letters(key).await
+    }
+
+    fn scoped(&self, key: &str) -> Arc<dyn ScopedTopicReader<T>> {
+        Arc::new(ScopedRabbitMqTopicWrapper {
+            key: key.to_owned(),
+            inner: self.inner.clone(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ScopedTopicReader<T> for ScopedRabbitMqTopicWrapper<T>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    async fn subscribe(&self) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_subscribe(&self.key, false, false, |_, i| {
+                deserialize::<T>(&i.data)
+            })
+            .await?
+            .boxed())
+    }
+
+    async fn subscribe_manual(
+        &self,
+    ) -> AppResult<BoxStream<'_, AppResult<(T, Arc<dyn MessageConfirmation>)>>>
+    {
+        Ok(self
+            .inner
+            .do_subscribe(&self.key, false, true, |queue, i| {
+                Ok((
+                    deserialize::<T>(&i.data)?,
+                    RabbitMQMessageConfirmation::new_arc(&self.inner, queue, i),
+                ))
+            })
+            .await?
+            .boxed())
+    }
+
+    async fn mirror(&self) -> AppResult<BoxStream<'_, AppResult<T>>> {
+        Ok(self
+            .inner
+            .do_subscribe(&self.key, true, false, |_, i| {
+                deserialize::<T>(&i.data)
+            })
+            .await?
+            .boxed())
+    }
+
+    async fn dead_letters(
+        &self,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>> {
+        self.inner.do_list_dead_letters(&self.key, limit).await
+    }
+
+    async fn replay_dead_letters(&self, limit: usize) -> AppResult<usize> {
+        self.inner.do_replay_dead_letters(&self.key, limit).await
+    }
+
+    async fn purge_dead_letters(&self) -> AppResult<usize> {
+        self.inner.do_purge_dead_letters(&self.key).await
+    }
+}
+
+impl<T> RabbitMqTopicWrapper<T>
+where
+    T: Send + Sync,
+{
+    pub(super) fn new_arc(value: Arc<RabbitMqTopic>) -> Arc<Self> {
+        Arc::new(RabbitMqTopicWrapper {
+            inner: value,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+pub(super) struct RabbitMQMessageConfirmation {
+    topic: Arc<RabbitMqTopic>,
+    queue: String,
+    delivery: Delivery,
+}
+
+impl RabbitMQMessageConfirmation {
+    fn new_arc(
+        topic: &Arc<RabbitMqTopic>,
+        queue: &str,
+        delivery: Delivery,
+    ) -> Arc<dyn MessageConfirmation> {
+        Arc::new(Self {
+            topic: topic.clone(),
+            queue: queue.to_owned(),
+            delivery,
+        })
+    }
+
+    fn acker(&self) -> &Acker {
+        &self.delivery.acker
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageConfirmation for RabbitMQMessageConfirmation {
+    async fn ack(&self) -> AppResult<()> {
+        self.acker()
+            .ack(Default::default())
+            .await
+            .map_err(map_ipc_error)
+    }
+
+    async fn nack(&self, requeue: bool) -> AppResult<()> {
+        let attempts = attempts_of(&self.delivery) + 1;
+        let data = &self.delivery.data;
+
+        let moved = if requeue && attempts < self.topic.retry.max_attempts {
+            self.topic.do_retry(&self.queue, data, attempts).await
+        } else {
+            self.topic.do_dead_letter(&self.queue, data, attempts).await
+        };
+
+        if let Err(err) = moved {
+            // fall back to an immediate redelivery, so the message is not lost
+            self.acker()
+                .nack(BasicNackOptions {
+                    multiple: false,
+                    requeue: true,
+                })
+                .await
+                .map_err(map_ipc_error)?;
+
+            return Err(err);
+        }
+
+        self.ack().await
+    }
+
+    fn is_last_attempt(&self) -> bool {
+        attempts_of(&self.delivery) + 1 >= self.topic.retry.max_attempts
+    }
+}
+
+fn attempts_of(delivery: &Delivery) -> u32 {
+    let attempts = delivery
+        .properties
+        .headers()
+        .as_ref()
+        .and_then(|h| h.inner().get(&ShortString::from(ATTEMPTS_HEADER)));
+
+    match attempts {
+        | Some(AMQPValue::LongUInt(attempts)) => *attempts,
+        | _ => 0,
+    }
+}
+
+fn dead_lettered_at_of(delivery: &Delivery) -> Option<DateTime<Utc>> {
+    let timestamp = (*delivery.properties.timestamp())?;
+
+    Utc.timestamp_opt(timestamp as i64, 0).single()
+}
+
+fn attempts_headers(attempts: u32) -> FieldTable {
+    let mut headers = FieldTable::default();
+    headers.insert(ATTEMPTS_HEADER.into(), AMQPValue::LongUInt(attempts));
+
+    headers
+}
+
+fn retry_queue_of(queue: &str, backoff: u64) -> String {
+    format!("{queue}.retry.{backoff}ms")
+}
+
+fn dead_letter_exchange_of(topic: &str) -> String {
+    format!("{topic}.dead")
+}
@@ -0,0 +1,39 @@
+use common_macros::into_fn;
+use common_validation::*;
+use serde::Deserialize;
+use validator::Validate;
+
+pub const STORAGE_CONFIG_SECTION: &str = "storage";
+
+into_fn!(default_max_blob_size_bytes: const usize => 50 * 1024 * 1024);
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct StorageConfig {
+    #[validate(custom = "supported_storage_provider")]
+    pub provider: String,
+    #[validate(range(min = 1))]
+    #[serde(default = "default_max_blob_size_bytes")]
+    pub max_blob_size_bytes: usize,
+    #[validate]
+    pub local: Option<LocalStorageConfig>,
+    #[validate]
+    pub s3: Option<S3StorageConfig>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LocalStorageConfig {
+    #[validate(length(min = 1))]
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct S3StorageConfig {
+    #[validate(url)]
+    pub endpoint: String,
+    #[validate(length(min = 1))]
+    pub region: String,
+    #[validate(length(min = 3, max = 63))]
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
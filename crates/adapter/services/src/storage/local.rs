@@ -0,0 +1,276 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use kernel_services::{
+    error::AppResult,
+    storage::blob::{Blob, BlobStorageService},
+    Service,
+};
+use tokio::fs;
+
+use super::{
+    config::LocalStorageConfig,
+    util::{map_io_error, validate_key, validate_size},
+};
+
+const CONTENT_TYPE_SUFFIX: &str = ".content-type";
+const TMP_SUFFIX: &str = ".tmp";
+
+pub struct LocalBlobStorageService {
+    root: PathBuf,
+    max_size: usize,
+}
+
+#[async_trait::async_trait]
+impl BlobStorageService for LocalBlobStorageService {
+    async fn put(&self, key: &str, blob: Blob) -> AppResult<()> {
+        let path = self.path_of(key)?;
+        validate_size(&blob, self.max_size)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(map_io_error)?;
+        }
+
+        let content_type_path = with_suffix(&path, CONTENT_TYPE_SUFFIX);
+
+        match blob.content_type {
+            | Some(content_type) => fs::write(content_type_path, content_type)
+                .await
+                .map_err(map_io_error)?,
+            | None => remove_if_exists(content_type_path).await?,
+        }
+
+        // write to a temporary file first, so that readers never observe
+        // partially written blobs
+        let tmp_path = with_suffix(&path, TMP_SUFFIX);
+
+        fs::write(&tmp_path, &blob.data)
+            .await
+            .map_err(map_io_error)?;
+        fs::rename(&tmp_path, &path).await.map_err(map_io_error)
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Blob> {
+        let path = self.path_of(key)?;
+        let data = fs::read(&path).await.map_err(map_io_error)?;
+        let content_type =
+            fs::read_to_string(with_suffix(&path, CONTENT_TYPE_SUFFIX))
+                .await
+                .ok();
+
+        Ok(Blob { content_type, data })
+    }
+
+    async fn remove(&self, key: &str) -> AppResult<()> {
+        let path = self.path_of(key)?;
+
+        fs::remove_file(&path).await.map_err(map_io_error)?;
+        remove_if_exists(with_suffix(&path, CONTENT_TYPE_SUFFIX)).await
+    }
+
+    async fn exists(&self, key: &str) -> AppResult<bool> {
+        let path = self.path_of(key)?;
+
+        fs::try_exists(path).await.map_err(map_io_error)
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for LocalBlobStorageService {
+    async fn initialize(self: Arc<Self>) -> AppResult<()> {
+        debug!("using local blob storage at: {:?}", self.root);
+
+        fs::create_dir_all(&self.root).await.map_err(map_io_error)?;
+
+        Ok(())
+    }
+}
+
+impl LocalBlobStorageService {
+    pub fn new(conf: LocalStorageConfig, max_size: usize) -> Self {
+        Self {
+            root: PathBuf::from(conf.path),
+            max_size,
+        }
+    }
+
+    fn path_of(&self, key: &str) -> AppResult<PathBuf> {
+        validate_key(key)?;
+
+        Ok(self.root.join(key))
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+
+    path.push(suffix);
+    path.into()
+}
+
+async fn remove_if_exists(path: PathBuf) -> AppResult<()> {
+    match fs::remove_file(path).await {
+        | Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(map_io_error(err))
+        }
+        | _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel_services::{error::AppError, storage::error::StorageError};
+    use uuid::Uuid;
+
+    use super::*;
+
+    const MAX_SIZE: usize = 16;
+
+    /// A storage rooted at a temporary directory, removed once dropped.
+    struct TempStorage(Arc<LocalBlobStorageService>);
+
+    impl TempStorage {
+        async fn new() -> Self {
+            let root =
+                std::env::temp_dir().join(format!("blobs-{}", Uuid::new_v4()));
+            let conf = LocalStorageConfig {
+                path: root.to_string_lossy().into_owned(),
+            };
+
+            let storage =
+                Arc::new(LocalBlobStorageService::new(conf, MAX_SIZE));
+            storage.clone().initialize().await.unwrap();
+
+            Self(storage)
+        }
+    }
+
+    impl std::ops::Deref for TempStorage {
+        type Target = LocalBlobStorageService;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl Drop for TempStorage {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0.root).ok();
+        }
+    }
+
+    fn blob_of(data: &[u8], content_type: Option<&str>) -> Blob {
+        Blob {
+            content_type: content_type.map(str::to_owned),
+            data: data.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn gets_put_blobs() {
+        let storage = TempStorage::new().await;
+        let key = "channels/a/photo.jpg";
+
+        storage
+            .put(key, blob_of(b"jpeg", Some("image/jpeg")))
+            .await
+            .unwrap();
+
+        let blob = storage.get(key).await.unwrap();
+        assert_eq!(blob.data, b"jpeg");
+        assert_eq!(blob.content_type.as_deref(), Some("image/jpeg"));
+        assert!(storage.exists(key).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn overwrites_blobs_and_their_content_types() {
+        let storage = TempStorage::new().await;
+        let key = "channels/a/file";
+
+        storage
+            .put(key, blob_of(b"first", Some("text/plain")))
+            .await
+            .unwrap();
+        storage.put(key, blob_of(b"second", None)).await.unwrap();
+
+        let blob = storage.get(key).await.unwrap();
+        assert_eq!(blob.data, b"second");
+        assert_eq!(blob.content_type, None);
+    }
+
+    #[tokio::test]
+    async fn removes_blobs() {
+        let storage = TempStorage::new().await;
+        let key = "channels/a/file";
+
+        storage
+            .put(key, blob_of(b"data", Some("text/plain")))
+            .await
+            .unwrap();
+        storage.remove(key).await.unwrap();
+
+        assert!(!storage.exists(key).await.unwrap());
+        assert!(matches!(
+            storage.get(key).await,
+            Err(AppError::Storage(StorageError::NotFound))
+        ));
+        assert!(matches!(
+            storage.remove(key).await,
+            Err(AppError::Storage(StorageError::NotFound))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_keys() {
+        let storage = TempStorage::new().await;
+
+        for key in [
+            "",
+            "/etc/passwd",
+            "../outside",
+            "channels/../../outside",
+            "channels/./file",
+            "channels//file",
+            "channels/file/",
+            "channels/with space",
+            "channels\\file",
+        ] {
+            assert!(
+                matches!(
+                    storage.put(key, blob_of(b"data", None)).await,
+                    Err(AppError::Storage(StorageError::InvalidKey(_)))
+                ),
+                "{key:?}"
+            );
+            assert!(
+                matches!(
+                    storage.exists(key).await,
+                    Err(AppError::Storage(StorageError::InvalidKey(_)))
+                ),
+                "{key:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_blobs_over_the_maximum_size() {
+        let storage = TempStorage::new().await;
+        let key = "channels/a/large";
+
+        let result = storage.put(key, blob_of(&[0; MAX_SIZE + 1], None)).await;
+
+        assert!(matches!(
+            result,
+            Err(AppError::Storage(StorageError::TooLarge { size, max_size }))
+                if size == MAX_SIZE + 1 && max_size == MAX_SIZE
+        ));
+        assert!(!storage.exists(key).await.unwrap());
+
+        storage
+            .put(key, blob_of(&[0; MAX_SIZE], None))
+            .await
+            .unwrap();
+    }
+}
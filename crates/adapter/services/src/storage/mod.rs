@@ -0,0 +1,52 @@
+mod config;
+mod local;
+mod s3_compat;
+mod util;
+
+use std::sync::Arc;
+
+use kernel_services::{
+    config::ConfigService,
+    error::AppResult,
+    storage::{blob::BlobStorageService, error::StorageError},
+    Service,
+};
+
+pub use self::{
+    config::*,
+    local::LocalBlobStorageService,
+    s3_compat::S3BlobStorageService,
+};
+
+pub async fn create_blob_storage<C: ConfigService>(
+    config: Arc<C>,
+) -> AppResult<Arc<dyn BlobStorageService>> {
+    let conf: StorageConfig = config.get_section(STORAGE_CONFIG_SECTION)?;
+
+    let svc: Arc<dyn BlobStorageService> = match conf.provider.as_str() {
+        | "local" => Arc::new(LocalBlobStorageService::new(
+            conf.local.ok_or_else(|| missing_section_error("local"))?,
+            conf.max_blob_size_bytes,
+        )),
+        | "s3" => Arc::new(S3BlobStorageService::create(
+            conf.s3.ok_or_else(|| missing_section_error("s3"))?,
+            conf.max_blob_size_bytes,
+        )?),
+        | provider => {
+            return Err(StorageError::Backend(anyhow::anyhow!(
+                "unsupported storage provider: {provider}"
+            ))
+            .into())
+        }
+    };
+
+    svc.clone().initialize().await?;
+
+    Ok(svc)
+}
+
+fn missing_section_error(provider: &str) -> StorageError {
+    StorageError::Backend(anyhow::anyhow!(
+        "missing `{STORAGE_CONFIG_SECTION}.{provider}` config section"
+    ))
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use kernel_services::{
+    error::AppResult,
+    storage::{
+        blob::{Blob, BlobStorageService},
+        error::StorageError,
+    },
+    Service,
+};
+use s3::{creds::Credentials, Bucket, Region};
+
+use super::{
+    config::S3StorageConfig,
+    util::{map_backend_error, validate_key, validate_size},
+};
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+const CONTENT_TYPE_HEADER: &str = "content-type";
+
+pub struct S3BlobStorageService {
+    bucket: Bucket,
+    max_size: usize,
+}
+
+#[async_trait::async_trait]
+impl BlobStorageService for S3BlobStorageService {
+    async fn put(&self, key: &str, blob: Blob) -> AppResult<()> {
+        validate_key(key)?;
+        validate_size(&blob, self.max_size)?;
+
+        let content_type =
+            blob.content_type.as_deref().unwrap_or(DEFAULT_CONTENT_TYPE);
+
+        let response = self
+            .bucket
+            .put_object_with_content_type(key, &blob.data, content_type)
+            .await
+            .map_err(map_backend_error)?;
+
+        check_status(response.status_code())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Blob> {
+        validate_key(key)?;
+
+        let response = self
+            .bucket
+            .get_object(key)
+            .await
+            .map_err(map_backend_error)?;
+
+        check_status(response.status_code())?;
+
+        Ok(Blob {
+            content_type: response.headers().remove(CONTENT_TYPE_HEADER),
+            data: response.bytes().to_vec(),
+        })
+    }
+
+    async fn remove(&self, key: &str) -> AppResult<()> {
+        validate_key(key)?;
+
+        let response = self
+            .bucket
+            .delete_object(key)
+            .await
+            .map_err(map_backend_error)?;
+
+        check_status(response.status_code())
+    }
+
+    async fn exists(&self, key: &str) -> AppResult<bool> {
+        validate_key(key)?;
+
+        let (_, status) = self
+            .bucket
+            .head_object(key)
+            .await
+            .map_err(map_backend_error)?;
+
+        match check_status(status) {
+            | Ok(()) => Ok(true),
+            | Err(_) if status == 404 => Ok(false),
+            | Err(err) => Err(err),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for S3BlobStorageService {
+    async fn initialize(self: Arc<Self>) -> AppResult<()> {
+        debug!(
+            "using s3 blob storage bucket `{}` at: {}",
+            self.bucket.name,
+            self.bucket.host()
+        );
+
+        Ok(())
+    }
+}
+
+impl S3BlobStorageService {
+    pub fn create(conf: S3StorageConfig, max_size: usize) -> AppResult<Self> {
+        let region = Region::Custom {
+            region: conf.region,
+            endpoint: conf.endpoint,
+        };
+
+        let credentials = Credentials::new(
+            Some(&conf.access_key),
+            Some(&conf.secret_key),
+            None,
+            None,
+            None,
+        )
+        .map_err(map_backend_error)?;
+
+        // path-style addressing is required by most self-hosted s3
+        // implementations (e.g. minio)
+        let bucket = Bucket::new(&conf.bucket, region, credentials)
+            .map_err(map_backend_error)?
+            .with_path_style();
+
+        Ok(Self { bucket, max_size })
+    }
+}
+
+fn check_status(status: u16) -> AppResult<()> {
+    match status {
+        | 200..=299 => Ok(()),
+        | 404 => Err(StorageError::NotFound.into()),
+        | _ => Err(StorageError::Io(format!(
+            "s3 responded with status code {status}"
+        ))
+        .into()),
+    }
+}
@@ -0,0 +1,44 @@
+use kernel_services::{
+    error::AppError,
+    storage::{blob::Blob, error::StorageError},
+};
+
+pub(super) fn validate_key(key: &str) -> Result<(), StorageError> {
+    let is_valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+                })
+        });
+
+    match is_valid {
+        | true => Ok(()),
+        | false => Err(StorageError::InvalidKey(key.into())),
+    }
+}
+
+pub(super) fn validate_size(
+    blob: &Blob,
+    max_size: usize,
+) -> Result<(), StorageError> {
+    match blob.data.len() {
+        | size if size > max_size => {
+            Err(StorageError::TooLarge { size, max_size })
+        }
+        | _ => Ok(()),
+    }
+}
+
+pub(super) fn map_io_error(err: std::io::Error) -> AppError {
+    match err.kind() {
+        | std::io::ErrorKind::NotFound => StorageError::NotFound.into(),
+        | _ => StorageError::Io(err.to_string()).into(),
+    }
+}
+
+pub(super) fn map_backend_error<E: Into<anyhow::Error>>(err: E) -> AppError {
+    StorageError::Backend(err.into()).into()
+}
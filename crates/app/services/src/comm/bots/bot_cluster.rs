@@ -1,30 +1,36 @@
 use std::{collections::HashMap, sync::Arc};
 
-use futures::TryStreamExt;
+use futures::StreamExt;
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Bot, MessageDirection},
+        comm::{Bot, ChatHandler, MessageDirection},
     },
     traits::Key,
 };
 use kernel_repositories::{error::RepoError, DataStore};
 use kernel_services::{
-    comm::chats::{ChatEventKind, ChatsService},
+    comm::{
+        bots::BotStatus,
+        chats::{ChatEvent, ChatEventKind, ChatsService},
+        error::CommError,
+    },
     error::AppResult,
+    link::rich_text::TextFormat,
 };
 use tokio::{
     sync::{Mutex, RwLock},
     task::JoinHandle,
 };
 
-use super::bot_context::BotContext;
+use super::{bot_context::BotContext, config::BotsConfig};
 
 pub(super) struct BotCluster {
     data: Arc<dyn DataStore>,
     bots: RwLock<HashMap<Key<Bot>, BotContext>>,
     user_id: Key<User>,
     chat_svc: Arc<dyn ChatsService>,
+    config: BotsConfig,
     watch_task: Mutex<Option<JoinHandle<()>>>,
 }
 
@@ -33,40 +39,46 @@ impl BotCluster {
         data: Arc<dyn DataStore>,
         user_id: &Key<User>,
         chat_svc: Arc<dyn ChatsService>,
+        config: BotsConfig,
     ) -> Self {
         Self {
             data,
             bots: Default::default(),
             user_id: user_id.clone(),
             chat_svc,
+            config,
             watch_task: Default::default(),
         }
     }
 
-    pub(super) async fn append_bot(&self, bot: Bot) -> AppResult<()> {
-        if self.bots.read().await.contains_key(&bot.id) {
-            warn!("an attempt to add an existing bot cluster, ignoring");
-            return Ok(());
-        }
-
+    /// Loads a bot into the cluster, replacing its previously loaded
+    /// context, if any.
+    pub(super) async fn load_bot(&self, bot: &Bot) -> AppResult<()> {
         let entry =
             match self.data.comm().menus().get_entry_menu_of(&bot.id).await {
                 | Ok(entry) => entry,
-                | Err(err) => {
-                    return match err {
-                        | RepoError::NotFound => {
-                            warn!("bot #{} has no menu, skipping", bot.id);
-                            Ok(())
-                        }
-                        | _ => Err(err.into()),
-                    };
+                | Err(RepoError::NotFound) => {
+                    return Err(CommError::InvalidBotState(format!(
+                        "bot #{} has no entry menu",
+                        bot.id
+                    ))
+                    .into());
                 }
+                | Err(err) => return Err(err.into()),
             };
 
-        let context = BotContext::new(entry, self.data.clone());
+        let channels = self.data.comm().bots().get_channels_of(&bot.id).await?;
+
+        let context = BotContext::new(
+            bot.clone(),
+            entry,
+            channels,
+            self.data.clone(),
+            self.config.conversation_idle_timeout(),
+        );
 
         info!(
-            "adding bot #{} to bot cluster of user #{}",
+            "loading bot #{} into bot cluster of user #{}",
             bot.id, bot.user_id
         );
 
@@ -75,14 +87,53 @@ impl BotCluster {
         Ok(())
     }
 
+    pub(super) async fn unload_bot(&self, bot_id: &Key<Bot>) {
+        if self.bots.write().await.remove(bot_id).is_some() {
+            info!(
+                "unloaded bot #{bot_id} from bot cluster of user #{}",
+                self.user_id
+            );
+        }
+    }
+
+    pub(super) async fn is_empty(&self) -> bool {
+        self.bots.read().await.is_empty()
+    }
+
+    pub(super) async fn status_of(
+        &self,
+        bot_id: &Key<Bot>,
+    ) -> Option<BotStatus> {
+        let is_running = self.is_running().await;
+
+        self.bots
+            .read()
+            .await
+            .get(bot_id)
+            .map(|ctx| ctx.status(is_running))
+    }
+
+    pub(super) async fn statuses(&self) -> Vec<(Key<Bot>, BotStatus)> {
+        let is_running = self.is_running().await;
+
+        self.bots
+            .read()
+            .await
+            .iter()
+            .map(|(id, ctx)| (id.clone(), ctx.status(is_running)))
+            .collect()
+    }
+
     pub(super) async fn start(self: Arc<Self>) -> AppResult<()> {
-        if self.watch_task.lock().await.is_some() {
+        let mut watch_task = self.watch_task.lock().await;
+
+        if watch_task.is_some() {
             return Ok(());
         }
 
         let this = self.clone();
 
-        *self.watch_task.lock().await = Some(tokio::spawn(async move {
+        *watch_task = Some(tokio::spawn(async move {
             if let Err(err) = this.clone().watch_user_messages().await {
                 error!(
                     "an error occured while running bot cluster of user #{}: \
@@ -97,54 +148,236 @@ impl BotCluster {
         Ok(())
     }
 
+    pub(super) async fn stop(&self) {
+        if let Some(task) = self.watch_task.lock().await.take() {
+            debug!("stopping bot cluster of user #{}", self.user_id);
+            task.abort();
+        }
+    }
+
     pub(super) async fn is_running(&self) -> bool {
-        self.watch_task.lock().await.is_some()
+        self.watch_task
+            .lock()
+            .await
+            .as_ref()
+            .map_or(false, |task| !task.is_finished())
     }
 
     async fn watch_user_messages(self: Arc<Self>) -> AppResult<()> {
         let mut stream = self.chat_svc.watch_user_chats(&self.user_id).await?;
 
-        while let Some(event) = stream.try_next().await? {
-            match event.kind {
-                | ChatEventKind::MessageAdded {
-                    id,
-                    text,
-                    instance_id,
-                    direction,
-                    created_at,
-                } => {
-                    if let MessageDirection::Outgoing = direction {
-                        continue;
+        // errors of single events are skipped, so that they do not stop the
+        // bots of the user
+        while let Some(event) = stream.next().await {
+            let handled = match event {
+                | Ok(event) => self.handle_event(event).await,
+                | Err(err) => Err(err),
+            };
+
+            if let Err(err) = handled {
+                warn!(
+                    "could not handle chat event on bot cluster of user #{}: \
+                     {err}",
+                    self.user_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_event(&self, event: ChatEvent) -> AppResult<()> {
+        match event.kind {
+            | ChatEventKind::MessageAdded {
+                id,
+                text,
+                attachments: _,
+                reply_to: _,
+                callback_data,
+                instance_id,
+                channel_id,
+                direction,
+                created_at,
+            } => {
+                if let MessageDirection::Outgoing = direction {
+                    return Ok(());
+                }
+
+                // button presses may come without the label of the button
+                let text = match (text, &callback_data) {
+                    | (Some(text), _) => text,
+                    | (None, Some(_)) => String::new(),
+                    | (None, None) => {
+                        info!(
+                            "ignoring empty message from instance #{} on bot \
+                             cluster of user #{}",
+                            instance_id, self.user_id
+                        );
+                        return Ok(());
                     }
+                };
+
+                // chats handed off to human operators are left to them
+                let handoff = self.chat_svc.get_handoff(&event.chat_id).await?;
 
-                    let Some(text) = text else {
-                        info!("ignoring empty message from instance #{} on bot cluster of user #{}", instance_id, self.user_id);
-                        continue;
-                    };
-
-                    let bots = self.bots.read().await;
-
-                    for (bot_id, ctx) in bots.iter() {
-                        if let Some(resp) =
-                            ctx.handle_message(&instance_id, &text).await?
-                        {
-                            info!(
-                                "sending response from bot #{} to instance \
-                                 #{} to message #{} sent at {}",
-                                bot_id, instance_id, id, created_at
-                            );
-
-                            self.chat_svc
-                                .send_message(&event.chat_id, resp)
-                                .await?;
-
-                            break;
-                        }
+                if let ChatHandler::Human = handoff.handler {
+                    debug!(
+                        "ignoring message #{} of chat #{} handled by a human \
+                         operator",
+                        id, event.chat_id
+                    );
+                    return Ok(());
+                }
+
+                let channel_id = match channel_id {
+                    | Some(channel_id) => channel_id,
+                    | None => {
+                        self.data
+                            .link()
+                            .instances()
+                            .get(&instance_id)
+                            .await?
+                            .channel_id
+                    }
+                };
+
+                let bots = self.bots.read().await;
+                let bots =
+                    by_priority(&bots, |ctx| ctx.priority_on(&channel_id));
+
+                let mut resp = None;
+
+                for (bot_id, ctx) in bots.iter() {
+                    let reply = ctx
+                        .handle_message(
+                            &instance_id,
+                            &text,
+                            callback_data.as_deref(),
+                        )
+                        .await?;
+
+                    if let Some(reply) = reply {
+                        resp = Some((*bot_id, reply));
+                        break;
                     }
                 }
-            };
-        }
+
+                // only when no bot matched the message, the first of them
+                // may fall back or re-prompt
+                if resp.is_none() {
+                    if let Some((bot_id, ctx)) = bots.first() {
+                        resp = ctx
+                            .handle_unmatched(&instance_id)
+                            .await?
+                            .map(|reply| (*bot_id, reply));
+                    }
+                }
+
+                let Some((bot_id, reply)) = resp else {
+                    return Ok(());
+                };
+
+                info!(
+                    "sending response from bot #{} to instance #{} to message \
+                     #{} sent at {}",
+                    bot_id, instance_id, id, created_at
+                );
+
+                self.chat_svc
+                    .send_bot_message(
+                        &event.chat_id,
+                        reply.text,
+                        TextFormat::Markdown,
+                        reply.keyboard,
+                    )
+                    .await?;
+
+                if reply.hands_off {
+                    info!(
+                        "bot #{} handed chat #{} off to a human operator",
+                        bot_id, event.chat_id
+                    );
+
+                    self.chat_svc
+                        .set_handler(&event.chat_id, ChatHandler::Human)
+                        .await?;
+                }
+            }
+
+            | ChatEventKind::MessageEdited { .. }
+            | ChatEventKind::MessageDeleted { .. }
+            | ChatEventKind::MessageStatusChanged { .. }
+            | ChatEventKind::HandlerChanged { .. } => {}
+        };
 
         Ok(())
     }
 }
+
+/// Gets the bots handling the messages of a channel, in the order in which
+/// they are tried, given their priorities on the channel.
+fn by_priority<'a, T>(
+    bots: &'a HashMap<Key<Bot>, T>,
+    priority_of: impl Fn(&T) -> Option<i32>,
+) -> Vec<(&'a Key<Bot>, &'a T)> {
+    let mut bots = bots
+        .iter()
+        .filter_map(|(id, ctx)| Some((priority_of(ctx)?, id, ctx)))
+        .collect::<Vec<_>>();
+
+    // bots of the same priority are ordered by id, to keep dispatch stable
+    bots.sort_by_key(|(priority, id, _)| (*priority, id.value()));
+
+    bots.into_iter().map(|(_, id, ctx)| (id, ctx)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn key(n: u128) -> Key<Bot> {
+        Key::new(Uuid::from_u128(n))
+    }
+
+    fn order(bots: &HashMap<Key<Bot>, Option<i32>>) -> Vec<Key<Bot>> {
+        by_priority(bots, |p| *p)
+            .into_iter()
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn orders_bots_by_priority() {
+        let bots = HashMap::from([
+            (key(1), Some(5)),
+            (key(2), Some(1)),
+            (key(3), Some(3)),
+        ]);
+
+        assert_eq!(order(&bots), vec![key(2), key(3), key(1)]);
+    }
+
+    #[test]
+    fn orders_bots_of_same_priority_by_id() {
+        let bots = HashMap::from([
+            (key(3), Some(1)),
+            (key(1), Some(1)),
+            (key(2), Some(0)),
+        ]);
+
+        assert_eq!(order(&bots), vec![key(2), key(1), key(3)]);
+    }
+
+    #[test]
+    fn skips_bots_not_handling_the_channel() {
+        let bots = HashMap::from([
+            (key(1), None),
+            (key(2), Some(i32::MAX)),
+            (key(3), Some(2)),
+        ]);
+
+        assert_eq!(order(&bots), vec![key(3), key(2)]);
+    }
+}
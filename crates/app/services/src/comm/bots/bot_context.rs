@@ -1,53 +1,295 @@
 use std::{collections::HashMap, sync::Arc};
 
+use chrono::{DateTime, Utc};
 use kernel_entities::{
-    entities::{comm::Menu, link::Instance},
+    entities::{
+        comm::{Bot, BotChannel, Menu},
+        link::{Channel, Instance},
+    },
     traits::Key,
 };
-use kernel_repositories::DataStore;
-use kernel_services::error::AppResult;
-use tokio::sync::RwLock;
+use kernel_repositories::{comm::BotConversation, error::RepoError, DataStore};
+use kernel_services::{
+    comm::{bots::BotStatus, error::CommError},
+    error::{AppError, AppResult},
+    link::channels::MessageKeyboard,
+};
 
 use super::menu_traverser::MenuTraverser;
 
+/// Attempts at handling a message whose conversation keeps being changed by
+/// other messages of the same instance.
+const MAX_SAVE_ATTEMPTS: usize = 3;
+
 pub(super) struct BotContext {
     data: Arc<dyn DataStore>,
+    bot: Bot,
     entry: Menu,
-    active: RwLock<HashMap<Key<Instance>, MenuTraverser>>,
+    channels: HashMap<Key<Channel>, i32>,
+    idle_timeout: chrono::Duration,
+    loaded_at: DateTime<Utc>,
+}
+
+pub(super) struct BotReply {
+    pub text: String,
+    pub keyboard: Option<MessageKeyboard>,
+    /// Whether the chat is handed off to a human operator after the reply
+    pub hands_off: bool,
 }
 
 impl BotContext {
-    pub(super) fn new(entry: Menu, data: Arc<dyn DataStore>) -> Self {
+    pub(super) fn new(
+        bot: Bot,
+        entry: Menu,
+        channels: Vec<BotChannel>,
+        data: Arc<dyn DataStore>,
+        idle_timeout: chrono::Duration,
+    ) -> Self {
+        let channels = channels
+            .into_iter()
+            .map(|binding| (binding.channel_id, binding.priority))
+            .collect();
+
         Self {
             data,
+            bot,
             entry,
-            active: Default::default(),
+            channels,
+            idle_timeout,
+            loaded_at: Utc::now(),
         }
     }
 
+    /// Gets the priority of the bot on a channel, if it handles the messages
+    /// of that channel. Bots that are not bound to any channel handle all
+    /// channels of their user, after the bound bots.
+    pub(super) fn priority_on(&self, channel_id: &Key<Channel>) -> Option<i32> {
+        if self.channels.is_empty() {
+            return Some(i32::MAX);
+        }
+
+        self.channels.get(channel_id).copied()
+    }
+
+    pub(super) fn status(&self, is_running: bool) -> BotStatus {
+        BotStatus {
+            loaded_at: self.loaded_at,
+            entry_menu_id: self.entry.id.clone(),
+            is_running,
+        }
+    }
+
+    /// Handles a message of an instance, returning the menu to reply with,
+    /// if it matches any. Button presses are routed by their
+    /// `callback_data`, while texts that match no submenu may still match the
+    /// built-in triggers.
+    ///
+    /// Unmatched messages leave the conversation as is, as they may be
+    /// matched by other bots; see [`Self::handle_unmatched`].
     pub(super) async fn handle_message(
         &self,
         instance_id: &Key<Instance>,
         text: &str,
-    ) -> AppResult<Option<String>> {
-        let active = self.active.read().await;
+        callback_data: Option<&str>,
+    ) -> AppResult<Option<BotReply>> {
+        for _ in 0..MAX_SAVE_ATTEMPTS {
+            let mut conversation = self.get_conversation(instance_id).await?;
+            let traverser = self.traverser_of(&mut conversation).await?;
+
+            let reply = match callback_data {
+                | Some(data) if traverser.process_button(data).await? => {
+                    Some(menu_reply(&traverser).await)
+                }
+                | Some(_) => None,
+                | None if traverser.process(text).await? => {
+                    Some(menu_reply(&traverser).await)
+                }
+                | None => self.process_builtin(&traverser, text).await?,
+            };
+
+            let Some(reply) = reply else {
+                return Ok(None);
+            };
+
+            conversation.unmatched_count = 0;
+            conversation.menu_id = traverser.menu_id().await;
 
-        if !active.contains_key(instance_id) {
-            let traverser =
-                MenuTraverser::new(&self.entry.id, self.data.clone()).await?;
+            if self.save_conversation(&mut conversation).await? {
+                return Ok(Some(reply));
+            }
+        }
+
+        Err(conversation_conflict(instance_id))
+    }
+
+    /// Handles a message of an instance that matched none of the bots of its
+    /// channel, which is only done by the first of them.
+    ///
+    /// Re-prompts the current menu once `reprompt_after` messages in a row
+    /// matched nothing, or else falls back to the fallback reply of the bot,
+    /// if any.
+    pub(super) async fn handle_unmatched(
+        &self,
+        instance_id: &Key<Instance>,
+    ) -> AppResult<Option<BotReply>> {
+        for _ in 0..MAX_SAVE_ATTEMPTS {
+            let mut conversation = self.get_conversation(instance_id).await?;
+            let traverser = self.traverser_of(&mut conversation).await?;
+            let reply =
+                self.reply_unmatched(&mut conversation, &traverser).await;
+
+            // the conversation is saved even without a reply, to keep it alive
+            conversation.menu_id = traverser.menu_id().await;
 
-            self.active
-                .write()
-                .await
-                .insert(instance_id.clone(), traverser);
+            if self.save_conversation(&mut conversation).await? {
+                return Ok(reply);
+            }
         }
 
-        if let Some(traverser) = active.get(instance_id) {
-            if traverser.process(text).await? {
-                return Ok(Some(traverser.to_formatted_string().await));
+        Err(conversation_conflict(instance_id))
+    }
+
+    async fn reply_unmatched(
+        &self,
+        conversation: &mut BotConversation,
+        traverser: &MenuTraverser,
+    ) -> Option<BotReply> {
+        conversation.unmatched_count += 1;
+
+        match self.bot.reprompt_after {
+            | Some(after) if conversation.unmatched_count >= after => {
+                conversation.unmatched_count = 0;
+
+                // the menu was already reached, so it is not handed off again
+                Some(BotReply {
+                    hands_off: false,
+                    ..menu_reply(traverser).await
+                })
             }
+            | _ => self.bot.fallback_reply.clone().map(|text| BotReply {
+                text,
+                keyboard: None,
+                hands_off: false,
+            }),
         }
+    }
+
+    /// Handles the back, home and help triggers of the bot, which are
+    /// available on all menus.
+    async fn process_builtin(
+        &self,
+        traverser: &MenuTraverser,
+        text: &str,
+    ) -> AppResult<Option<BotReply>> {
+        let text = text.trim().to_lowercase();
+        let matches = |trigger: &Option<String>| {
+            trigger.as_ref().is_some_and(|t| t.to_lowercase() == text)
+        };
+
+        if matches(&self.bot.back_trigger) {
+            traverser.back().await?;
+        } else if matches(&self.bot.home_trigger) {
+            traverser.go_to(&self.entry.id).await?;
+        } else if matches(&self.bot.help_trigger) {
+            let builtins = [&self.bot.back_trigger, &self.bot.home_trigger]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            return Ok(Some(BotReply {
+                text: traverser.to_help_string(&builtins).await,
+                keyboard: traverser.to_keyboard().await,
+                hands_off: false,
+            }));
+        } else {
+            return Ok(None);
+        }
+
+        Ok(Some(menu_reply(traverser).await))
+    }
+
+    /// Saves a conversation, unless it was changed meanwhile by another
+    /// message of the instance, in which case `false` is returned.
+    async fn save_conversation(
+        &self,
+        conversation: &mut BotConversation,
+    ) -> AppResult<bool> {
+        let loaded_at =
+            std::mem::replace(&mut conversation.updated_at, Utc::now());
+
+        let saved = self
+            .data
+            .comm()
+            .bot_conversations()
+            .save(conversation, &loaded_at)
+            .await?;
+
+        Ok(saved)
+    }
+
+    /// Gets the stored conversation of an instance, or starts a new one if
+    /// there is none or it was idle for too long.
+    async fn get_conversation(
+        &self,
+        instance_id: &Key<Instance>,
+    ) -> AppResult<BotConversation> {
+        let stored = self
+            .data
+            .comm()
+            .bot_conversations()
+            .get_of(&self.entry.bot_id, instance_id)
+            .await?;
+
+        match stored {
+            | Some(conversation)
+                if Utc::now() - conversation.updated_at < self.idle_timeout =>
+            {
+                Ok(conversation)
+            }
+            // idle conversations are restarted in place, keeping their last
+            // update so that they are not restarted twice concurrently
+            | stored => Ok(BotConversation {
+                bot_id: self.entry.bot_id.clone(),
+                instance_id: instance_id.clone(),
+                menu_id: self.entry.id.clone(),
+                data: HashMap::new(),
+                unmatched_count: 0,
+                updated_at: stored.map_or_else(Utc::now, |c| c.updated_at),
+            }),
+        }
+    }
+
+    async fn traverser_of(
+        &self,
+        conversation: &mut BotConversation,
+    ) -> AppResult<MenuTraverser> {
+        match MenuTraverser::new(&conversation.menu_id, self.data.clone()).await
+        {
+            // menus may be removed while a conversation is at them
+            | Err(AppError::Repo(RepoError::NotFound)) => {
+                conversation.menu_id = self.entry.id.clone();
+                conversation.data.clear();
+
+                MenuTraverser::new(&self.entry.id, self.data.clone()).await
+            }
+            | ret => ret,
+        }
+    }
+}
+
+fn conversation_conflict(instance_id: &Key<Instance>) -> AppError {
+    CommError::InvalidBotState(format!(
+        "conversation of instance #{instance_id} kept changing while handling \
+         a message"
+    ))
+    .into()
+}
 
-        Ok(None)
+async fn menu_reply(traverser: &MenuTraverser) -> BotReply {
+    BotReply {
+        text: traverser.to_formatted_string().await,
+        keyboard: traverser.to_keyboard().await,
+        hands_off: traverser.hands_off().await,
     }
 }
@@ -0,0 +1,16 @@
+use kernel_entities::{
+    entities::{auth::User, comm::Bot},
+    traits::Key,
+};
+use serde::{Deserialize, Serialize};
+
+/// A change of bots made on a node, which is broadcast with the id of the
+/// owner of the bots as key, so that all other nodes reload them from the
+/// data store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct BotControl {
+    pub node_id: String,
+    pub user_id: Key<User>,
+    /// Changed bot, or `None` when all bots of the user changed
+    pub bot_id: Option<Key<Bot>>,
+}
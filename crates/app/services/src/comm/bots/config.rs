@@ -0,0 +1,28 @@
+use serde::Deserialize;
+use validator::Validate;
+
+pub const BOTS_CONFIG_SECTION: &str = "bots";
+
+into_fn!(default_conversation_idle_timeout_minutes: const u64 => 30);
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct BotsConfig {
+    #[validate(range(min = 1))]
+    #[serde(default = "default_conversation_idle_timeout_minutes")]
+    pub conversation_idle_timeout_minutes: u64,
+}
+
+impl BotsConfig {
+    pub fn conversation_idle_timeout(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.conversation_idle_timeout_minutes as i64)
+    }
+}
+
+impl Default for BotsConfig {
+    fn default() -> Self {
+        Self {
+            conversation_idle_timeout_minutes:
+                default_conversation_idle_timeout_minutes(),
+        }
+    }
+}
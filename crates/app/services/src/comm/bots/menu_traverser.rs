@@ -1,13 +1,22 @@
 use std::sync::Arc;
 
 use kernel_entities::{
-    entities::comm::{Menu, TriggerMatchingStrategy},
+    entities::comm::{Menu, MenuContentFormat, TriggerMatchingStrategy},
     traits::Key,
 };
-use kernel_repositories::DataStore;
-use kernel_services::error::AppResult;
+use kernel_repositories::{error::RepoError, DataStore};
+use kernel_services::{
+    error::{AppError, AppResult},
+    link::{
+        channels::{KeyboardButton, MessageKeyboard},
+        rich_text::escape_markdown,
+    },
+};
 use tokio::sync::RwLock;
 
+/// Prefix of the data of menu buttons, followed by the id of the menu.
+const MENU_BUTTON_PREFIX: &str = "menu:";
+
 struct MenuHierarchy {
     menu: Menu,
     sub: Vec<Menu>,
@@ -33,8 +42,7 @@ impl MenuTraverser {
 
     pub(super) async fn process(&self, msg: &str) -> AppResult<bool> {
         if let Some(next_id) = self.get_next_menu(msg).await {
-            *self.current.write().await =
-                Self::get_hierarchy(&next_id, &self.data).await?;
+            self.go_to(&next_id).await?;
 
             return Ok(true);
         }
@@ -42,44 +50,134 @@ impl MenuTraverser {
         Ok(false)
     }
 
+    /// Moves to the menu of a pressed button, returning `false` if the
+    /// button is not one of the bot.
+    ///
+    /// Buttons of earlier menus, which stay in the chat history, are routed
+    /// to their menu as well.
+    pub(super) async fn process_button(&self, data: &str) -> AppResult<bool> {
+        let Some(menu_id) = data
+            .strip_prefix(MENU_BUTTON_PREFIX)
+            .and_then(|id| id.parse::<Key<Menu>>().ok())
+        else {
+            return Ok(false);
+        };
+
+        let hierarchy = match Self::get_hierarchy(&menu_id, &self.data).await {
+            | Ok(hierarchy) => hierarchy,
+            | Err(AppError::Repo(RepoError::NotFound)) => return Ok(false),
+            | Err(err) => return Err(err),
+        };
+
+        let mut current = self.current.write().await;
+
+        if hierarchy.menu.bot_id != current.menu.bot_id {
+            return Ok(false);
+        }
+
+        *current = hierarchy;
+
+        Ok(true)
+    }
+
+    /// Moves to the parent of the current menu; entry menus are their own
+    /// parents.
+    pub(super) async fn back(&self) -> AppResult<()> {
+        let parent_id = self.current.read().await.menu.parent_menu_id.clone();
+
+        self.go_to(&parent_id).await
+    }
+
+    pub(super) async fn go_to(&self, menu_id: &Key<Menu>) -> AppResult<()> {
+        *self.current.write().await =
+            Self::get_hierarchy(menu_id, &self.data).await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn menu_id(&self) -> Key<Menu> {
+        self.current.read().await.menu.id.clone()
+    }
+
+    pub(super) async fn hands_off(&self) -> bool {
+        self.current.read().await.menu.hands_off
+    }
+
+    /// Renders the current menu as markdown, where the content of the menu
+    /// is escaped unless it was authored as markdown.
     pub(super) async fn to_formatted_string(&self) -> String {
         let current = self.current.read().await;
 
-        let mut message = format!("- {}\n", current.menu.title);
+        let mut message =
+            format!("**{}**\n", escape_markdown(&current.menu.title));
 
         if let Some(ref content) = current.menu.content {
-            message += &format!("  {content}\n");
+            match current.menu.content_format {
+                | MenuContentFormat::Plain => {
+                    message += &format!("\n{}\n", escape_markdown(content));
+                }
+                | MenuContentFormat::Markdown => {
+                    message += &format!("\n{content}\n");
+                }
+            }
         }
 
-        if !current.sub.is_empty() {
-            message += "\nSub-Menus:\n";
+        message
+    }
+
+    /// Renders the triggers of the submenus, followed by the given built-in
+    /// triggers, as a markdown list.
+    pub(super) async fn to_help_string(&self, builtins: &[&str]) -> String {
+        let current = self.current.read().await;
 
-            for m in current.sub.iter() {
-                message +=
-                    &format!("- {} (with: {})\n", m.title, m.menu_trigger);
-            }
+        let mut message =
+            format!("**{}**\n", escape_markdown(&current.menu.title));
+
+        for m in current.sub.iter() {
+            message += &format!(
+                "\n- {}: {}",
+                escape_markdown(&m.menu_trigger),
+                escape_markdown(&m.title)
+            );
+        }
+
+        for trigger in builtins {
+            message += &format!("\n- {}", escape_markdown(trigger));
         }
 
         message
     }
 
+    /// Renders the submenus as buttons, one per row.
+    pub(super) async fn to_keyboard(&self) -> Option<MessageKeyboard> {
+        let current = self.current.read().await;
+
+        if current.sub.is_empty() {
+            return None;
+        }
+
+        let rows = current
+            .sub
+            .iter()
+            .map(|m| {
+                vec![KeyboardButton {
+                    text: m.title.clone(),
+                    data: format!("{MENU_BUTTON_PREFIX}{}", m.id),
+                }]
+            })
+            .collect();
+
+        Some(MessageKeyboard::Inline(rows))
+    }
+
     async fn get_next_menu(&self, msg: &str) -> Option<Key<Menu>> {
         let msg = msg.to_lowercase();
         let cur = self.current.read().await;
 
-        for m in cur.sub.iter() {
-            let trigger = m.menu_trigger.to_lowercase();
-            let matches = match m.matching_strategy {
-                | TriggerMatchingStrategy::Full => msg == trigger,
-                | TriggerMatchingStrategy::SubString => msg.contains(&trigger),
-            };
-
-            if matches {
-                return Some(m.id.clone());
-            }
-        }
-
-        None
+        cur.sub
+            .iter()
+            .find(|m| is_triggered_by(m, &msg))
+            .map(|m| m.id.clone())
     }
 
     async fn get_hierarchy(
@@ -96,3 +194,63 @@ impl MenuTraverser {
         Ok(MenuHierarchy { menu, sub })
     }
 }
+
+/// Whether the given lowercase message triggers the menu.
+fn is_triggered_by(menu: &Menu, msg: &str) -> bool {
+    let trigger = menu.menu_trigger.to_lowercase();
+    let matches = match menu.matching_strategy {
+        | TriggerMatchingStrategy::Full => msg == trigger,
+        | TriggerMatchingStrategy::SubString => msg.contains(&trigger),
+    };
+
+    // labels of buttons may be typed back where they are not shown
+    matches || msg == menu.title.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn menu(trigger: &str, strategy: TriggerMatchingStrategy) -> Menu {
+        Menu {
+            id: Key::new(Uuid::from_u128(2)),
+            title: "Opening Hours".into(),
+            content: None,
+            menu_trigger: trigger.into(),
+            matching_strategy: strategy,
+            is_active: true,
+            parent_menu_id: Key::new(Uuid::from_u128(1)),
+            bot_id: Key::new(Uuid::from_u128(1)),
+            hands_off: false,
+            content_format: Default::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn full_trigger_matches_whole_message_only() {
+        let m = menu("Hours", TriggerMatchingStrategy::Full);
+
+        assert!(is_triggered_by(&m, "hours"));
+        assert!(!is_triggered_by(&m, "what are your hours"));
+    }
+
+    #[test]
+    fn substring_trigger_matches_within_message() {
+        let m = menu("Hours", TriggerMatchingStrategy::SubString);
+
+        assert!(is_triggered_by(&m, "what are your hours?"));
+        assert!(!is_triggered_by(&m, "prices"));
+    }
+
+    #[test]
+    fn typed_back_title_matches() {
+        let m = menu("1", TriggerMatchingStrategy::Full);
+
+        assert!(is_triggered_by(&m, "opening hours"));
+    }
+}
@@ -1,64 +1,358 @@
+pub mod config;
+
 mod bot_cluster;
 mod bot_context;
+mod bot_control;
 mod menu_traverser;
 
 use std::{collections::HashMap, sync::Arc};
 
-use futures::TryStreamExt;
-use kernel_entities::{entities::auth::User, traits::Key};
-use kernel_repositories::DataStore;
+use futures::{StreamExt, TryStreamExt};
+use kernel_entities::{
+    entities::{auth::User, comm::Bot},
+    traits::Key,
+};
+use kernel_repositories::{comm::UpdateBot, error::RepoError, DataStore};
 use kernel_services::{
-    comm::{bots::BotsService, chats::ChatsService},
-    error::AppResult,
+    comm::{
+        bots::{BotStatus, BotsService},
+        chats::ChatsService,
+        error::CommError,
+    },
+    error::{AppError, AppResult},
+    link::message_passing::{MessagePassingService, TopicReader, TopicWriter},
     Service,
 };
 use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use self::{
+    bot_cluster::BotCluster,
+    bot_control::BotControl,
+    config::BotsConfig,
+};
 
-use self::bot_cluster::BotCluster;
+type BotClusters = HashMap<Key<User>, Arc<BotCluster>>;
+
+const CONTROL_TOPIC_NAME: &str = "bot_control";
 
 pub struct AppBotsService {
     data: Arc<dyn DataStore>,
     chats_svc: Arc<dyn ChatsService>,
-    clusters: RwLock<HashMap<Key<User>, Arc<BotCluster>>>,
+    config: BotsConfig,
+    node_id: String,
+    control_tx: Arc<dyn TopicWriter<BotControl>>,
+    control_rx: Arc<dyn TopicReader<BotControl>>,
+    // held for writing throughout changes, so that they are serialized
+    clusters: RwLock<BotClusters>,
 }
 
 #[async_trait::async_trait]
-impl BotsService for AppBotsService {}
+impl BotsService for AppBotsService {
+    async fn status(&self, id: &Key<Bot>) -> AppResult<Option<BotStatus>> {
+        for cluster in self.clusters.read().await.values() {
+            if let Some(status) = cluster.status_of(id).await {
+                return Ok(Some(status));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn status_of(
+        &self,
+        user_id: &Key<User>,
+    ) -> AppResult<Vec<(Key<Bot>, BotStatus)>> {
+        let Some(cluster) = self.clusters.read().await.get(user_id).cloned()
+        else {
+            return Ok(Vec::new());
+        };
+
+        Ok(cluster.statuses().await)
+    }
+
+    async fn start_bots_of(&self, user_id: &Key<User>) -> AppResult<()> {
+        self.data.comm().bots().set_active_of(user_id, true).await?;
+
+        self.reload_bots_of(user_id).await
+    }
+
+    async fn stop_bots_of(&self, user_id: &Key<User>) -> AppResult<()> {
+        self.data
+            .comm()
+            .bots()
+            .set_active_of(user_id, false)
+            .await?;
+
+        self.reload_bots_of(user_id).await
+    }
+
+    async fn reload_bots_of(&self, user_id: &Key<User>) -> AppResult<()> {
+        self.reload_local_bots_of(user_id).await?;
+
+        self.broadcast(user_id, None).await
+    }
+
+    async fn start_bot(
+        &self,
+        user_id: &Key<User>,
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()> {
+        let mut bot = self.data.comm().bots().get_of(user_id, bot_id).await?;
+
+        self.set_active(&bot, true).await?;
+        bot.is_active = true;
+
+        let mut clusters = self.clusters.write().await;
+
+        let is_loaded = match clusters.get(user_id) {
+            | Some(cluster) => cluster.status_of(bot_id).await.is_some(),
+            | None => false,
+        };
+
+        let ret = if is_loaded {
+            Ok(())
+        } else {
+            self.load_bot_into(&mut clusters, &bot).await
+        };
+
+        Self::refresh_cluster(&mut clusters, user_id).await?;
+        drop(clusters);
+
+        self.broadcast(user_id, Some(bot_id)).await?;
+
+        ret
+    }
+
+    async fn stop_bot(
+        &self,
+        user_id: &Key<User>,
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()> {
+        let mut clusters = self.clusters.write().await;
+
+        if let Some(cluster) = clusters.get(user_id) {
+            cluster.unload_bot(bot_id).await;
+        }
+
+        Self::refresh_cluster(&mut clusters, user_id).await?;
+        drop(clusters);
+
+        match self.data.comm().bots().get_of(user_id, bot_id).await {
+            | Ok(bot) => self.set_active(&bot, false).await?,
+            | Err(RepoError::NotFound) => {}
+            | Err(err) => return Err(err.into()),
+        }
+
+        self.broadcast(user_id, Some(bot_id)).await
+    }
+
+    async fn reload_bot(
+        &self,
+        user_id: &Key<User>,
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()> {
+        let ret = self.reload_local_bot(user_id, bot_id).await;
+
+        self.broadcast(user_id, Some(bot_id)).await?;
+
+        ret
+    }
+}
 
 impl AppBotsService {
-    pub fn new(
+    pub async fn create<IPC: MessagePassingService>(
         data: Arc<dyn DataStore>,
         chats_svc: Arc<dyn ChatsService>,
-    ) -> Self {
-        Self {
+        ipc: &IPC,
+        config: BotsConfig,
+    ) -> AppResult<Self> {
+        Ok(Self {
             data,
             chats_svc,
+            config,
+            node_id: Uuid::new_v4().to_string(),
+            control_tx: ipc.get_topic_writer(CONTROL_TOPIC_NAME).await?,
+            control_rx: ipc.get_topic_reader(CONTROL_TOPIC_NAME).await?,
             clusters: Default::default(),
+        })
+    }
+
+    /// Unloads the bots of a user on this node, and loads the active ones
+    /// again.
+    async fn reload_local_bots_of(&self, user_id: &Key<User>) -> AppResult<()> {
+        let mut clusters = self.clusters.write().await;
+
+        if let Some(cluster) = clusters.remove(user_id) {
+            cluster.stop().await;
         }
+
+        let mut bots =
+            self.data.comm().bots().stream_active_of(user_id.clone());
+
+        while let Some(bot) = bots.try_next().await? {
+            if let Err(err) = self.load_bot_into(&mut clusters, &bot).await {
+                warn!("could not load bot #{}: {err}", bot.id);
+            }
+        }
+
+        Self::refresh_cluster(&mut clusters, user_id).await
     }
 
-    async fn ensure_clustre_created(
+    async fn reload_local_bot(
         &self,
         user_id: &Key<User>,
-    ) -> AppResult<Arc<BotCluster>> {
-        if let Some(cluster) = self.clusters.read().await.get(user_id) {
-            return Ok(cluster.clone());
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()> {
+        let mut clusters = self.clusters.write().await;
+
+        if let Some(cluster) = clusters.get(user_id) {
+            cluster.unload_bot(bot_id).await;
         }
 
-        debug!("creating bot cluster for user #{user_id}");
+        let ret = match self.data.comm().bots().get_of(user_id, bot_id).await {
+            | Ok(bot) if bot.is_active => {
+                match self.load_bot_into(&mut clusters, &bot).await {
+                    // bots are loaded once they are given an entry menu
+                    | Err(AppError::Comm(CommError::InvalidBotState(_))) => {
+                        Ok(())
+                    }
+                    | ret => ret,
+                }
+            }
+            | Ok(_) | Err(RepoError::NotFound) => Ok(()),
+            | Err(err) => Err(err.into()),
+        };
+
+        Self::refresh_cluster(&mut clusters, user_id).await?;
 
-        let cluster = Arc::new(BotCluster::new(
-            self.data.clone(),
-            user_id,
-            self.chats_svc.clone(),
-        ));
+        ret
+    }
 
-        self.clusters
-            .write()
+    /// Notifies the other nodes that bots of a user changed, so that they
+    /// reload them as well.
+    async fn broadcast(
+        &self,
+        user_id: &Key<User>,
+        bot_id: Option<&Key<Bot>>,
+    ) -> AppResult<()> {
+        let control = BotControl {
+            node_id: self.node_id.clone(),
+            user_id: user_id.clone(),
+            bot_id: bot_id.cloned(),
+        };
+
+        self.control_tx
+            .broadcast(&user_id.to_string(), &control)
             .await
-            .insert(user_id.clone(), cluster.clone());
+    }
+
+    /// Reloads the bots changed on other nodes.
+    fn spawn_control_listener(self: Arc<Self>) {
+        tokio::spawn(async move {
+            if let Err(err) = self.listen_controls().await {
+                error!("stopped listening to changes of bots: {err}");
+            }
+        });
+    }
+
+    async fn listen_controls(&self) -> AppResult<()> {
+        let mut controls = self.control_rx.mirror("*").await?;
+
+        while let Some(control) = controls.next().await {
+            let control = match control {
+                | Ok(control) => control,
+                | Err(err) => {
+                    warn!("could not read bot change: {err}");
+                    continue;
+                }
+            };
+
+            if control.node_id == self.node_id {
+                continue;
+            }
+
+            let BotControl {
+                user_id, bot_id, ..
+            } = control;
+
+            let ret = match bot_id {
+                | Some(ref bot_id) => {
+                    self.reload_local_bot(&user_id, bot_id).await
+                }
+                | None => self.reload_local_bots_of(&user_id).await,
+            };
+
+            if let Err(err) = ret {
+                warn!("could not reload bots of #{user_id}: {err}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn load_bot_into(
+        &self,
+        clusters: &mut BotClusters,
+        bot: &Bot,
+    ) -> AppResult<()> {
+        let cluster =
+            clusters.entry(bot.user_id.clone()).or_insert_with(|| {
+                debug!("creating bot cluster for user #{}", bot.user_id);
+
+                Arc::new(BotCluster::new(
+                    self.data.clone(),
+                    &bot.user_id,
+                    self.chats_svc.clone(),
+                    self.config.clone(),
+                ))
+            });
+
+        cluster.load_bot(bot).await
+    }
 
-        Ok(cluster)
+    /// Persists whether a bot is active, so that bots started or stopped
+    /// explicitly stay so across reloads and restarts.
+    async fn set_active(&self, bot: &Bot, is_active: bool) -> AppResult<()> {
+        if bot.is_active == is_active {
+            return Ok(());
+        }
+
+        let model = UpdateBot::new(
+            bot.name.clone(),
+            is_active,
+            bot.fallback_reply.clone(),
+            bot.back_trigger.clone(),
+            bot.home_trigger.clone(),
+            bot.help_trigger.clone(),
+            bot.reprompt_after,
+        );
+
+        self.data.comm().bots().update(&bot.id, model).await?;
+
+        Ok(())
+    }
+
+    /// Starts the bot cluster of a user, or stops and drops it if it has no
+    /// bots loaded.
+    async fn refresh_cluster(
+        clusters: &mut BotClusters,
+        user_id: &Key<User>,
+    ) -> AppResult<()> {
+        let Some(cluster) = clusters.get(user_id) else {
+            return Ok(());
+        };
+
+        if cluster.is_empty().await {
+            cluster.stop().await;
+            clusters.remove(user_id);
+
+            return Ok(());
+        }
+
+        debug!("starting bot cluster for user #{user_id}");
+
+        cluster.clone().start().await
     }
 }
 
@@ -67,30 +361,30 @@ impl Service for AppBotsService {
     async fn initialize(self: Arc<Self>) -> AppResult<()> {
         debug!("loading bots");
 
+        let mut clusters = self.clusters.write().await;
         let mut bots = self.data.comm().bots().stream_active();
 
         while let Some(bot) = bots.try_next().await? {
-            let cluster = self.ensure_clustre_created(&bot.user_id).await?;
-
-            cluster.append_bot(bot).await?;
+            if let Err(err) = self.load_bot_into(&mut clusters, &bot).await {
+                warn!("could not load bot #{}: {err}", bot.id);
+            }
         }
 
         debug!("starting bot clusters");
 
-        let clusters = self.clusters.read().await;
-
-        for (user_id, cluster) in clusters.iter() {
-            if !cluster.is_running().await {
-                continue;
-            }
-
-            debug!("starting bot cluster for user #{user_id}");
+        let user_ids = clusters.keys().cloned().collect::<Vec<_>>();
 
-            if let Err(err) = cluster.clone().start().await {
+        for user_id in user_ids {
+            if let Err(err) =
+                Self::refresh_cluster(&mut clusters, &user_id).await
+            {
                 warn!("error starting bot cluster: {err}")
             }
         }
 
+        drop(clusters);
+        self.spawn_control_listener();
+
         Ok(())
     }
 }
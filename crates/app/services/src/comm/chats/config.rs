@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use validator::Validate;
+
+pub const CHATS_CONFIG_SECTION: &str = "chats";
+
+into_fn!(default_human_idle_timeout_minutes: const u64 => 30);
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct ChatsConfig {
+    #[serde(default)]
+    pub instances_quota_policy: InstancesQuotaPolicy,
+    #[validate(range(min = 1))]
+    #[serde(default = "default_human_idle_timeout_minutes")]
+    pub human_idle_timeout_minutes: u64,
+}
+
+impl ChatsConfig {
+    pub fn human_idle_timeout(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.human_idle_timeout_minutes as i64)
+    }
+}
+
+impl Default for ChatsConfig {
+    fn default() -> Self {
+        Self {
+            instances_quota_policy: Default::default(),
+            human_idle_timeout_minutes: default_human_idle_timeout_minutes(),
+        }
+    }
+}
+
+/// What happens to updates of new contacts of a channel that already has
+/// `max_instances` instances.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstancesQuotaPolicy {
+    /// Updates are dropped, and no instance is created.
+    #[default]
+    Drop,
+    /// Updates are rejected to the dead-letter queue, so that they can be
+    /// replayed once the quota is raised.
+    DeadLetter,
+    /// Instances are created beyond the quota, with a warning.
+    Allow,
+}
@@ -1,80 +1,258 @@
-use std::sync::Arc;
+pub mod config;
 
-use chrono::Utc;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
 use futures::{stream::BoxStream, StreamExt, TryStreamExt};
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Chat, ChatState, MessageDirection},
+        comm::{
+            Attachment,
+            Chat,
+            ChatHandler,
+            ChatState,
+            Message,
+            MessageDirection,
+            MessageStatus,
+        },
         link::{Channel, Instance},
     },
     traits::Key,
 };
 use kernel_repositories::{
-    comm::{InsertChat, InsertMessage},
+    comm::{InsertChat, InsertMessage, MessageChange},
     error::RepoError,
-    link::InsertInstance,
+    link::{InsertInstance, RefreshInstance},
     DataStore, DocumentStore,
 };
 use kernel_services::{
-    comm::chats::{ChatEvent, ChatEventKind, ChatsService},
+    comm::{
+        chats::{ChatEvent, ChatEventKind, ChatHandoff, ChatsService},
+        error::CommError,
+    },
     error::AppResult,
-    link::channels::{
-        ChannelPipe, ChannelsService, IncomingChannelUpdate,
-        IncomingChannelUpdateKind, IncomingMessageUpdateKind,
-        OutgoingChannelUpdate, OutgoingChannelUpdateKind,
-        OutgoingMessageUpdateKind,
+    link::{
+        channels::{
+            ChannelLifecycleEvent, ChannelPipe, ChannelsService,
+            IncomingChannelUpdate, IncomingChannelUpdateKind,
+            IncomingMessageUpdateKind, MessageKeyboard, OutgoingChannelUpdate,
+            OutgoingChannelUpdateKind, OutgoingMessageUpdateKind, PlatformGroup,
+            SenderProfile,
+        },
+        error::LinkError,
+        rich_text::TextFormat,
     },
+    storage::blob::{blob_key_of, channel_of_blob_key},
     Service,
 };
 use tokio::sync::Mutex;
 
+use self::config::{ChatsConfig, InstancesQuotaPolicy};
+
+/// Interval at which idle handoffs are handed back to bots.
+const HANDOFF_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct AppChatsService {
     data: Arc<dyn DataStore>,
     docs: Arc<dyn DocumentStore>,
     channels_svc: Arc<dyn ChannelsService>,
+    config: ChatsConfig,
     read_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 #[async_trait::async_trait]
 impl ChatsService for AppChatsService {
     async fn send_message(
+        &self,
+        chat_id: &Key<Chat>,
+        text: Option<String>,
+        format: TextFormat,
+        attachments: Vec<Attachment>,
+        reply_to: Option<Key<Message>>,
+        keyboard: Option<MessageKeyboard>,
+    ) -> AppResult<()> {
+        if text.is_none() && attachments.is_empty() {
+            return Err(LinkError::InvalidParams(
+                "message must have text or attachments".into(),
+            )
+            .into());
+        }
+
+        let chat = self.docs.chats().get(chat_id).await?;
+
+        self.check_attachments(&chat, &attachments).await?;
+
+        let reply_to = match reply_to {
+            | Some(id) => {
+                Some(self.docs.messages().get_of(chat_id, &id).await?)
+            }
+            | None => None,
+        };
+
+        // the chat is handed off before sending, so that bots do not answer
+        // messages arriving in the meantime
+        self.docs
+            .chats()
+            .set_handler(chat_id, ChatHandler::Human, Utc::now())
+            .await?;
+
+        self.send_update(chat, text, format, attachments, reply_to, keyboard)
+            .await
+    }
+
+    async fn send_bot_message(
         &self,
         chat_id: &Key<Chat>,
         text: String,
+        format: TextFormat,
+        keyboard: Option<MessageKeyboard>,
     ) -> AppResult<()> {
         let chat = self.docs.chats().get(chat_id).await?;
 
-        self.send_update(chat, text).await
+        self.send_update(chat, Some(text), format, Vec::new(), None, keyboard)
+            .await
+    }
+
+    async fn edit_message(
+        &self,
+        chat_id: &Key<Chat>,
+        message_id: &Key<Message>,
+        text: String,
+        format: TextFormat,
+    ) -> AppResult<()> {
+        let (message, platform_message_id) =
+            self.get_modifiable_message(chat_id, message_id).await?;
+
+        self.publish_to_instance(
+            &message,
+            OutgoingMessageUpdateKind::Edit {
+                platform_message_id,
+                content: text.clone(),
+                format,
+            },
+        )
+        .await?;
+
+        self.docs
+            .messages()
+            .update_text(message_id, Some(text))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_message(
+        &self,
+        chat_id: &Key<Chat>,
+        message_id: &Key<Message>,
+    ) -> AppResult<()> {
+        let (message, platform_message_id) =
+            self.get_modifiable_message(chat_id, message_id).await?;
+
+        self.publish_to_instance(
+            &message,
+            OutgoingMessageUpdateKind::Delete {
+                platform_message_id,
+            },
+        )
+        .await?;
+
+        self.docs
+            .messages()
+            .mark_deleted(message_id, Utc::now())
+            .await?;
+
+        Ok(())
     }
 
     async fn watch_user_chats(
         &self,
         user_id: &Key<User>,
     ) -> AppResult<BoxStream<'static, AppResult<ChatEvent>>> {
-        Ok(self
+        let messages =
+            self.docs.chats().watch_all_of(user_id).await?.map(|c| {
+                let (chat_id, kind) = match c? {
+                    | MessageChange::Created(message) => (
+                        message.chat_id,
+                        ChatEventKind::MessageAdded {
+                            id: message.id,
+                            text: message.text,
+                            attachments: message.attachments,
+                            reply_to: message.reply_to,
+                            callback_data: message.callback_data,
+                            instance_id: message.instance_id,
+                            channel_id: message.channel_id,
+                            direction: message.direction,
+                            created_at: message.created_at,
+                        },
+                    ),
+                    | MessageChange::Edited(message) => (
+                        message.chat_id,
+                        ChatEventKind::MessageEdited {
+                            id: message.id,
+                            text: message.text,
+                            instance_id: message.instance_id,
+                            updated_at: message.updated_at,
+                        },
+                    ),
+                    | MessageChange::Deleted(message) => (
+                        message.chat_id,
+                        ChatEventKind::MessageDeleted {
+                            id: message.id,
+                            instance_id: message.instance_id,
+                            deleted_at: message
+                                .deleted_at
+                                .unwrap_or(message.updated_at),
+                        },
+                    ),
+                    | MessageChange::StatusChanged(message) => (
+                        message.chat_id,
+                        ChatEventKind::MessageStatusChanged {
+                            id: message.id,
+                            instance_id: message.instance_id,
+                            status: message.status,
+                            updated_at: message.updated_at,
+                        },
+                    ),
+                };
+
+                Ok(ChatEvent { chat_id, kind })
+            });
+
+        let handlers = self
             .docs
             .chats()
-            .watch_all_of(user_id)
+            .watch_handlers_of(user_id)
             .await?
-            .map(|m| {
-                let message = match m {
-                    | Ok(message) => message,
-                    | Err(err) => return Err(err.into()),
-                };
+            .map(|c| Ok(handler_changed_of(c?)));
 
-                Ok(ChatEvent {
-                    chat_id: message.chat_id,
-                    kind: ChatEventKind::MessageAdded {
-                        id: message.id,
-                        text: message.text,
-                        instance_id: message.instance_id,
-                        direction: message.direction,
-                        created_at: message.created_at,
-                    },
-                })
-            })
-            .boxed())
+        Ok(futures::stream::select(messages, handlers).boxed())
+    }
+
+    async fn get_handoff(&self, chat_id: &Key<Chat>) -> AppResult<ChatHandoff> {
+        let chat = self.docs.chats().get(chat_id).await?;
+        let handoff = self.handoff_of(chat.handler, chat.human_active_at);
+
+        // the handoff may have expired since the last sweep
+        match handoff.resumes_at {
+            | Some(resumes_at) if resumes_at <= Utc::now() => {
+                self.set_handler(chat_id, ChatHandler::Bot).await
+            }
+            | _ => Ok(handoff),
+        }
+    }
+
+    async fn set_handler(
+        &self,
+        chat_id: &Key<Chat>,
+        handler: ChatHandler,
+    ) -> AppResult<ChatHandoff> {
+        let now = Utc::now();
+
+        self.docs.chats().set_handler(chat_id, handler, now).await?;
+
+        Ok(self.handoff_of(handler, Some(now)))
     }
 }
 
@@ -83,62 +261,334 @@ impl AppChatsService {
         data: Arc<dyn DataStore>,
         docs: Arc<dyn DocumentStore>,
         channels_svc: Arc<dyn ChannelsService>,
+        config: ChatsConfig,
     ) -> AppResult<Self> {
         Ok(Self {
             data,
             docs,
             channels_svc,
+            config,
             read_task: Default::default(),
         })
     }
 
+    /// Periodically hands the chats of idle human operators back to bots,
+    /// which notifies the watchers of their handlers.
+    fn spawn_handoff_sweeper(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HANDOFF_SWEEP_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let idle_since = Utc::now() - self.config.human_idle_timeout();
+                let resumed =
+                    self.docs.chats().resume_idle_handoffs(idle_since).await;
+
+                match resumed {
+                    | Ok(0) => {}
+                    | Ok(count) => {
+                        debug!("handed {count} idle chats back to bots")
+                    }
+                    | Err(err) => {
+                        warn!("could not resume idle handoffs: {err:#?}")
+                    }
+                }
+            }
+        });
+    }
+
+    fn handoff_of(
+        &self,
+        handler: ChatHandler,
+        human_active_at: Option<DateTime<Utc>>,
+    ) -> ChatHandoff {
+        let resumes_at = match handler {
+            | ChatHandler::Bot => None,
+            | ChatHandler::Human => {
+                human_active_at.map(|at| at + self.config.human_idle_timeout())
+            }
+        };
+
+        ChatHandoff {
+            handler,
+            resumes_at,
+        }
+    }
+
     pub(super) async fn send_update(
         &self,
         chat: Chat,
-        text: String,
+        text: Option<String>,
+        format: TextFormat,
+        attachments: Vec<Attachment>,
+        reply_to: Option<Message>,
+        keyboard: Option<MessageKeyboard>,
     ) -> AppResult<()> {
-        let instances = self
+        let mut instances = self
             .data
             .link()
             .instances()
             .get_members_of(&chat.id)
             .await?;
 
+        // participants of a group share a single platform chat, which is sent
+        // to once, preferably through the author of the replied-to message
+        if let Some(target) = reply_to.as_ref() {
+            if let Some(idx) =
+                instances.iter().position(|i| i.id == target.instance_id)
+            {
+                instances.swap(0, idx);
+            }
+        }
+
+        let mut groups = HashSet::new();
+
+        instances.retain(|instance| match instance.platform_group_id {
+            | Some(group_id) => {
+                groups.insert((instance.channel_id.clone(), group_id))
+            }
+            | None => true,
+        });
+
         for instance in instances {
             let ChannelPipe { tx, rx: _ } = self
                 .channels_svc
                 .get_pipe_of(&chat.user_id, Some(&instance.channel_id))
                 .await?;
 
-            tx.publish(&OutgoingChannelUpdate {
-                user_id: chat.user_id.clone(),
-                channel_id: instance.channel_id,
-                kind: OutgoingChannelUpdateKind::Message {
-                    platform_user_id: instance.platform_identifier,
-                    kind: OutgoingMessageUpdateKind::New {
-                        content: text.clone(),
-                    },
-                    timestamp: Utc::now(),
-                },
-            })
-            .await?;
+            // replies can only be threaded on the platform of the instance
+            // the replied-to message was exchanged with
+            let reply_to_platform_id = reply_to
+                .as_ref()
+                .filter(|target| target.instance_id == instance.id)
+                .and_then(|target| target.platform_message_id.clone());
 
-            self.docs
+            // messages stay pending until the channel reports their status
+            let message = self
+                .docs
                 .messages()
                 .create(InsertMessage {
-                    text: Some(text.clone()),
+                    text: text.clone(),
+                    attachments: attachments.clone(),
                     direction: MessageDirection::Outgoing,
+                    status: MessageStatus::Pending,
                     user_id: chat.user_id.clone(),
                     chat_id: chat.id.clone(),
                     instance_id: instance.id,
-                    delivered_at: Utc::now(),
+                    channel_id: instance.channel_id.clone(),
+                    platform_message_id: None,
+                    reply_to: reply_to.as_ref().map(|target| target.id.clone()),
+                    callback_data: None,
+                    idempotency_key: None,
+                    delivered_at: None,
                 })
                 .await?;
+
+            let ret = tx
+                .publish(&OutgoingChannelUpdate {
+                    user_id: chat.user_id.clone(),
+                    channel_id: instance.channel_id,
+                    idempotency_key: None,
+                    kind: OutgoingChannelUpdateKind::Message {
+                        platform_user_id: instance.platform_identifier,
+                        group_id: instance.platform_group_id,
+                        kind: OutgoingMessageUpdateKind::New {
+                            message_id: message.id.clone(),
+                            content: text.clone(),
+                            format,
+                            attachments: attachments.clone(),
+                            reply_to: reply_to_platform_id,
+                            keyboard: keyboard.clone(),
+                        },
+                        timestamp: Utc::now(),
+                    },
+                })
+                .await;
+
+            if let Err(err) = ret {
+                self.docs
+                    .messages()
+                    .update_status(
+                        &message.id,
+                        MessageStatus::Failed,
+                        None,
+                        Utc::now(),
+                    )
+                    .await?;
+
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_modifiable_message(
+        &self,
+        chat_id: &Key<Chat>,
+        message_id: &Key<Message>,
+    ) -> AppResult<(Message, String)> {
+        let message = self.docs.messages().get_of(chat_id, message_id).await?;
+
+        if !matches!(message.direction, MessageDirection::Outgoing) {
+            return Err(LinkError::InvalidParams(
+                "only outgoing messages can be modified".into(),
+            )
+            .into());
+        }
+
+        if message.deleted_at.is_some() {
+            return Err(LinkError::InvalidParams(
+                "message was already deleted".into(),
+            )
+            .into());
+        }
+
+        // platform ids are needed to reference the message on the platform
+        let Some(platform_message_id) = message.platform_message_id.clone()
+        else {
+            return Err(LinkError::InvalidParams(format!(
+                "message #{} has no platform id",
+                message.id
+            ))
+            .into());
+        };
+
+        Ok((message, platform_message_id))
+    }
+
+    async fn publish_to_instance(
+        &self,
+        message: &Message,
+        kind: OutgoingMessageUpdateKind,
+    ) -> AppResult<()> {
+        let instance = self
+            .data
+            .link()
+            .instances()
+            .get(&message.instance_id)
+            .await?;
+
+        let ChannelPipe { tx, rx: _ } = self
+            .channels_svc
+            .get_pipe_of(&message.user_id, Some(&instance.channel_id))
+            .await?;
+
+        tx.publish(&OutgoingChannelUpdate {
+            user_id: message.user_id.clone(),
+            channel_id: instance.channel_id,
+            idempotency_key: None,
+            kind: OutgoingChannelUpdateKind::Message {
+                platform_user_id: instance.platform_identifier,
+                group_id: instance.platform_group_id,
+                kind,
+                timestamp: Utc::now(),
+            },
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Makes a newly created chat the chat of a platform group, unless the
+    /// first messages of the group were handled concurrently and another
+    /// chat was set meanwhile, in which case that one is used instead.
+    async fn claim_group_chat(
+        &self,
+        channel_id: &Key<Channel>,
+        group_id: i64,
+        chat_id: Key<Chat>,
+    ) -> AppResult<Key<Chat>> {
+        let group_chat_id = self
+            .data
+            .link()
+            .instances()
+            .set_group_chat(channel_id, group_id, &chat_id)
+            .await?;
+
+        if group_chat_id != chat_id {
+            debug!("group #{group_id} already has chat #{group_chat_id}");
+
+            self.remove_unused_chat(&chat_id).await;
+        }
+
+        Ok(group_chat_id)
+    }
+
+    async fn remove_unused_chat(&self, chat_id: &Key<Chat>) {
+        self.docs
+            .chats()
+            .remove(chat_id)
+            .await
+            .unwrap_or_else(|err| {
+                warn!("could not remove unused chat #{chat_id}: {err:#?}")
+            });
+    }
+
+    /// Ensures that stored attachments were stored for channels of the chat
+    /// owner, as their contents would otherwise be leaked to the chat.
+    async fn check_attachments(
+        &self,
+        chat: &Chat,
+        attachments: &[Attachment],
+    ) -> AppResult<()> {
+        for attachment in attachments {
+            let Some(key) = blob_key_of(&attachment.uri) else {
+                continue;
+            };
+
+            let is_owned = match channel_of_blob_key(key) {
+                | Some(channel_id) => match self
+                    .data
+                    .link()
+                    .channels()
+                    .get_of(&chat.user_id, &channel_id)
+                    .await
+                {
+                    | Ok(_) => true,
+                    | Err(RepoError::NotFound) => false,
+                    | Err(err) => return Err(err.into()),
+                },
+                | None => false,
+            };
+
+            if !is_owned {
+                return Err(LinkError::InvalidParams(format!(
+                    "attachment `{}` is not accessible",
+                    attachment.uri
+                ))
+                .into());
+            }
         }
 
         Ok(())
     }
 
+    async fn get_platform_message(
+        &self,
+        instance_id: &Key<Instance>,
+        platform_message_id: &str,
+    ) -> AppResult<Option<Message>> {
+        match self
+            .docs
+            .messages()
+            .get_by_platform_id(instance_id, platform_message_id)
+            .await
+        {
+            | Ok(message) => Ok(Some(message)),
+            | Err(RepoError::NotFound) => {
+                warn!(
+                    "message #{platform_message_id} of instance \
+                     #{instance_id} was not found"
+                );
+
+                Ok(None)
+            }
+            | Err(err) => Err(err.into()),
+        }
+    }
+
     async fn handle_incoming(
         &self,
         update: IncomingChannelUpdate,
@@ -151,6 +601,8 @@ impl AppChatsService {
         match update.kind {
             | IncomingChannelUpdateKind::Message {
                 platform_user_id,
+                group,
+                profile,
                 kind,
                 timestamp,
             } => {
@@ -159,6 +611,9 @@ impl AppChatsService {
                         &update.user_id,
                         &update.channel_id,
                         platform_user_id,
+                        group,
+                        profile,
+                        timestamp,
                     )
                     .await?;
 
@@ -167,25 +622,261 @@ impl AppChatsService {
                 };
 
                 match kind {
-                    | IncomingMessageUpdateKind::New { content } => {
-                        let message = self
+                    | IncomingMessageUpdateKind::New {
+                        platform_message_id,
+                        content,
+                        attachments,
+                        reply_to,
+                    } => {
+                        let reply_to = match reply_to {
+                            | Some(id) => self
+                                .get_platform_message(&instance.id, &id)
+                                .await?
+                                .map(|target| target.id),
+                            | None => None,
+                        };
+
+                        let ret = self
                             .docs
                             .messages()
                             .create(InsertMessage {
-                                text: content.clone(),
+                                text: content,
+                                attachments,
                                 direction: MessageDirection::Incoming,
-                                delivered_at: timestamp,
+                                status: MessageStatus::Delivered,
+                                delivered_at: Some(timestamp),
                                 user_id: update.user_id,
                                 chat_id: instance.chat_id,
                                 instance_id: instance.id.clone(),
+                                channel_id: instance.channel_id.clone(),
+                                platform_message_id: Some(platform_message_id),
+                                reply_to,
+                                callback_data: None,
+                                idempotency_key: update.idempotency_key,
                             })
-                            .await?;
+                            .await;
+
+                        // redelivered updates are rejected by the repo
+                        let message = match ret {
+                            | Ok(message) => message,
+                            | Err(RepoError::AlreadyExists) => {
+                                debug!("skipping already processed message");
+                                return Ok(());
+                            }
+                            | Err(err) => return Err(err.into()),
+                        };
 
                         debug!(
                             "message from instance #{} saved with #{}",
                             instance.id, message.id
                         );
                     }
+
+                    // button presses are kept as incoming messages replying
+                    // to the message of the button, so that they show up in
+                    // the chat and reach the bots watching it
+                    | IncomingMessageUpdateKind::ButtonPressed {
+                        callback_id: _,
+                        platform_message_id,
+                        text,
+                        data,
+                    } => {
+                        let reply_to = match platform_message_id {
+                            | Some(id) => self
+                                .get_platform_message(&instance.id, &id)
+                                .await?
+                                .map(|target| target.id),
+                            | None => None,
+                        };
+
+                        let ret = self
+                            .docs
+                            .messages()
+                            .create(InsertMessage {
+                                // presses without a known label have no text
+                                text,
+                                attachments: Vec::new(),
+                                direction: MessageDirection::Incoming,
+                                status: MessageStatus::Delivered,
+                                delivered_at: Some(timestamp),
+                                user_id: update.user_id,
+                                chat_id: instance.chat_id,
+                                instance_id: instance.id.clone(),
+                                channel_id: instance.channel_id.clone(),
+                                platform_message_id: None,
+                                reply_to,
+                                callback_data: Some(data),
+                                idempotency_key: update.idempotency_key,
+                            })
+                            .await;
+
+                        match ret {
+                            | Ok(message) => debug!(
+                                "button press of instance #{} saved with #{}",
+                                instance.id, message.id
+                            ),
+                            | Err(RepoError::AlreadyExists) => {
+                                debug!("skipping already processed press");
+                            }
+                            | Err(err) => return Err(err.into()),
+                        }
+                    }
+
+                    | IncomingMessageUpdateKind::Edited {
+                        platform_message_id,
+                        content,
+                    } => {
+                        let Some(message) = self
+                            .get_platform_message(
+                                &instance.id,
+                                &platform_message_id,
+                            )
+                            .await?
+                        else {
+                            return Ok(());
+                        };
+
+                        if message.text == content {
+                            debug!("message #{} is up to date", message.id);
+                            return Ok(());
+                        }
+
+                        self.docs
+                            .messages()
+                            .update_text(&message.id, content)
+                            .await?;
+
+                        debug!("message #{} was edited", message.id);
+                    }
+
+                    | IncomingMessageUpdateKind::Deleted {
+                        platform_message_id,
+                    } => {
+                        let Some(message) = self
+                            .get_platform_message(
+                                &instance.id,
+                                &platform_message_id,
+                            )
+                            .await?
+                        else {
+                            return Ok(());
+                        };
+
+                        if message.deleted_at.is_some() {
+                            debug!(
+                                "message #{} is already deleted",
+                                message.id
+                            );
+                            return Ok(());
+                        }
+
+                        self.docs
+                            .messages()
+                            .mark_deleted(&message.id, timestamp)
+                            .await?;
+
+                        debug!("message #{} was deleted", message.id);
+                    }
+                }
+            }
+
+            | IncomingChannelUpdateKind::MessageStatus {
+                platform_user_id,
+                message_id,
+                platform_message_id,
+                status,
+                timestamp,
+            } => {
+                let message = match message_id {
+                    | Some(id) => self.docs.messages().get(&id).await?,
+                    | None => {
+                        let Some(id) = platform_message_id.as_deref() else {
+                            warn!("skipping status of an unidentified message");
+                            return Ok(());
+                        };
+
+                        let instance = match self
+                            .data
+                            .link()
+                            .instances()
+                            .get_by_platform_identifier(
+                                &update.channel_id,
+                                None,
+                                platform_user_id,
+                            )
+                            .await
+                        {
+                            | Ok(instance) => instance,
+                            | Err(RepoError::NotFound) => {
+                                warn!("skipping status of an unknown instance");
+                                return Ok(());
+                            }
+                            | Err(err) => return Err(err.into()),
+                        };
+
+                        // statuses may arrive before the sent message is
+                        // recorded, in which case they are redelivered later
+                        match self
+                            .docs
+                            .messages()
+                            .get_by_platform_id(&instance.id, id)
+                            .await
+                        {
+                            | Ok(message) => message,
+                            | Err(RepoError::NotFound) => {
+                                return Err(CommError::UnknownMessage(
+                                    format!(
+                                        "message #{id} of instance #{} is not \
+                                         recorded yet",
+                                        instance.id
+                                    ),
+                                )
+                                .into());
+                            }
+                            | Err(err) => return Err(err.into()),
+                        }
+                    }
+                };
+
+                if !message.status.can_transition_to(status) {
+                    debug!(
+                        "ignoring status `{status}` of message #{} with \
+                         status `{}`",
+                        message.id, message.status
+                    );
+
+                    return Ok(());
+                }
+
+                self.docs
+                    .messages()
+                    .update_status(
+                        &message.id,
+                        status,
+                        platform_message_id,
+                        timestamp,
+                    )
+                    .await?;
+
+                debug!("message #{} is now {status}", message.id);
+            }
+
+            | IncomingChannelUpdateKind::Lifecycle { event, timestamp } => {
+                info!(
+                    "channel #{} of user #{} emitted `{event}` at {timestamp}",
+                    update.channel_id, update.user_id
+                );
+
+                match event {
+                    // expired channels are deactivated, so that they show as
+                    // such until they are renewed and activated again
+                    | ChannelLifecycleEvent::Expired => {
+                        self.data
+                            .link()
+                            .channels()
+                            .deactivate(&update.channel_id)
+                            .await?;
+                    }
                 }
             }
         };
@@ -198,54 +889,211 @@ impl AppChatsService {
         user_id: &Key<User>,
         channel_id: &Key<Channel>,
         identifier: i64,
+        group: Option<PlatformGroup>,
+        profile: SenderProfile,
+        timestamp: DateTime<Utc>,
     ) -> AppResult<Option<Instance>> {
+        let group_id = group.as_ref().map(|group| group.id);
         let ret = self
             .data
             .link()
             .instances()
-            .get_by_platform_identifier(channel_id, identifier)
+            .get_by_platform_identifier(channel_id, group_id, identifier)
             .await;
 
         if let Err(RepoError::NotFound) = ret {
             info!("a new instance was detected on channel #{channel_id}");
 
-            if !self.data.link().channels().exists(channel_id).await? {
-                warn!("could not create instance because channel #{} of user #{} does not exist", channel_id, user_id);
+            let channel =
+                match self.data.link().channels().get(channel_id).await {
+                    | Ok(channel) => channel,
+                    | Err(RepoError::NotFound) => {
+                        warn!(
+                            "could not create instance because channel #{} of \
+                             user #{} does not exist",
+                            channel_id, user_id
+                        );
+                        return Ok(None);
+                    }
+                    | Err(err) => return Err(err.into()),
+                };
+
+            if !self.check_instances_quota(&channel).await? {
                 return Ok(None);
             }
 
-            debug!("creating a chat for the new instance");
+            // participants of a group join the chat of the group, if any
+            let group_chat_id = match group_id {
+                | Some(group_id) => match self
+                    .data
+                    .link()
+                    .instances()
+                    .get_group_chat(channel_id, group_id)
+                    .await
+                {
+                    | Ok(chat_id) => Some(chat_id),
+                    | Err(RepoError::NotFound) => None,
+                    | Err(err) => return Err(err.into()),
+                },
+                | None => None,
+            };
 
-            let chat = self
-                .docs
-                .chats()
-                .create(InsertChat {
-                    label: None,
-                    state: ChatState::Active,
-                    user_id: user_id.clone(),
-                })
-                .await?;
+            let chat_id = match group_chat_id {
+                | Some(chat_id) => chat_id,
+                | None => {
+                    debug!("creating a chat for the new instance");
 
-            debug!("creating instance record in chat #{}", chat.id);
-
-            let instance = self
-                .data
-                .link()
-                .instances()
-                .create(InsertInstance {
-                    platform_identifier: identifier,
-                    username: None,
-                    display_name: None,
-                    phone_number: None,
-                    chat_id: chat.id.clone(),
-                    channel_id: channel_id.clone(),
-                })
-                .await?;
+                    let chat_id = self
+                        .docs
+                        .chats()
+                        .create(InsertChat {
+                            label: group.and_then(|group| group.title),
+                            state: ChatState::Active,
+                            user_id: user_id.clone(),
+                        })
+                        .await?
+                        .id;
+
+                    match group_id {
+                        | Some(group_id) => {
+                            self.claim_group_chat(channel_id, group_id, chat_id)
+                                .await?
+                        }
+                        | None => chat_id,
+                    }
+                }
+            };
+
+            debug!("creating instance record in chat #{chat_id}");
+
+            let model = InsertInstance {
+                platform_identifier: identifier,
+                platform_group_id: group_id,
+                username: profile.username,
+                display_name: profile.display_name,
+                phone_number: profile.phone_number,
+                last_active: Some(timestamp),
+                chat_id: chat_id.clone(),
+                channel_id: channel_id.clone(),
+            };
+
+            let instances = self.data.link().instances();
+
+            // the quota is enforced again while inserting, as other instances
+            // may have been created since it was checked
+            let instance = match channel.max_instances {
+                | Some(max_instances)
+                    if !matches!(
+                        self.config.instances_quota_policy,
+                        InstancesQuotaPolicy::Allow
+                    ) =>
+                {
+                    let created = instances
+                        .create_within_quota(model, max_instances)
+                        .await?;
+
+                    let Some(instance) = created else {
+                        // chats of groups are kept for their other members
+                        if group_id.is_none() {
+                            self.remove_unused_chat(&chat_id).await;
+                        }
+
+                        self.apply_instances_quota(&channel, max_instances)?;
+
+                        return Ok(None);
+                    };
+
+                    instance
+                }
+                | _ => instances.create(model).await?,
+            };
 
             return Ok(Some(instance));
         }
 
-        Ok(Some(ret?))
+        let instance = ret?;
+
+        // a stale profile should not prevent the update from being handled
+        if let Err(err) = self
+            .data
+            .link()
+            .instances()
+            .refresh(
+                &instance.id,
+                RefreshInstance {
+                    username: profile.username,
+                    display_name: profile.display_name,
+                    phone_number: profile.phone_number,
+                    last_active: timestamp,
+                },
+            )
+            .await
+        {
+            warn!("could not refresh instance #{}: {err:#?}", instance.id);
+        }
+
+        Ok(Some(instance))
+    }
+
+    /// Checks whether a new instance can be added to `channel`, applying the
+    /// configured policy once its `max_instances` quota is reached.
+    async fn check_instances_quota(
+        &self,
+        channel: &Channel,
+    ) -> AppResult<bool> {
+        let Some(max_instances) = channel.max_instances else {
+            return Ok(true);
+        };
+
+        let count = self
+            .data
+            .link()
+            .instances()
+            .get_count_of(&channel.id)
+            .await?;
+
+        if (count as i64) < max_instances {
+            return Ok(true);
+        }
+
+        self.apply_instances_quota(channel, max_instances)
+    }
+
+    /// Applies the configured policy to a new instance of `channel`, which
+    /// reached its quota, returning whether the instance can be created.
+    fn apply_instances_quota(
+        &self,
+        channel: &Channel,
+        max_instances: i64,
+    ) -> AppResult<bool> {
+        match self.config.instances_quota_policy {
+            | InstancesQuotaPolicy::Drop => {
+                warn!(
+                    "channel #{} reached its quota of {max_instances} \
+                     instances, dropping update",
+                    channel.id
+                );
+
+                Ok(false)
+            }
+            | InstancesQuotaPolicy::DeadLetter => {
+                Err(LinkError::InvalidChannelState(format!(
+                    "channel #{} reached its quota of {max_instances} \
+                     instances",
+                    channel.id
+                ))
+                .into())
+            }
+            | InstancesQuotaPolicy::Allow => {
+                warn!(
+                    "channel #{} exceeded its quota of {max_instances} \
+                     instances",
+                    channel.id
+                );
+
+                Ok(true)
+            }
+        }
     }
 }
 
@@ -289,7 +1137,9 @@ impl Service for AppChatsService {
                         error!(
                             "an error occured while handling update: {err:#?}"
                         );
-                        confirm.nack(true).await
+
+                        // only transient failures are worth a redelivery
+                        confirm.nack(err.is_transient()).await
                     }
                 } {
                     error!("could not ack/nack IPC message: {err:#?}");
@@ -297,6 +1147,18 @@ impl Service for AppChatsService {
             }
         }));
 
+        self.spawn_handoff_sweeper();
+
         Ok(())
     }
 }
+
+fn handler_changed_of(chat: Chat) -> ChatEvent {
+    ChatEvent {
+        chat_id: chat.id,
+        kind: ChatEventKind::HandlerChanged {
+            handler: chat.handler,
+            updated_at: chat.updated_at,
+        },
+    }
+}
@@ -0,0 +1,20 @@
+use kernel_entities::{
+    entities::{auth::User, link::Channel},
+    traits::Key,
+};
+use serde::{Deserialize, Serialize};
+
+/// A command on a channel running on another node, which is published with
+/// the id of the node owning the lease of the channel as key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct ChannelControl {
+    pub user_id: Key<User>,
+    pub channel_id: Key<Channel>,
+    pub command: ChannelCommand,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(super) enum ChannelCommand {
+    Stop,
+    Restart,
+}
@@ -1,23 +1,35 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use chrono::{DateTime, Utc};
 use kernel_entities::entities::link::{Channel, ChannelPlatform};
+use kernel_repositories::DataStore;
 use kernel_services::{
     error::AppResult,
-    link::channels::{IncomingChannelUpdate, ReverseChannelPipe},
+    link::channels::ReverseChannelPipe,
+    storage::blob::BlobStorageService,
 };
-use tokio_stream::StreamExt;
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 
 use super::{
-    channel_stream::ChannelStream, telegram::telegram_stream::TelegramStream,
+    channel_stream::ChannelStream,
+    channel_supervisor::{ChannelHealth, ChannelSupervisor},
+    config::{ChannelsConfig, SupervisorConfig},
+    rate_limiter::RateLimiter,
+    send_queue::SendQueue,
+    telegram::telegram_stream::TelegramStream,
+    whatsapp::whatsapp_stream::WhatsAppStream,
 };
 
 pub(super) struct ChannelState {
     channel: Channel,
     stream: Arc<dyn ChannelStream>,
+    blobs: Arc<dyn BlobStorageService>,
     pipe: ReverseChannelPipe,
     cancellation: CancellationToken,
+    health: Arc<Mutex<ChannelHealth>>,
+    queue: SendQueue,
+    supervisor: SupervisorConfig,
     started_at: DateTime<Utc>,
 }
 
@@ -25,81 +37,57 @@ impl ChannelState {
     pub(super) fn new(
         channel: Channel,
         pipe: ReverseChannelPipe,
+        data: Arc<dyn DataStore>,
+        blobs: Arc<dyn BlobStorageService>,
+        config: &ChannelsConfig,
     ) -> AppResult<Self> {
-        let stream = match channel.platform {
-            | ChannelPlatform::Telegram => TelegramStream::new(&channel)?,
+        let stream = create_stream(&channel, data, blobs.clone(), config)?;
+        let cancellation = CancellationToken::new();
+        let health: Arc<Mutex<ChannelHealth>> = Default::default();
+
+        let rate_limit = &config.rate_limit;
+        let queue = SendQueue {
+            channel: channel.clone(),
+            stream: stream.clone(),
+            pipe: pipe.clone(),
+            cancellation: cancellation.clone(),
+            health: health.clone(),
+            limiter: Arc::new(RateLimiter::new(
+                rate_limit.of(channel.platform).clone(),
+            )),
+            pending: Arc::new(Semaphore::new(rate_limit.max_pending)),
+            chats: Default::default(),
         };
 
         Ok(Self {
             channel,
             stream,
+            blobs,
             pipe,
-            cancellation: CancellationToken::new(),
+            cancellation,
+            health,
+            queue,
+            supervisor: config.supervisor.clone(),
             started_at: Utc::now(),
         })
     }
 
     pub(super) async fn run(&self) -> AppResult<()> {
-        let (channel, stream, pipe, cancellation) = (
-            self.channel.clone(),
-            self.stream.clone(),
-            self.pipe.clone(),
-            self.cancellation.clone(),
-        );
-
-        tokio::spawn(async move {
-            let Ok(mut outgoing_stream) = pipe.rx.subscribe_manual().await else {
-                error!("could not subscribe to channel IPC pipe");
-                return;
-            };
-
-            while !cancellation.is_cancelled() {
-                tokio::select! {
-                    Some(Ok((update, confirm))) = outgoing_stream.next() => {
-
-                        if update.user_id != channel.user_id || update.channel_id != channel.id {
-                            warn!("mismatching channel info found in update, skipping");
-                            confirm.nack(true).await.unwrap_or_else(|err| {
-                                error!("could not nack ipc message: {err:#?}");
-                            });
-                        } else {
-                             match stream.send(update.kind).await {
-                                Ok(_) => confirm.ack().await,
-                                Err(err) => {
-                                    warn!("could not send outgoing update: {err:#?}");
-                                    confirm.nack(true).await
-                                }
-                            }.unwrap_or_else(|err| error!("failed to send ack/nack: {err:#?}"));
-                        }
-                    },
-
-                    Ok(update_kind) = stream.recv() => {
-                        let update = IncomingChannelUpdate {
-                            user_id: channel.user_id.clone(),
-                            channel_id: channel.id.clone(),
-                            kind: update_kind,
-                        };
-
-                        if let Err(err) = pipe.tx.publish(&update).await {
-                            warn!("could not publish update: {err:#?}");
-                        }
-                    }
-
-                _ = cancellation.cancelled() => {
-                        debug!("handler stopped due to cancellation");
-                        break;
-                    }
-
-                else => {
-                        debug!("handler stopped due to unexpected error");
-                        break;
-                    }
-                };
-            }
-
-            cancellation.cancelled().await;
+        self.stream.initialize().await?;
+
+        let supervisor = Arc::new(ChannelSupervisor {
+            channel: self.channel.clone(),
+            stream: self.stream.clone(),
+            blobs: self.blobs.clone(),
+            pipe: self.pipe.clone(),
+            cancellation: self.cancellation.clone(),
+            health: self.health.clone(),
+            queue: self.queue.clone(),
+            config: self.supervisor.clone(),
         });
 
+        tokio::spawn(supervisor.supervise());
+
         Ok(())
     }
 
@@ -115,4 +103,28 @@ impl ChannelState {
     pub(super) fn channel(&self) -> &Channel {
         &self.channel
     }
+
+    pub(super) fn health(&self) -> ChannelHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    pub(super) fn is_degraded(&self) -> bool {
+        self.health.lock().unwrap().failures >= self.supervisor.degraded_after
+    }
+}
+
+pub(super) fn create_stream(
+    channel: &Channel,
+    data: Arc<dyn DataStore>,
+    blobs: Arc<dyn BlobStorageService>,
+    config: &ChannelsConfig,
+) -> AppResult<Arc<dyn ChannelStream>> {
+    Ok(match channel.platform {
+        | ChannelPlatform::Telegram => {
+            TelegramStream::new(channel, data, blobs, config)?
+        }
+        | ChannelPlatform::WhatsApp => {
+            WhatsAppStream::new(channel, blobs, &config.whatsapp)?
+        }
+    })
 }
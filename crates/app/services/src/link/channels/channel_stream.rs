@@ -1,10 +1,28 @@
 use kernel_services::{
     error::AppResult,
     link::channels::{IncomingChannelUpdateKind, OutgoingChannelUpdateKind},
+    storage::blob::Blob,
 };
 
 #[async_trait::async_trait]
 pub(super) trait ChannelStream: Send + Sync {
+    async fn initialize(&self) -> AppResult<()> {
+        Ok(())
+    }
+
     async fn recv(&self) -> AppResult<IncomingChannelUpdateKind>;
-    async fn send(&self, update: OutgoingChannelUpdateKind) -> AppResult<()>;
+
+    /// Sends an update, returning the platform id of the sent message, if any.
+    async fn send(
+        &self,
+        update: OutgoingChannelUpdateKind,
+    ) -> AppResult<Option<String>>;
+
+    async fn fetch_media(&self, uri: &str) -> AppResult<Blob>;
+
+    /// Parses the payload of a webhook call of the platform into updates.
+    async fn parse_webhook_payload(
+        &self,
+        payload: &[u8],
+    ) -> AppResult<Vec<IncomingChannelUpdateKind>>;
 }
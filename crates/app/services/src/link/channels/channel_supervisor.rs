@@ -0,0 +1,258 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use kernel_entities::entities::link::Channel;
+use kernel_services::{
+    error::{AppError, AppResult},
+    link::{
+        channels::{
+            IncomingChannelUpdate,
+            IncomingChannelUpdateKind,
+            OutgoingChannelUpdate,
+            ReverseChannelPipe,
+        },
+        error::LinkError,
+        message_passing::MessageConfirmation,
+    },
+    storage::blob::BlobStorageService,
+};
+use tokio::{sync::OwnedSemaphorePermit, time::Instant};
+use tokio_stream::StreamExt;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    channel_stream::ChannelStream,
+    config::SupervisorConfig,
+    media::store_incoming_media,
+    send_queue::SendQueue,
+};
+
+#[derive(Clone, Debug, Default)]
+pub(super) struct ChannelHealth {
+    pub restarts: u32,
+    pub failures: u32,
+    pub last_error: Option<String>,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+/// Runs the update handler of a channel, restarting it with an exponential
+/// backoff whenever it exits unexpectedly, until the channel is stopped.
+pub(super) struct ChannelSupervisor {
+    pub channel: Channel,
+    pub stream: Arc<dyn ChannelStream>,
+    pub blobs: Arc<dyn BlobStorageService>,
+    pub pipe: ReverseChannelPipe,
+    pub cancellation: CancellationToken,
+    pub health: Arc<Mutex<ChannelHealth>>,
+    pub queue: SendQueue,
+    pub config: SupervisorConfig,
+}
+
+impl ChannelHealth {
+    pub(super) fn record_activity(&mut self) {
+        self.failures = 0;
+        self.last_activity = Some(Utc::now());
+    }
+}
+
+impl ChannelSupervisor {
+    pub(super) async fn supervise(self: Arc<Self>) {
+        loop {
+            // the handler runs in its own task, so that a panic in it is
+            // treated as a failure rather than ending the supervisor
+            let handler = tokio::spawn({
+                let this = self.clone();
+                async move { this.handle_updates().await }
+            });
+
+            let err = match handler.await {
+                | Ok(Ok(())) => {
+                    debug!("handler of channel #{} stopped", self.channel.id);
+                    break;
+                }
+                | Ok(Err(err)) => err,
+                | Err(err) => LinkError::InternalError(anyhow::anyhow!(
+                    "handler panicked: {err}"
+                ))
+                .into(),
+            };
+
+            if !self.restart_after(err).await {
+                break;
+            }
+        }
+    }
+
+    /// Waits for the backoff of the failure, then re-initializes the stream,
+    /// until that succeeds. Returns `false` if the channel was stopped
+    /// meanwhile.
+    async fn restart_after(&self, mut err: AppError) -> bool {
+        loop {
+            let failures = self.record_failure(&err);
+            let backoff = self.config.backoff_of(failures);
+
+            warn!(
+                "handler of channel #{} failed ({failures} in a row), \
+                 restarting in {}ms: {err}",
+                self.channel.id,
+                backoff.as_millis(),
+            );
+
+            if failures == self.config.degraded_after {
+                error!("channel #{} is degraded", self.channel.id);
+            }
+
+            tokio::select! {
+                _ = self.cancellation.cancelled() => return false,
+                _ = tokio::time::sleep(backoff) => {}
+            }
+
+            self.health.lock().unwrap().restarts += 1;
+
+            match self.stream.initialize().await {
+                | Ok(()) => return true,
+                | Err(e) => err = e,
+            }
+        }
+    }
+
+    async fn handle_updates(&self) -> AppResult<()> {
+        let mut outgoing_stream = self.pipe.rx.subscribe_manual().await?;
+        let mut recv_failures = 0;
+
+        // the failures of a restarted handler are only reset by activity
+        // otherwise, which channels receiving webhooks may not have, so they
+        // are also reset once it ran for a while without failing to receive
+        let healthy_after = Duration::from_millis(self.config.healthy_after_ms);
+        let recovery = tokio::time::sleep(healthy_after);
+        let mut is_recovered = false;
+
+        tokio::pin!(recovery);
+
+        loop {
+            tokio::select! {
+                _ = self.cancellation.cancelled() => return Ok(()),
+
+                _ = &mut recovery, if !is_recovered => {
+                    is_recovered = true;
+                    self.health.lock().unwrap().failures = 0;
+                }
+
+                // updates are only read once there is room to queue them,
+                // leaving the rest in the message queue meanwhile, while
+                // incoming updates are still received
+                update = async {
+                    let permit = self.queue.acquire_slot().await;
+
+                    (outgoing_stream.next().await, permit)
+                } => match update {
+                    | (Some(Ok((update, confirm))), permit) => {
+                        self.handle_outgoing(update, confirm, permit).await;
+                    }
+                    | (Some(Err(err)), _) => {
+                        warn!("could not read outgoing update: {err:#?}");
+                    }
+                    | (None, _) => {
+                        return Err(LinkError::MessagePassing(anyhow::anyhow!(
+                            "outgoing updates stream ended"
+                        ))
+                        .into());
+                    }
+                },
+
+                update = self.stream.recv() => match update {
+                    | Ok(update_kind) => {
+                        recv_failures = 0;
+                        // an update that could not be published ends the
+                        // handler, so that the restarted stream resumes from
+                        // the last published one rather than skipping it
+                        self.handle_incoming(update_kind).await?;
+                    }
+                    | Err(err) => {
+                        recv_failures += 1;
+                        recovery
+                            .as_mut()
+                            .reset(Instant::now() + healthy_after);
+
+                        if recv_failures >= self.config.max_recv_failures {
+                            return Err(err);
+                        }
+
+                        let backoff = self.config.backoff_of(recv_failures);
+
+                        warn!(
+                            "could not receive channel update, retrying in \
+                             {}ms: {err:#?}",
+                            backoff.as_millis(),
+                        );
+                        self.health.lock().unwrap().last_error =
+                            Some(err.to_string());
+
+                        tokio::select! {
+                            _ = self.cancellation.cancelled() => return Ok(()),
+                            _ = tokio::time::sleep(backoff) => {}
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    async fn handle_outgoing(
+        &self,
+        update: OutgoingChannelUpdate,
+        confirm: Arc<dyn MessageConfirmation>,
+        permit: OwnedSemaphorePermit,
+    ) {
+        let channel = &self.channel;
+
+        if update.user_id != channel.user_id || update.channel_id != channel.id
+        {
+            warn!("mismatching channel info found in update, skipping");
+            confirm.nack(false).await.unwrap_or_else(|err| {
+                error!("could not nack ipc message: {err:#?}");
+            });
+
+            return;
+        }
+
+        self.queue.enqueue(update.kind, confirm, permit);
+    }
+
+    async fn handle_incoming(
+        &self,
+        mut update_kind: IncomingChannelUpdateKind,
+    ) -> AppResult<()> {
+        self.health.lock().unwrap().record_activity();
+
+        store_incoming_media(
+            &*self.blobs,
+            &*self.stream,
+            &self.channel,
+            &mut update_kind,
+        )
+        .await;
+
+        let update = IncomingChannelUpdate::new(
+            self.channel.user_id.clone(),
+            self.channel.id.clone(),
+            update_kind,
+        );
+
+        self.pipe.tx.publish(&update).await?;
+
+        Ok(())
+    }
+
+    fn record_failure(&self, err: &AppError) -> u32 {
+        let mut health = self.health.lock().unwrap();
+
+        health.failures += 1;
+        health.last_error = Some(err.to_string());
+
+        health.failures
+    }
+}
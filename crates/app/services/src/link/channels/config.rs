@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use kernel_entities::{
+    entities::link::{Channel, ChannelPlatform},
+    traits::Key,
+};
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+
+pub const CHANNELS_CONFIG_SECTION: &str = "channels";
+
+into_fn!(default_whatsapp_api_url: String => "https://graph.facebook.com".to_string());
+into_fn!(default_whatsapp_api_version: String => "v16.0".to_string());
+into_fn!(default_whatsapp_timeout_seconds: const u64 => 30);
+into_fn!(default_lease_ttl_seconds: const u64 => 30);
+into_fn!(default_lease_renew_interval_seconds: const u64 => 10);
+into_fn!(default_supervisor_max_recv_failures: const u32 => 5);
+into_fn!(default_supervisor_degraded_after: const u32 => 5);
+into_fn!(default_supervisor_initial_backoff_ms: const u64 => 1000);
+into_fn!(default_supervisor_max_backoff_ms: const u64 => 5 * 60 * 1000);
+into_fn!(default_supervisor_healthy_after_ms: const u64 => 60 * 1000);
+into_fn!(default_rate_limit_max_pending: const usize => 256);
+
+#[derive(Clone, Debug, Default, Deserialize, Validate)]
+pub struct ChannelsConfig {
+    #[validate(url)]
+    pub webhook_base_url: Option<String>,
+
+    #[validate]
+    #[serde(default)]
+    pub whatsapp: WhatsAppConfig,
+
+    #[validate]
+    #[serde(default)]
+    pub lease: LeaseConfig,
+
+    #[validate]
+    #[serde(default)]
+    pub supervisor: SupervisorConfig,
+
+    #[validate]
+    #[serde(default)]
+    pub rate_limit: RateLimitsConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct WhatsAppConfig {
+    #[validate(url)]
+    #[serde(default = "default_whatsapp_api_url")]
+    pub api_url: String,
+
+    #[validate(length(min = 1))]
+    #[serde(default = "default_whatsapp_api_version")]
+    pub api_version: String,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_whatsapp_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_lease_renewal"))]
+pub struct LeaseConfig {
+    #[validate(length(min = 1, max = 64))]
+    pub node_id: Option<String>,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_lease_ttl_seconds")]
+    pub ttl_seconds: u64,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_lease_renew_interval_seconds")]
+    pub renew_interval_seconds: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct SupervisorConfig {
+    #[validate(range(min = 1))]
+    #[serde(default = "default_supervisor_max_recv_failures")]
+    pub max_recv_failures: u32,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_supervisor_degraded_after")]
+    pub degraded_after: u32,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_supervisor_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_supervisor_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+
+    #[validate(range(min = 1))]
+    #[serde(default = "default_supervisor_healthy_after_ms")]
+    pub healthy_after_ms: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct RateLimitsConfig {
+    #[validate(range(min = 1))]
+    #[serde(default = "default_rate_limit_max_pending")]
+    pub max_pending: usize,
+
+    #[validate]
+    #[serde(default = "RateLimitConfig::telegram")]
+    pub telegram: RateLimitConfig,
+
+    #[validate]
+    #[serde(default = "RateLimitConfig::whatsapp")]
+    pub whatsapp: RateLimitConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct RateLimitConfig {
+    #[validate(range(min = 0.001))]
+    pub per_second: f64,
+
+    #[validate(range(min = 1))]
+    pub burst: u32,
+
+    #[validate(range(min = 0.001))]
+    pub chat_per_second: f64,
+
+    #[validate(range(min = 1))]
+    pub chat_burst: u32,
+
+    pub max_retries: u32,
+}
+
+fn validate_lease_renewal(config: &LeaseConfig) -> Result<(), ValidationError> {
+    if config.renew_interval_seconds >= config.ttl_seconds {
+        return Err(ValidationError::new("renew_interval_not_below_ttl"));
+    }
+
+    Ok(())
+}
+
+impl ChannelsConfig {
+    pub fn webhook_url_of(&self, channel_id: &Key<Channel>) -> Option<String> {
+        self.webhook_base_url.as_ref().map(|base| {
+            format!(
+                "{}/api/link/channels/{channel_id}/webhook",
+                base.trim_end_matches('/')
+            )
+        })
+    }
+}
+
+impl RateLimitsConfig {
+    pub fn of(&self, platform: ChannelPlatform) -> &RateLimitConfig {
+        match platform {
+            | ChannelPlatform::Telegram => &self.telegram,
+            | ChannelPlatform::WhatsApp => &self.whatsapp,
+        }
+    }
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_pending: default_rate_limit_max_pending(),
+            telegram: RateLimitConfig::telegram(),
+            whatsapp: RateLimitConfig::whatsapp(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Telegram allows about 30 messages per second per bot, and about one
+    /// message per second per chat.
+    fn telegram() -> Self {
+        Self {
+            per_second: 30.0,
+            burst: 30,
+            chat_per_second: 1.0,
+            chat_burst: 3,
+            max_retries: 3,
+        }
+    }
+
+    /// WhatsApp Cloud API allows about 80 messages per second per phone
+    /// number, with a lower limit on messages to the same user.
+    fn whatsapp() -> Self {
+        Self {
+            per_second: 80.0,
+            burst: 80,
+            chat_per_second: 1.0,
+            chat_burst: 5,
+            max_retries: 3,
+        }
+    }
+}
+
+impl Default for LeaseConfig {
+    fn default() -> Self {
+        Self {
+            node_id: None,
+            ttl_seconds: default_lease_ttl_seconds(),
+            renew_interval_seconds: default_lease_renew_interval_seconds(),
+        }
+    }
+}
+
+impl SupervisorConfig {
+    /// Delay before restarting a channel after `failures` consecutive
+    /// failures.
+    pub fn backoff_of(&self, failures: u32) -> Duration {
+        let factor = 2u64.saturating_pow(failures.saturating_sub(1));
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms);
+
+        Duration::from_millis(backoff)
+    }
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            max_recv_failures: default_supervisor_max_recv_failures(),
+            degraded_after: default_supervisor_degraded_after(),
+            initial_backoff_ms: default_supervisor_initial_backoff_ms(),
+            max_backoff_ms: default_supervisor_max_backoff_ms(),
+            healthy_after_ms: default_supervisor_healthy_after_ms(),
+        }
+    }
+}
+
+impl Default for WhatsAppConfig {
+    fn default() -> Self {
+        Self {
+            api_url: default_whatsapp_api_url(),
+            api_version: default_whatsapp_api_version(),
+            timeout_seconds: default_whatsapp_timeout_seconds(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use kernel_entities::entities::{comm::Attachment, link::Channel};
+use kernel_services::{
+    error::AppResult,
+    link::channels::{IncomingChannelUpdateKind, IncomingMessageUpdateKind},
+    storage::blob::{
+        blob_key_of,
+        blob_uri_of,
+        new_channel_blob_key,
+        BlobStorageService,
+    },
+};
+
+use super::channel_stream::ChannelStream;
+
+fn attachments_of(
+    update: &mut IncomingChannelUpdateKind,
+) -> Option<&mut Vec<Attachment>> {
+    match update {
+        | IncomingChannelUpdateKind::Message {
+            kind: IncomingMessageUpdateKind::New { attachments, .. },
+            ..
+        } => Some(attachments),
+        | IncomingChannelUpdateKind::Message { .. }
+        | IncomingChannelUpdateKind::MessageStatus { .. }
+        | IncomingChannelUpdateKind::Lifecycle { .. } => None,
+    }
+}
+
+pub(super) async fn store_incoming_media(
+    blobs: &dyn BlobStorageService,
+    stream: &dyn ChannelStream,
+    channel: &Channel,
+    update: &mut IncomingChannelUpdateKind,
+) {
+    let Some(attachments) = attachments_of(update) else {
+        return;
+    };
+
+    for attachment in attachments {
+        if blob_key_of(&attachment.uri).is_some() {
+            continue;
+        }
+
+        let key = new_channel_blob_key(&channel.id);
+
+        match store_media(blobs, stream, &key, &attachment.uri).await {
+            | Ok(()) => attachment.uri = blob_uri_of(&key),
+            | Err(err) => {
+                // the platform reference is kept, so the attachment can
+                // still be resolved later on
+                warn!(
+                    "could not store media `{}` of channel #{}: {err}",
+                    attachment.uri, channel.id
+                );
+            }
+        }
+    }
+}
+
+async fn store_media(
+    blobs: &dyn BlobStorageService,
+    stream: &dyn ChannelStream,
+    key: &str,
+    uri: &str,
+) -> AppResult<()> {
+    let blob = stream.fetch_media(uri).await?;
+
+    blobs.put(key, blob).await
+}
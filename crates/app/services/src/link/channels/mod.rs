@@ -1,45 +1,85 @@
+mod channel_control;
 mod channel_state;
 mod channel_stream;
+mod channel_supervisor;
+mod media;
+mod rate_limiter;
+mod send_queue;
 mod telegram;
+mod whatsapp;
 
-use std::{collections::HashMap, sync::Arc};
+pub mod config;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use futures::{
     stream::{self, BoxStream},
     Stream, StreamExt,
 };
 use kernel_entities::{
-    entities::{auth::User, link::Channel},
+    entities::{
+        auth::User,
+        link::{Channel, ChannelPlatform},
+    },
     traits::Key,
 };
-use kernel_repositories::{error::RepoResult, DataStore};
+use kernel_repositories::{
+    error::{RepoError, RepoResult},
+    DataStore,
+};
 use kernel_services::{
-    error::AppResult,
+    error::{AppResult, AuthError},
     link::{
         channels::{
-            ChannelPipe, ChannelStatus, ChannelsService, ReverseChannelPipe,
+            ChannelLifecycleEvent,
+            ChannelPipe,
+            ChannelStatus,
+            ChannelsService,
+            IncomingChannelUpdate,
+            IncomingChannelUpdateKind,
+            ReverseChannelPipe,
         },
+        error::LinkError,
         message_passing::{
             MessagePassingService, ScopedTopicReader, ScopedTopicWriter,
         },
     },
+    storage::blob::BlobStorageService,
     Service,
 };
 use serde::{de::DeserializeOwned, Serialize};
+use subtle::ConstantTimeEq;
 use tokio::sync::RwLock;
-
-use self::channel_state::ChannelState;
+use uuid::Uuid;
+
+use self::{
+    channel_control::{ChannelCommand, ChannelControl},
+    channel_state::{create_stream, ChannelState},
+    channel_stream::ChannelStream,
+    config::ChannelsConfig,
+    media::store_incoming_media,
+    whatsapp::whatsapp_stream::WhatsAppStream,
+};
 
 type ChannelStatesMap = HashMap<Key<Channel>, ChannelState>;
 type UserChannelsMap = HashMap<Key<User>, ChannelStatesMap>;
+type WebhookStreamsMap =
+    HashMap<Key<Channel>, (DateTime<Utc>, Arc<dyn ChannelStream>)>;
 
 const CHANNELS_TOPIC_NAME: &str = "channels";
+const CONTROL_TOPIC_NAME: &str = "channel_control";
 
 pub struct AppChannelsService<IPC> {
     data: Arc<dyn DataStore>,
     ipc: Arc<IPC>,
+    blobs: Arc<dyn BlobStorageService>,
+    config: ChannelsConfig,
+    node_id: String,
     states: RwLock<UserChannelsMap>,
+    /// Streams parsing webhook calls, kept across calls of the same channel
+    webhook_streams: RwLock<WebhookStreamsMap>,
 }
 
 #[async_trait]
@@ -48,10 +88,26 @@ impl<Ipc: MessagePassingService> ChannelsService for AppChannelsService<Ipc> {
         &self,
         id: &Key<Channel>,
     ) -> AppResult<Option<ChannelStatus>> {
-        Ok(self.states.read().await.values().find_map(|chs| {
-            chs.get(id).map(|s| ChannelStatus {
-                started_at: s.started_at(),
-            })
+        let local = self
+            .states
+            .read()
+            .await
+            .values()
+            .find_map(|chs| chs.get(id).map(|s| self.status_of_state(s)));
+
+        if local.is_some() {
+            return Ok(local);
+        }
+
+        let lease = self.data.link().channels().get_lease(id).await?;
+
+        Ok(lease.map(|lease| ChannelStatus {
+            started_at: lease.acquired_at,
+            node_id: lease.node_id,
+            is_degraded: None,
+            restarts: None,
+            last_error: None,
+            last_activity: None,
         }))
     }
 
@@ -66,7 +122,7 @@ impl<Ipc: MessagePassingService> ChannelsService for AppChannelsService<Ipc> {
             match states {
                 | Some(states) => {
                     for (channel_id, state) in states {
-                        yield (channel_id.clone(), state.into());
+                        yield (channel_id.clone(), self.status_of_state(state));
                     }
                 }
                 | None => ()
@@ -116,6 +172,8 @@ impl<Ipc: MessagePassingService> ChannelsService for AppChannelsService<Ipc> {
                     channel.platform, channel.id, channel.user_id, channel.name,
                 );
 
+                self.release_lease(&channel_id).await;
+
                 match state.stop().await {
                     Ok(()) => yield Ok(()),
                     Err(err) => {
@@ -144,6 +202,12 @@ impl<Ipc: MessagePassingService> ChannelsService for AppChannelsService<Ipc> {
             .get_of(user_id, channel_id)
             .await?;
 
+        self.data
+            .link()
+            .channels()
+            .set_stopped(channel_id, false)
+            .await?;
+
         self.start_channel(channel).await
     }
 
@@ -152,24 +216,40 @@ impl<Ipc: MessagePassingService> ChannelsService for AppChannelsService<Ipc> {
         user_id: &Key<User>,
         channel_id: &Key<Channel>,
     ) -> AppResult<()> {
-        let Some(state) = self.remove_state(user_id, channel_id).await else {
-            debug!(
-                "channel #{} of #{} is not running, skipping",
-                channel_id, user_id
-            );
+        // persisted first, so that no node takes the channel over meanwhile
+        self.data
+            .link()
+            .channels()
+            .set_stopped(channel_id, true)
+            .await?;
 
-            return Ok(());
-        };
+        if !self.stop_local_channel(user_id, channel_id).await? {
+            self.send_command(user_id, channel_id, ChannelCommand::Stop)
+                .await?;
+        }
 
-        debug!("stopping channel #{} of #{}", channel_id, user_id,);
+        Ok(())
+    }
 
-        match state.stop().await {
-            | Ok(()) => Ok(()),
-            | Err(err) => {
-                warn!("could not stop channel #{}: {err}", channel_id);
-                Err(err)
-            }
+    async fn restart_channel(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+    ) -> AppResult<()> {
+        debug!("restarting channel #{} of #{}", channel_id, user_id);
+
+        let was_local = self.stop_local_channel(user_id, channel_id).await?;
+
+        // channels running on other nodes are restarted by their owner
+        if !was_local
+            && self
+                .send_command(user_id, channel_id, ChannelCommand::Restart)
+                .await?
+        {
+            return Ok(());
         }
+
+        ChannelsService::start_channel(self, user_id, channel_id).await
     }
 
     async fn get_pipe_of(
@@ -183,38 +263,164 @@ impl<Ipc: MessagePassingService> ChannelsService for AppChannelsService<Ipc> {
     async fn get_pipe_of_all(&self) -> AppResult<ChannelPipe> {
         self.create_pipe(None, None).await
     }
+
+    async fn get_reverse_pipe_of(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+    ) -> AppResult<ReverseChannelPipe> {
+        self.create_reverse_pipe(user_id, channel_id).await
+    }
+
+    async fn push_webhook_update(
+        &self,
+        channel_id: &Key<Channel>,
+        secret: Option<&str>,
+        payload: &[u8],
+    ) -> AppResult<()> {
+        let channel = self.data.link().channels().get(channel_id).await?;
+
+        if !channel.is_active {
+            return Err(LinkError::InvalidChannelState(format!(
+                "channel #{} is not active",
+                channel.id
+            ))
+            .into());
+        }
+
+        if self.data.link().channels().is_stopped(channel_id).await? {
+            return Err(LinkError::InvalidChannelState(format!(
+                "channel #{} is stopped",
+                channel.id
+            ))
+            .into());
+        }
+
+        match channel.platform {
+            | ChannelPlatform::Telegram => {
+                if !channel.use_webhook {
+                    return Err(LinkError::InvalidChannelState(format!(
+                        "channel #{} is not in webhook mode",
+                        channel.id
+                    ))
+                    .into());
+                }
+
+                let Some(expected) = channel.webhook_secret.as_deref() else {
+                    warn!("channel #{} has no webhook secret", channel.id);
+
+                    return Err(AuthError::NotAuthenticated.into());
+                };
+
+                let is_valid = secret.map_or(false, |secret| {
+                    expected.as_bytes().ct_eq(secret.as_bytes()).into()
+                });
+
+                if !is_valid {
+                    return Err(AuthError::NotAuthenticated.into());
+                }
+            }
+            | ChannelPlatform::WhatsApp => {
+                WhatsAppStream::verify_webhook_signature(
+                    &channel, secret, payload,
+                )?;
+            }
+        }
+
+        let stream = self.webhook_stream_of(&channel).await?;
+        let mut updates = stream.parse_webhook_payload(payload).await?;
+
+        for update in updates.iter_mut() {
+            store_incoming_media(&*self.blobs, &*stream, &channel, update)
+                .await;
+        }
+
+        let pipe = self
+            .create_reverse_pipe(&channel.user_id, &channel.id)
+            .await?;
+
+        for kind in updates {
+            let update = IncomingChannelUpdate::new(
+                channel.user_id.clone(),
+                channel.id.clone(),
+                kind,
+            );
+
+            pipe.tx.publish(&update).await?;
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
-impl<Ipc: MessagePassingService> Service for AppChannelsService<Ipc> {
+impl<Ipc: MessagePassingService + 'static> Service for AppChannelsService<Ipc> {
     async fn initialize(self: Arc<Self>) -> AppResult<()> {
         self.get_pipe_of_all().await?;
 
         debug!("starting channels");
 
-        let mut channels = self.start_channels();
+        {
+            let mut channels = self.start_channels();
 
-        while let Some(res) = channels.next().await {
-            if let Err(err) = res {
-                warn!("error starting channel: {err}")
+            while let Some(res) = channels.next().await {
+                if let Err(err) = res {
+                    warn!("error starting channel: {err}")
+                }
             }
         }
 
+        self.clone().spawn_secrets_reencryption();
+        self.clone().spawn_command_listener();
+        self.spawn_lease_keeper();
+
         Ok(())
     }
 }
 
 impl<IPC: MessagePassingService> AppChannelsService<IPC> {
-    pub fn new(data: Arc<dyn DataStore>, ipc: Arc<IPC>) -> Self {
+    pub fn new(
+        data: Arc<dyn DataStore>,
+        ipc: Arc<IPC>,
+        blobs: Arc<dyn BlobStorageService>,
+        config: ChannelsConfig,
+    ) -> Self {
+        let node_id = config
+            .lease
+            .node_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        info!("running channels as node `{node_id}`");
+
         Self {
             data,
             ipc,
+            blobs,
+            config,
+            node_id,
             states: Default::default(),
+            webhook_streams: Default::default(),
         }
     }
 
     async fn start_channel(&self, channel: Channel) -> AppResult<()> {
-        if self.has_state(&channel.user_id, &channel.id).await {
+        if is_expired(&channel) {
+            return Err(LinkError::InvalidChannelState(format!(
+                "channel #{} has expired",
+                channel.id
+            ))
+            .into());
+        }
+
+        // the slot stays locked until the channel is running, so concurrent
+        // starts of the same channel cannot both get past this check
+        let mut states = self.states.write().await;
+
+        if states
+            .get(&channel.user_id)
+            .is_some_and(|channels| channels.contains_key(&channel.id))
+        {
             debug!(
                 "channel #{} of #{} ({}) is already running, skipping",
                 channel.id, channel.user_id, channel.name,
@@ -222,6 +428,25 @@ impl<IPC: MessagePassingService> AppChannelsService<IPC> {
 
             return Ok(());
         }
+
+        let acquired = self
+            .data
+            .link()
+            .channels()
+            .acquire_lease(&channel.id, &self.node_id, self.lease_ttl())
+            .await?;
+
+        if !acquired {
+            trace!(
+                "channel #{} of #{} ({}) is owned by another node, skipping",
+                channel.id,
+                channel.user_id,
+                channel.name,
+            );
+
+            return Ok(());
+        }
+
         debug!(
             "starting {:?} channel #{} of#{} ({})",
             channel.platform, channel.id, channel.user_id, channel.name,
@@ -230,23 +455,385 @@ impl<IPC: MessagePassingService> AppChannelsService<IPC> {
         let pipe = self
             .create_reverse_pipe(&channel.user_id, &channel.id)
             .await?;
-        let state = ChannelState::new(channel, pipe)?;
+        let state = ChannelState::new(
+            channel,
+            pipe,
+            self.data.clone(),
+            self.blobs.clone(),
+            &self.config,
+        )?;
 
         match state.run().await {
             | Ok(_) => {
-                self.append_state(&state.channel().user_id.clone(), state)
-                    .await;
+                states
+                    .entry(state.channel().user_id.clone())
+                    .or_default()
+                    .insert(state.channel().id.clone(), state);
 
                 Ok(())
             }
 
             | Err(err) => {
                 warn!("could not start channel: {err}");
+                self.release_lease(&state.channel().id).await;
+
                 Err(err)
             }
         }
     }
 
+    /// Stops a channel if it is running on this node, returning whether it
+    /// was.
+    async fn stop_local_channel(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+    ) -> AppResult<bool> {
+        let Some(state) = self.remove_state(user_id, channel_id).await else {
+            return Ok(false);
+        };
+
+        debug!("stopping channel #{} of #{}", channel_id, user_id,);
+
+        self.release_lease(channel_id).await;
+
+        match state.stop().await {
+            | Ok(()) => Ok(true),
+            | Err(err) => {
+                warn!("could not stop channel #{}: {err}", channel_id);
+                Err(err)
+            }
+        }
+    }
+
+    /// Sends a command to the node owning the lease of a channel, returning
+    /// whether the channel is running on any other node.
+    async fn send_command(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+        command: ChannelCommand,
+    ) -> AppResult<bool> {
+        let lease = self.data.link().channels().get_lease(channel_id).await?;
+
+        let Some(lease) = lease.filter(|l| l.node_id != self.node_id) else {
+            debug!(
+                "channel #{} of #{} is not running, skipping",
+                channel_id, user_id
+            );
+
+            return Ok(false);
+        };
+
+        debug!(
+            "sending {command:?} of channel #{} to node `{}`",
+            channel_id, lease.node_id
+        );
+
+        let control = ChannelControl {
+            user_id: user_id.clone(),
+            channel_id: channel_id.clone(),
+            command,
+        };
+
+        self.ipc
+            .get_topic_writer::<ChannelControl>(CONTROL_TOPIC_NAME)
+            .await?
+            .publish_confirmed(&lease.node_id, &control)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Runs the commands sent by other nodes on the channels of this node.
+    fn spawn_command_listener(self: Arc<Self>)
+    where
+        IPC: 'static,
+    {
+        tokio::spawn(async move {
+            if let Err(err) = self.listen_commands().await {
+                error!(
+                    "stopped listening to commands of node `{}`: {err}",
+                    self.node_id
+                );
+            }
+        });
+    }
+
+    async fn listen_commands(&self) -> AppResult<()> {
+        let reader = self
+            .ipc
+            .get_topic_reader::<ChannelControl>(CONTROL_TOPIC_NAME)
+            .await?;
+        let mut commands = reader.subscribe(&self.node_id).await?;
+
+        while let Some(control) = commands.next().await {
+            let ChannelControl {
+                user_id,
+                channel_id,
+                command,
+            } = match control {
+                | Ok(control) => control,
+                | Err(err) => {
+                    warn!("could not read channel command: {err}");
+                    continue;
+                }
+            };
+
+            debug!("received {command:?} of channel #{channel_id}");
+
+            // commands are only run locally, so they never bounce between
+            // nodes when the lease moved in the meantime
+            let ran = match command {
+                | ChannelCommand::Stop => self
+                    .stop_local_channel(&user_id, &channel_id)
+                    .await
+                    .map(|_| ()),
+                | ChannelCommand::Restart => {
+                    match self.stop_local_channel(&user_id, &channel_id).await {
+                        | Ok(true) => {
+                            ChannelsService::start_channel(
+                                self,
+                                &user_id,
+                                &channel_id,
+                            )
+                            .await
+                        }
+                        | ret => ret.map(|_| ()),
+                    }
+                }
+            };
+
+            if let Err(err) = ran {
+                warn!(
+                    "could not run {command:?} of channel #{channel_id}: {err}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Periodically stops the expired, deactivated, stopped and deleted
+    /// channels and renews the leases of the remaining ones running on this
+    /// node, and takes over the active channels that are not owned by any
+    /// node, such as the ones of a node that went down.
+    fn spawn_lease_keeper(self: Arc<Self>)
+    where
+        IPC: 'static,
+    {
+        let period =
+            Duration::from_secs(self.config.lease.renew_interval_seconds);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                self.stop_expired_channels().await;
+                self.renew_leases().await;
+
+                let mut channels = self.data.link().channels().stream_active();
+
+                while let Some(channel) = channels.next().await {
+                    let channel = match channel {
+                        | Ok(channel) => channel,
+                        | Err(err) => {
+                            warn!("could not load channel: {err}");
+                            continue;
+                        }
+                    };
+
+                    if self.has_state(&channel.user_id, &channel.id).await {
+                        continue;
+                    }
+
+                    if let Err(err) = self.start_channel(channel).await {
+                        warn!("error starting channel: {err}")
+                    }
+                }
+            }
+        });
+    }
+
+    /// Stops the channels running on this node whose `valid_until` has
+    /// passed, emitting an `Expired` lifecycle event for each of them.
+    async fn stop_expired_channels(&self) {
+        let expired = self
+            .states
+            .read()
+            .await
+            .values()
+            .flat_map(|chs| chs.values().map(ChannelState::channel))
+            .filter(|channel| is_expired(channel))
+            .map(|channel| (channel.user_id.clone(), channel.id.clone()))
+            .collect::<Vec<_>>();
+
+        for (user_id, channel_id) in expired {
+            info!("channel #{channel_id} has expired, stopping");
+
+            if let Err(err) =
+                self.stop_local_channel(&user_id, &channel_id).await
+            {
+                warn!("could not stop channel #{channel_id}: {err}");
+            }
+
+            let emitted = self
+                .emit_lifecycle_event(
+                    &user_id,
+                    &channel_id,
+                    ChannelLifecycleEvent::Expired,
+                )
+                .await;
+
+            if let Err(err) = emitted {
+                warn!("could not emit event of channel #{channel_id}: {err}");
+            }
+        }
+    }
+
+    async fn emit_lifecycle_event(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+        event: ChannelLifecycleEvent,
+    ) -> AppResult<()> {
+        let pipe = self.create_reverse_pipe(user_id, channel_id).await?;
+        let update = IncomingChannelUpdate::new(
+            user_id.clone(),
+            channel_id.clone(),
+            IncomingChannelUpdateKind::Lifecycle {
+                event,
+                timestamp: Utc::now(),
+            },
+        );
+
+        pipe.tx.publish(&update).await
+    }
+
+    /// Re-encrypts the secrets of channels that are not encrypted with the
+    /// current master key in the background, such as after it was rotated.
+    fn spawn_secrets_reencryption(self: Arc<Self>)
+    where
+        IPC: 'static,
+    {
+        tokio::spawn(async move {
+            match self.data.link().channels().reencrypt_secrets().await {
+                | Ok(0) => {}
+                | Ok(count) => {
+                    info!("re-encrypted secrets of {count} channels")
+                }
+                | Err(err) => error!("could not re-encrypt secrets: {err}"),
+            }
+        });
+    }
+
+    async fn renew_leases(&self) {
+        let owned = self
+            .states
+            .read()
+            .await
+            .iter()
+            .flat_map(|(user_id, chs)| {
+                chs.keys().map(|id| (user_id.clone(), id.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        for (user_id, channel_id) in owned {
+            let channel = self.data.link().channels().get(&channel_id).await;
+            let is_active = match channel {
+                | Ok(channel) => channel.is_active,
+                | Err(RepoError::NotFound) => false,
+                | Err(err) => {
+                    warn!("could not load channel #{channel_id}: {err}");
+                    true
+                }
+            };
+
+            // stops persisted by other nodes are picked up here as well
+            let is_stopped = is_active
+                && self
+                    .data
+                    .link()
+                    .channels()
+                    .is_stopped(&channel_id)
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!("could not load channel #{channel_id}: {err}");
+                        false
+                    });
+
+            if !is_active || is_stopped {
+                info!("channel #{channel_id} is no longer active, stopping");
+
+                if let Err(err) =
+                    self.stop_local_channel(&user_id, &channel_id).await
+                {
+                    warn!("could not stop channel #{channel_id}: {err}");
+                }
+
+                continue;
+            }
+
+            let renewed = self
+                .data
+                .link()
+                .channels()
+                .acquire_lease(&channel_id, &self.node_id, self.lease_ttl())
+                .await;
+
+            match renewed {
+                | Ok(true) => {}
+                | Ok(false) => {
+                    warn!("lost the lease of channel #{channel_id}, stopping");
+
+                    if let Err(err) =
+                        self.stop_local_channel(&user_id, &channel_id).await
+                    {
+                        warn!("could not stop channel #{channel_id}: {err}");
+                    }
+                }
+                | Err(err) => {
+                    warn!(
+                        "could not renew lease of channel #{channel_id}: {err}"
+                    )
+                }
+            }
+        }
+    }
+
+    async fn release_lease(&self, channel_id: &Key<Channel>) {
+        let released = self
+            .data
+            .link()
+            .channels()
+            .release_lease(channel_id, &self.node_id)
+            .await;
+
+        if let Err(err) = released {
+            warn!("could not release lease of channel #{channel_id}: {err}");
+        }
+    }
+
+    fn lease_ttl(&self) -> Duration {
+        Duration::from_secs(self.config.lease.ttl_seconds)
+    }
+
+    fn status_of_state(&self, state: &ChannelState) -> ChannelStatus {
+        let health = state.health();
+
+        ChannelStatus {
+            started_at: state.started_at(),
+            node_id: self.node_id.clone(),
+            is_degraded: Some(state.is_degraded()),
+            restarts: Some(health.restarts),
+            last_error: health.last_error,
+            last_activity: health.last_activity,
+        }
+    }
+
     fn start_channels_stream<'a, S>(
         &'a self,
         channels: S,
@@ -269,21 +856,33 @@ impl<IPC: MessagePassingService> AppChannelsService<IPC> {
             .boxed()
     }
 
-    async fn append_state(&self, user_id: &Key<User>, state: ChannelState) {
-        let mut states = self.states.write().await;
-
-        match states.get_mut(user_id) {
-            | Some(channels) => {
-                channels.insert(state.channel().id.clone(), state);
+    /// Returns the stream of a channel handling its webhook calls, which is
+    /// created once, and again only after the channel is updated.
+    async fn webhook_stream_of(
+        &self,
+        channel: &Channel,
+    ) -> AppResult<Arc<dyn ChannelStream>> {
+        if let Some((updated_at, stream)) =
+            self.webhook_streams.read().await.get(&channel.id)
+        {
+            if *updated_at == channel.updated_at {
+                return Ok(stream.clone());
             }
+        }
 
-            | None => {
-                let mut user_states = HashMap::default();
-                user_states.insert(state.channel().id.clone(), state);
+        let stream = create_stream(
+            channel,
+            self.data.clone(),
+            self.blobs.clone(),
+            &self.config,
+        )?;
 
-                states.insert(user_id.clone(), user_states);
-            }
-        }
+        self.webhook_streams
+            .write()
+            .await
+            .insert(channel.id.clone(), (channel.updated_at, stream.clone()));
+
+        Ok(stream)
     }
 
     async fn remove_state(
@@ -371,10 +970,8 @@ impl<IPC: MessagePassingService> AppChannelsService<IPC> {
     }
 }
 
-impl From<&ChannelState> for ChannelStatus {
-    fn from(val: &ChannelState) -> Self {
-        ChannelStatus {
-            started_at: val.started_at(),
-        }
-    }
+fn is_expired(channel: &Channel) -> bool {
+    channel
+        .valid_until
+        .map_or(false, |until| until <= Utc::now())
 }
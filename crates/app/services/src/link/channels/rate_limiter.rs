@@ -0,0 +1,182 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
+
+use super::config::RateLimitConfig;
+
+/// Number of tracked chats, beyond which the buckets of idle chats are
+/// dropped.
+const MAX_IDLE_CHATS: usize = 1024;
+
+/// Schedules the outgoing updates of a channel within the rate limits of its
+/// platform, both channel-wide and per chat.
+pub(super) struct RateLimiter {
+    config: RateLimitConfig,
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    channel: TokenBucket,
+    chats: HashMap<i64, TokenBucket>,
+    paused_until: Option<Instant>,
+}
+
+/// A token bucket, tracked as the theoretical arrival time of the next
+/// token (i.e. GCRA), so that sends can be scheduled ahead of time.
+struct TokenBucket {
+    interval: Duration,
+    tolerance: Duration,
+    tat: Instant,
+}
+
+impl RateLimiter {
+    pub(super) fn new(config: RateLimitConfig) -> Self {
+        let channel =
+            TokenBucket::new(config.per_second, config.burst, Instant::now());
+
+        Self {
+            config,
+            state: Mutex::new(RateLimiterState {
+                channel,
+                chats: HashMap::new(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// Reserves a send to `chat_id`, returning the instant at which it may
+    /// be performed. Reservations of the same chat are handed out in order.
+    pub(super) fn reserve(&self, chat_id: i64) -> Instant {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+
+        if state.chats.len() >= MAX_IDLE_CHATS {
+            state.chats.retain(|_, bucket| bucket.tat > now);
+        }
+
+        let mut at = state.paused_until.map_or(now, |until| until.max(now));
+        at = at.max(state.channel.available_at(now));
+
+        let chat = state.chats.entry(chat_id).or_insert_with(|| {
+            TokenBucket::new(
+                self.config.chat_per_second,
+                self.config.chat_burst,
+                now,
+            )
+        });
+
+        at = at.max(chat.available_at(now));
+        chat.consume_at(at);
+        state.channel.consume_at(at);
+
+        at
+    }
+
+    /// Holds back all sends for `delay`, as requested by the platform.
+    pub(super) fn pause(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut state = self.state.lock().unwrap();
+
+        state.paused_until =
+            Some(state.paused_until.map_or(until, |paused| paused.max(until)));
+    }
+
+    pub(super) fn max_retries(&self) -> u32 {
+        self.config.max_retries
+    }
+}
+
+impl TokenBucket {
+    fn new(per_second: f64, burst: u32, now: Instant) -> Self {
+        let interval = Duration::from_secs_f64(1.0 / per_second);
+
+        Self {
+            interval,
+            tolerance: interval * burst.saturating_sub(1),
+            tat: now,
+        }
+    }
+
+    fn available_at(&self, now: Instant) -> Instant {
+        self.tat
+            .checked_sub(self.tolerance)
+            .map_or(now, |at| at.max(now))
+    }
+
+    fn consume_at(&mut self, at: Instant) {
+        self.tat = self.tat.max(at) + self.interval;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(
+        per_second: f64,
+        burst: u32,
+        chat_per_second: f64,
+    ) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_second,
+            burst,
+            chat_per_second,
+            chat_burst: 1,
+            max_retries: 0,
+        })
+    }
+
+    #[test]
+    fn sends_bursts_right_away() {
+        let limiter = limiter(1.0, 3, 1000.0);
+
+        for chat_id in 0..3 {
+            assert!(limiter.reserve(chat_id) <= Instant::now());
+        }
+
+        assert!(limiter.reserve(3) > Instant::now());
+    }
+
+    #[test]
+    fn spaces_sends_of_a_chat() {
+        let limiter = limiter(1000.0, 1000, 10.0);
+
+        let first = limiter.reserve(1);
+        let second = limiter.reserve(1);
+        let third = limiter.reserve(1);
+
+        assert_eq!(second - first, Duration::from_millis(100));
+        assert_eq!(third - second, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn limits_chats_independently() {
+        let limiter = limiter(1000.0, 1000, 1.0);
+
+        limiter.reserve(1);
+
+        assert!(limiter.reserve(2) <= Instant::now());
+        assert!(limiter.reserve(1) > Instant::now());
+    }
+
+    #[test]
+    fn limits_the_channel_across_chats() {
+        let limiter = limiter(10.0, 1, 1000.0);
+
+        let first = limiter.reserve(1);
+        let second = limiter.reserve(2);
+
+        assert_eq!(second - first, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn holds_back_sends_while_paused() {
+        let limiter = limiter(1000.0, 1000, 1000.0);
+        let start = Instant::now();
+
+        limiter.pause(Duration::from_secs(5));
+        limiter.pause(Duration::from_secs(1));
+
+        assert!(limiter.reserve(1) >= start + Duration::from_secs(5));
+    }
+}
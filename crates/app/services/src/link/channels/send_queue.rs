@@ -0,0 +1,343 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use kernel_entities::{
+    entities::{
+        comm::{Message, MessageStatus},
+        link::Channel,
+    },
+    traits::Key,
+};
+use kernel_services::{
+    error::{AppError, AppResult},
+    link::{
+        channels::{
+            IncomingChannelUpdate,
+            IncomingChannelUpdateKind,
+            OutgoingChannelUpdateKind,
+            OutgoingMessageUpdateKind,
+            ReverseChannelPipe,
+        },
+        error::LinkError,
+        message_passing::MessageConfirmation,
+    },
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    channel_stream::ChannelStream,
+    channel_supervisor::ChannelHealth,
+    rate_limiter::RateLimiter,
+};
+
+/// Sends the outgoing updates of a channel, while keeping within the rate
+/// limits of its platform. Chats are sent to concurrently, but the updates
+/// of a chat are sent one after the other, in order. Sends that are rate
+/// limited by the platform anyway are retried after the delay it requests,
+/// before any later update of the chat.
+#[derive(Clone)]
+pub(super) struct SendQueue {
+    pub channel: Channel,
+    pub stream: Arc<dyn ChannelStream>,
+    pub pipe: ReverseChannelPipe,
+    pub cancellation: CancellationToken,
+    pub health: Arc<Mutex<ChannelHealth>>,
+    pub limiter: Arc<RateLimiter>,
+    pub pending: Arc<Semaphore>,
+    pub chats: Arc<Mutex<ChatQueues<PendingUpdate>>>,
+}
+
+pub(super) struct PendingUpdate {
+    kind: OutgoingChannelUpdateKind,
+    confirm: Arc<dyn MessageConfirmation>,
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Queues of items per chat, where the item at the front of a queue is the
+/// one being processed, and stays there until it is done.
+pub(super) struct ChatQueues<T> {
+    queues: HashMap<i64, VecDeque<T>>,
+}
+
+impl SendQueue {
+    /// Waits for a free slot to queue an update in, as long as too many
+    /// updates are already pending.
+    pub(super) async fn acquire_slot(&self) -> OwnedSemaphorePermit {
+        self.pending
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("pending sends semaphore is never closed")
+    }
+
+    /// Schedules an update to be sent after the pending updates of its chat.
+    pub(super) fn enqueue(
+        &self,
+        kind: OutgoingChannelUpdateKind,
+        confirm: Arc<dyn MessageConfirmation>,
+        permit: OwnedSemaphorePermit,
+    ) {
+        let chat_id = chat_of(&kind);
+        let update = PendingUpdate {
+            kind,
+            confirm,
+            _permit: permit,
+        };
+
+        // chats with pending updates already have a task sending them
+        if self.chats.lock().unwrap().push(chat_id, update) {
+            tokio::spawn(self.clone().drain(chat_id));
+        }
+    }
+
+    /// Sends the pending updates of a chat, until there are none left.
+    async fn drain(self, chat_id: i64) {
+        loop {
+            let (kind, confirm) = {
+                let chats = self.chats.lock().unwrap();
+
+                let Some(update) = chats.front(chat_id) else {
+                    return;
+                };
+
+                (update.kind.clone(), update.confirm.clone())
+            };
+
+            self.deliver(kind, confirm).await;
+
+            if !self.chats.lock().unwrap().pop(chat_id) {
+                return;
+            }
+        }
+    }
+
+    async fn deliver(
+        &self,
+        kind: OutgoingChannelUpdateKind,
+        confirm: Arc<dyn MessageConfirmation>,
+    ) {
+        let Some(ret) = self.send(kind, confirm.is_last_attempt()).await else {
+            requeue(&*confirm).await;
+            return;
+        };
+
+        match ret {
+            | Ok(_) => {
+                self.health.lock().unwrap().record_activity();
+                confirm.ack().await
+            }
+            | Err(err) => {
+                // permanent failures are reported back as message statuses,
+                // so only transient ones are retried
+                warn!("could not send outgoing update: {err:#?}");
+                confirm.nack(err.is_transient()).await
+            }
+        }
+        .unwrap_or_else(|err| error!("failed to send ack/nack: {err:#?}"));
+    }
+
+    /// Sends an update once the rate limits allow it, and reports the status
+    /// of the sent message, if any. Returns `None` if the channel was stopped
+    /// before the update could be sent.
+    async fn send(
+        &self,
+        kind: OutgoingChannelUpdateKind,
+        is_last_attempt: bool,
+    ) -> Option<AppResult<()>> {
+        let chat_id = chat_of(&kind);
+        let tracked = tracked_message_of(&kind);
+        let mut retries = 0;
+
+        let ret = loop {
+            let at = self.limiter.reserve(chat_id);
+
+            tokio::select! {
+                _ = self.cancellation.cancelled() => return None,
+                _ = tokio::time::sleep_until(at) => {}
+            }
+
+            match self.stream.send(kind.clone()).await {
+                | Err(AppError::Link(LinkError::RateLimited(delay)))
+                    if retries < self.limiter.max_retries() =>
+                {
+                    retries += 1;
+
+                    warn!(
+                        "channel #{} was rate limited, retrying in {}ms",
+                        self.channel.id,
+                        delay.as_millis(),
+                    );
+
+                    self.limiter.pause(delay);
+                }
+                | ret => break ret,
+            }
+        };
+
+        let Some((platform_user_id, message_id)) = tracked else {
+            return Some(ret.map(|_| ()));
+        };
+
+        let (status, platform_message_id) = match ret {
+            | Ok(ref platform_message_id) => {
+                (MessageStatus::Sent, platform_message_id.clone())
+            }
+            // the message is still pending while the update is retried, and
+            // fails once it is dead-lettered after its last attempt
+            | Err(ref err) if err.is_transient() && !is_last_attempt => {
+                return Some(ret.map(|_| ()));
+            }
+            | Err(_) => (MessageStatus::Failed, None),
+        };
+
+        let update = IncomingChannelUpdate::new(
+            self.channel.user_id.clone(),
+            self.channel.id.clone(),
+            IncomingChannelUpdateKind::MessageStatus {
+                platform_user_id,
+                message_id: Some(message_id),
+                platform_message_id,
+                status,
+                timestamp: Utc::now(),
+            },
+        );
+
+        if let Err(err) = self.pipe.tx.publish(&update).await {
+            warn!("could not publish message status: {err:#?}");
+        }
+
+        Some(ret.map(|_| ()))
+    }
+}
+
+impl<T> ChatQueues<T> {
+    /// Appends an item to the queue of a chat, returning whether it is at
+    /// the front of it.
+    pub(super) fn push(&mut self, chat_id: i64, item: T) -> bool {
+        let queue = self.queues.entry(chat_id).or_default();
+        queue.push_back(item);
+
+        queue.len() == 1
+    }
+
+    pub(super) fn front(&self, chat_id: i64) -> Option<&T> {
+        self.queues.get(&chat_id).and_then(VecDeque::front)
+    }
+
+    /// Removes the item at the front of the queue of a chat once it is done,
+    /// returning whether more items are queued.
+    pub(super) fn pop(&mut self, chat_id: i64) -> bool {
+        let Some(queue) = self.queues.get_mut(&chat_id) else {
+            return false;
+        };
+
+        queue.pop_front();
+
+        if queue.is_empty() {
+            self.queues.remove(&chat_id);
+            return false;
+        }
+
+        true
+    }
+}
+
+impl<T> Default for ChatQueues<T> {
+    fn default() -> Self {
+        Self {
+            queues: HashMap::new(),
+        }
+    }
+}
+
+async fn requeue(confirm: &dyn MessageConfirmation) {
+    // requeued to be sent once the channel runs again, possibly on another
+    // node
+    confirm.nack(true).await.unwrap_or_else(|err| {
+        error!("could not nack ipc message: {err:#?}");
+    });
+}
+
+fn chat_of(kind: &OutgoingChannelUpdateKind) -> i64 {
+    match kind {
+        | OutgoingChannelUpdateKind::Message {
+            platform_user_id,
+            group_id,
+            ..
+        } => group_id.unwrap_or(*platform_user_id),
+    }
+}
+
+fn tracked_message_of(
+    kind: &OutgoingChannelUpdateKind,
+) -> Option<(i64, Key<Message>)> {
+    match kind {
+        | OutgoingChannelUpdateKind::Message {
+            platform_user_id,
+            kind: OutgoingMessageUpdateKind::New { message_id, .. },
+            ..
+        } => Some((*platform_user_id, message_id.clone())),
+        | OutgoingChannelUpdateKind::Message { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChatQueues;
+
+    #[test]
+    fn only_first_items_of_chats_are_at_the_front() {
+        let mut queues = ChatQueues::default();
+
+        assert!(queues.push(1, "new"));
+        assert!(!queues.push(1, "edit"));
+        assert!(queues.push(2, "other"));
+    }
+
+    #[test]
+    fn keeps_items_of_a_chat_in_order() {
+        let mut queues = ChatQueues::default();
+
+        queues.push(1, "new");
+        queues.push(2, "other");
+        queues.push(1, "edit");
+        queues.push(1, "delete");
+
+        assert_eq!(queues.front(1), Some(&"new"));
+        assert!(queues.pop(1));
+        assert_eq!(queues.front(1), Some(&"edit"));
+        assert!(queues.pop(1));
+        assert_eq!(queues.front(1), Some(&"delete"));
+        assert!(!queues.pop(1));
+        assert_eq!(queues.front(1), None);
+        assert_eq!(queues.front(2), Some(&"other"));
+    }
+
+    #[test]
+    fn keeps_items_at_the_front_until_popped() {
+        let mut queues = ChatQueues::default();
+
+        queues.push(1, "new");
+
+        // e.g. while the send is retried after being rate limited
+        assert_eq!(queues.front(1), Some(&"new"));
+        assert_eq!(queues.front(1), Some(&"new"));
+        assert!(!queues.push(1, "edit"));
+        assert_eq!(queues.front(1), Some(&"new"));
+    }
+
+    #[test]
+    fn restarts_drained_chats() {
+        let mut queues = ChatQueues::default();
+
+        queues.push(1, "new");
+        assert!(!queues.pop(1));
+        assert!(!queues.pop(1));
+
+        assert!(queues.push(1, "edit"));
+    }
+}
@@ -0,0 +1,67 @@
+use kernel_services::link::rich_text::{RichSpan, RichText, TextStyle};
+
+/// Renders a text in the html subset supported by telegram, to be sent with
+/// the `Html` parse mode.
+pub(super) fn to_html(text: &RichText) -> String {
+    let mut html = String::new();
+
+    for span in text.spans.iter().filter(|span| !span.is_empty()) {
+        match span {
+            | RichSpan::Text { text, style } => {
+                push_styled(&mut html, &escape(text), *style);
+            }
+            | RichSpan::Link { text, url, style } => {
+                let link =
+                    format!("<a href=\"{}\">{}</a>", escape(url), escape(text));
+
+                push_styled(&mut html, &link, *style);
+            }
+            | RichSpan::Code(code) => {
+                html += &format!("<code>{}</code>", escape(code));
+            }
+            | RichSpan::Pre {
+                language: Some(language),
+                code,
+            } => {
+                html += &format!(
+                    "<pre><code class=\"language-{}\">{}</code></pre>",
+                    escape(language),
+                    escape(code)
+                );
+            }
+            | RichSpan::Pre {
+                language: None,
+                code,
+            } => {
+                html += &format!("<pre>{}</pre>", escape(code));
+            }
+        }
+    }
+
+    html
+}
+
+fn push_styled(html: &mut String, inner: &str, style: TextStyle) {
+    let tags = [
+        (style.bold, "b"),
+        (style.italic, "i"),
+        (style.strikethrough, "s"),
+    ];
+
+    for (_, tag) in tags.iter().filter(|(enabled, _)| *enabled) {
+        *html += &format!("<{tag}>");
+    }
+
+    *html += inner;
+
+    for (_, tag) in tags.iter().rev().filter(|(enabled, _)| *enabled) {
+        *html += &format!("</{tag}>");
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
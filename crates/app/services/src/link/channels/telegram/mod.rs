@@ -1,2 +1,3 @@
+mod markup;
 pub(super) mod telegram_stream;
 mod util;
@@ -1,53 +1,198 @@
 use std::{
+    collections::VecDeque,
     sync::{
         atomic::{AtomicI32, Ordering},
         Arc,
+        Mutex,
     },
     time::Duration,
 };
 
-use common_async_utils::queue::BoundedQueue;
-use kernel_entities::entities::link::Channel;
+use chrono::Utc;
+use kernel_entities::{
+    entities::{
+        comm::{Attachment, AttachmentKind},
+        link::{Channel, ChannelGroupMode},
+    },
+    traits::Key,
+};
+use kernel_repositories::DataStore;
 use kernel_services::{
     error::AppResult,
     link::{
         channels::{
             IncomingChannelUpdateKind,
             IncomingMessageUpdateKind,
+            MessageKeyboard,
             OutgoingChannelUpdateKind,
             OutgoingMessageUpdateKind,
+            PlatformGroup,
+            SenderProfile,
         },
         error::LinkError,
+        rich_text::RichText,
     },
+    storage::blob::{blob_key_of, Blob, BlobStorageService},
 };
+use reqwest::Url;
 use teloxide::{
-    requests::Requester,
-    types::{MediaKind, Message, MessageKind, Update, UpdateKind, UserId},
+    net::Download,
+    payloads::SetWebhookSetters,
+    requests::{HasPayload, Requester},
+    types::{
+        CallbackQuery,
+        ChatId,
+        FileMeta,
+        InlineKeyboardButton,
+        InlineKeyboardButtonKind,
+        InlineKeyboardMarkup,
+        InputFile,
+        KeyboardButton,
+        KeyboardMarkup,
+        Me,
+        MediaKind,
+        Message,
+        MessageEntity,
+        MessageEntityKind,
+        MessageId,
+        MessageKind,
+        ParseMode,
+        ReplyMarkup,
+        Update,
+        UpdateKind,
+        User,
+    },
     Bot,
 };
+use tokio::sync::OnceCell;
 
-use super::util::map_request_error;
-use crate::link::channels::channel_stream::ChannelStream;
+use super::{
+    markup::to_html,
+    util::{is_markup_error, map_download_error, map_request_error},
+};
+use crate::link::channels::{
+    channel_stream::ChannelStream,
+    config::ChannelsConfig,
+};
+
+const MAX_MESSAGE_LENGTH: usize = 4096;
+const MAX_CAPTION_LENGTH: usize = 1024;
 
 pub(crate) struct TelegramStream {
     bot: Bot,
+    channel_id: Key<Channel>,
+    data: Arc<dyn DataStore>,
+    blobs: Arc<dyn BlobStorageService>,
+    webhook: Option<TelegramWebhook>,
+    group_mode: ChannelGroupMode,
+    me: OnceCell<Me>,
     update_idx: AtomicI32,
-    in_buf: BoundedQueue<IncomingChannelUpdateKind>,
+    persisted_idx: AtomicI32,
+    /// Fetched updates that were not handed over yet, which are only removed
+    /// once converted, so that receiving can be cancelled at any point
+    in_buf: Mutex<VecDeque<Update>>,
+}
+
+struct TelegramWebhook {
+    url: Url,
+    secret: String,
 }
 
 #[async_trait::async_trait]
 impl ChannelStream for TelegramStream {
+    async fn initialize(&self) -> AppResult<()> {
+        match self.webhook {
+            | Some(ref webhook) => {
+                self.bot
+                    .set_webhook(webhook.url.clone())
+                    .secret_token(webhook.secret.clone())
+                    .await
+                    .map_err(map_request_error)?;
+            }
+
+            | None => {
+                // a registered webhook prevents `get_updates` from working
+                self.bot.delete_webhook().await.map_err(map_request_error)?;
+
+                // resume after the last update handed over before a restart,
+                // fetching buffered updates that were not published again
+                let offset = self
+                    .data
+                    .link()
+                    .channels()
+                    .get_update_offset(&self.channel_id)
+                    .await?
+                    .unwrap_or_default() as i32;
+
+                self.in_buf.lock().unwrap().clear();
+
+                self.update_idx.store(offset, Ordering::Release);
+                self.persisted_idx.store(offset, Ordering::Release);
+            }
+        }
+
+        Ok(())
+    }
+
     async fn recv(&self) -> AppResult<IncomingChannelUpdateKind> {
+        if self.webhook.is_some() {
+            // updates are delivered through the webhook, and are published
+            // directly to the channel pipe; see `parse_webhook_payload`
+            return futures::future::pending().await;
+        }
+
         self.read_next_update().await
     }
 
-    async fn send(&self, update: OutgoingChannelUpdateKind) -> AppResult<()> {
+    async fn fetch_media(&self, uri: &str) -> AppResult<Blob> {
+        let file = self.bot.get_file(uri).await.map_err(map_request_error)?;
+        let mut data = Vec::new();
+
+        self.bot
+            .download_file(&file.path, &mut data)
+            .await
+            .map_err(map_download_error)?;
+
+        let content_type = mime_guess::from_path(&file.path)
+            .first()
+            .map(|mime| mime.to_string());
+
+        Ok(Blob { content_type, data })
+    }
+
+    async fn send(
+        &self,
+        update: OutgoingChannelUpdateKind,
+    ) -> AppResult<Option<String>> {
         self.send_update(update).await
     }
+
+    async fn parse_webhook_payload(
+        &self,
+        payload: &[u8],
+    ) -> AppResult<Vec<IncomingChannelUpdateKind>> {
+        let update: Update = serde_json::from_slice(payload)
+            .map_err(|err| LinkError::InvalidParams(err.to_string()))?;
+
+        match self.convert_from_telegram_update(update).await {
+            | Ok(update) => Ok(update.into_iter().collect()),
+            // the webhook call fails, so the update is delivered again
+            | Err(err) if err.is_transient() => Err(err),
+            | Err(err) => {
+                warn!("skipping telegram update: {err}");
+                Ok(vec![])
+            }
+        }
+    }
 }
 
 impl TelegramStream {
-    pub(crate) fn new(channel: &Channel) -> AppResult<Arc<Self>> {
+    pub(crate) fn new(
+        channel: &Channel,
+        data: Arc<dyn DataStore>,
+        blobs: Arc<dyn BlobStorageService>,
+        config: &ChannelsConfig,
+    ) -> AppResult<Arc<Self>> {
         let client = teloxide::net::default_reqwest_settings()
             .timeout(Duration::from_secs(180))
             .connect_timeout(Duration::from_secs(300))
@@ -56,32 +201,110 @@ impl TelegramStream {
 
         let bot = Bot::with_client(&channel.api_key, client);
 
+        let webhook = if channel.use_webhook {
+            let url = config
+                .webhook_url_of(&channel.id)
+                .ok_or_else(|| {
+                    LinkError::InvalidParams(
+                        "webhook base url is not configured".into(),
+                    )
+                })?
+                .parse::<Url>()
+                .map_err(|err| LinkError::InvalidParams(err.to_string()))?;
+
+            // webhook updates cannot be authenticated without a secret
+            let secret = channel.webhook_secret.clone().ok_or_else(|| {
+                LinkError::InvalidParams(
+                    "webhook mode requires a webhook secret".into(),
+                )
+            })?;
+
+            Some(TelegramWebhook { url, secret })
+        } else {
+            None
+        };
+
         Ok(Arc::new(Self {
             bot,
+            channel_id: channel.id.clone(),
+            data,
+            blobs,
+            webhook,
+            group_mode: channel.group_mode,
+            me: OnceCell::new(),
             update_idx: 0.into(),
-            in_buf: BoundedQueue::new(1024),
+            persisted_idx: 0.into(),
+            in_buf: Default::default(),
         }))
     }
 
     #[inline]
     async fn read_next_update(&self) -> AppResult<IncomingChannelUpdateKind> {
         loop {
-            if let Ok(update) = self.in_buf.try_dequeue().await {
-                return Ok(update);
-            };
+            let next = self.in_buf.lock().unwrap().front().cloned();
+
+            if let Some(update) = next {
+                let converted =
+                    match self.convert_from_telegram_update(update).await {
+                        // transient failures are left buffered, to be retried
+                        | Err(err) if err.is_transient() => return Err(err),
+                        | converted => converted,
+                    };
+
+                self.in_buf.lock().unwrap().pop_front();
+
+                match converted {
+                    | Ok(Some(item)) => return Ok(item),
+                    | Ok(None) => continue,
+                    | Err(err) => {
+                        warn!("skipping telegram update: {err}");
+                        continue;
+                    }
+                }
+            }
+
+            let offset = self.update_idx.load(Ordering::Acquire);
+
+            // the buffer is drained at this point, and updates are only
+            // received again once the previous one was published, so all
+            // updates before the offset need not be fetched again
+            self.persist_offset(offset).await;
 
             let mut req = self.bot.get_updates();
 
-            req.offset = Some(self.update_idx.load(Ordering::Acquire));
+            req.offset = Some(offset);
 
-            for update in req.await.map_err(map_request_error)? {
-                self.update_idx.store(update.id + 1, Ordering::Relaxed);
+            let updates = req.await.map_err(map_request_error)?;
 
-                let item = self.convert_from_telegram_update(update)?;
+            // the offset only moves past updates once they are buffered
+            let mut in_buf = self.in_buf.lock().unwrap();
 
-                if let Err(err) = self.in_buf.enqueue(item).await {
-                    warn!("error enqueuing item: {err:#?}");
-                }
+            for update in updates {
+                self.update_idx.store(update.id + 1, Ordering::Release);
+                in_buf.push_back(update);
+            }
+        }
+    }
+
+    async fn persist_offset(&self, offset: i32) {
+        if self.persisted_idx.load(Ordering::Acquire) == offset {
+            return;
+        }
+
+        let ret = self
+            .data
+            .link()
+            .channels()
+            .set_update_offset(&self.channel_id, offset as i64)
+            .await;
+
+        match ret {
+            | Ok(()) => self.persisted_idx.store(offset, Ordering::Release),
+            | Err(err) => {
+                warn!(
+                    "could not persist update offset of channel #{}: {err}",
+                    self.channel_id
+                );
             }
         }
     }
@@ -90,35 +313,296 @@ impl TelegramStream {
     async fn send_update(
         &self,
         update: OutgoingChannelUpdateKind,
-    ) -> AppResult<()> {
+    ) -> AppResult<Option<String>> {
         match update {
             | OutgoingChannelUpdateKind::Message {
                 platform_user_id,
+                group_id,
                 kind,
                 timestamp: _,
-            } => match kind {
-                | OutgoingMessageUpdateKind::New { content } => {
-                    self.bot
-                        .send_message(UserId(platform_user_id as u64), content)
-                        .await
-                        .map_err(map_request_error)?;
-
-                    Ok(())
+            } => {
+                let chat_id = ChatId(group_id.unwrap_or(platform_user_id));
+
+                self.send_message_update(chat_id, kind).await
+            }
+        }
+    }
+
+    async fn send_message_update(
+        &self,
+        chat_id: ChatId,
+        kind: OutgoingMessageUpdateKind,
+    ) -> AppResult<Option<String>> {
+        match kind {
+            | OutgoingMessageUpdateKind::New {
+                message_id: _,
+                content,
+                format,
+                attachments,
+                reply_to,
+                keyboard,
+            } => {
+                let reply_to =
+                    reply_to.as_deref().map(parse_message_id).transpose()?;
+
+                self.send_new_message(
+                    chat_id,
+                    content.map(|content| RichText::parse(&content, format)),
+                    attachments,
+                    reply_to,
+                    keyboard.map(reply_markup_of),
+                )
+                .await
+            }
+            | OutgoingMessageUpdateKind::Edit {
+                platform_message_id,
+                content,
+                format,
+            } => {
+                // edits cannot span multiple messages
+                let text = RichText::parse(&content, format);
+
+                if text.len() > MAX_MESSAGE_LENGTH {
+                    return Err(LinkError::InvalidParams(format!(
+                        "edited text exceeds {MAX_MESSAGE_LENGTH} characters"
+                    ))
+                    .into());
                 }
-            },
+
+                let message_id = parse_message_id(&platform_message_id)?;
+
+                let mut req = self.bot.edit_message_text(
+                    chat_id,
+                    message_id,
+                    to_html(&text),
+                );
+                req.payload_mut().parse_mode = Some(ParseMode::Html);
+
+                match req.await {
+                    | Err(err) if is_markup_error(&err) => {
+                        warn!("editing text without formatting: {err}");
+
+                        self.bot
+                            .edit_message_text(
+                                chat_id,
+                                message_id,
+                                text.to_plain_text(),
+                            )
+                            .await
+                            .map_err(map_request_error)?;
+                    }
+                    | ret => {
+                        ret.map_err(map_request_error)?;
+                    }
+                }
+
+                Ok(None)
+            }
+            | OutgoingMessageUpdateKind::Delete {
+                platform_message_id,
+            } => {
+                self.bot
+                    .delete_message(
+                        chat_id,
+                        parse_message_id(&platform_message_id)?,
+                    )
+                    .await
+                    .map_err(map_request_error)?;
+
+                Ok(None)
+            }
         }
     }
 
-    fn convert_from_telegram_update(
+    async fn send_new_message(
+        &self,
+        chat_id: ChatId,
+        content: Option<RichText>,
+        attachments: Vec<Attachment>,
+        mut reply_to: Option<MessageId>,
+        mut keyboard: Option<ReplyMarkup>,
+    ) -> AppResult<Option<String>> {
+        // the first sent message identifies the whole update
+        let mut sent_id = None;
+
+        // captions are limited in length, so long texts are sent in separate
+        // messages before the attachments
+        let mut caption = match content {
+            | Some(text)
+                if attachments.is_empty()
+                    || text.len() > MAX_CAPTION_LENGTH =>
+            {
+                let mut chunks =
+                    text.split(MAX_MESSAGE_LENGTH).into_iter().peekable();
+
+                while let Some(chunk) = chunks.next() {
+                    // the keyboard is shown below the whole text
+                    let chunk_keyboard =
+                        if chunks.peek().is_none() && attachments.is_empty() {
+                            keyboard.take()
+                        } else {
+                            None
+                        };
+
+                    let message = self
+                        .send_text(
+                            chat_id,
+                            &chunk,
+                            reply_to.take(),
+                            chunk_keyboard,
+                        )
+                        .await?;
+
+                    sent_id.get_or_insert(message.id);
+                }
+
+                None
+            }
+            | content => content,
+        };
+
+        for attachment in attachments {
+            let id = self
+                .send_attachment(
+                    chat_id,
+                    attachment,
+                    caption.take(),
+                    reply_to.take(),
+                    keyboard.take(),
+                )
+                .await?;
+
+            sent_id.get_or_insert(id);
+        }
+
+        Ok(sent_id.map(|id| id.0.to_string()))
+    }
+
+    /// Sends a text rendered in html, or as plain text if telegram rejects
+    /// the rendered markup, so that the text is delivered either way.
+    async fn send_text(
+        &self,
+        chat_id: ChatId,
+        text: &RichText,
+        reply_to: Option<MessageId>,
+        keyboard: Option<ReplyMarkup>,
+    ) -> AppResult<Message> {
+        let mut req = self.bot.send_message(chat_id, to_html(text));
+        req.payload_mut().parse_mode = Some(ParseMode::Html);
+        req.payload_mut().reply_to_message_id = reply_to;
+        req.payload_mut().reply_markup = keyboard.clone();
+
+        match req.await {
+            | Err(err) if is_markup_error(&err) => {
+                warn!("sending text without formatting: {err}");
+
+                let mut req =
+                    self.bot.send_message(chat_id, text.to_plain_text());
+                req.payload_mut().reply_to_message_id = reply_to;
+                req.payload_mut().reply_markup = keyboard;
+
+                req.await.map_err(map_request_error)
+            }
+            | ret => ret.map_err(map_request_error),
+        }
+    }
+
+    async fn send_attachment(
+        &self,
+        chat_id: ChatId,
+        attachment: Attachment,
+        caption: Option<RichText>,
+        reply_to: Option<MessageId>,
+        keyboard: Option<ReplyMarkup>,
+    ) -> AppResult<MessageId> {
+        let Attachment { kind, label, uri } = attachment;
+        let caption = caption.map(|caption| to_html(&caption));
+
+        let file = match blob_key_of(&uri) {
+            | Some(key) => {
+                let blob = self.blobs.get(key).await?;
+                let file = InputFile::memory(blob.data);
+
+                match label {
+                    | Some(name) => file.file_name(name),
+                    | None => file,
+                }
+            }
+            | None => match uri.parse::<Url>() {
+                | Ok(url) if matches!(url.scheme(), "http" | "https") => {
+                    InputFile::url(url)
+                }
+                | _ => InputFile::file_id(uri),
+            },
+        };
+
+        let ret = match kind {
+            | AttachmentKind::Document => {
+                let mut req = self.bot.send_document(chat_id, file);
+                req.payload_mut().caption = caption;
+                req.payload_mut().parse_mode = Some(ParseMode::Html);
+                req.payload_mut().reply_to_message_id = reply_to;
+                req.payload_mut().reply_markup = keyboard;
+                req.await
+            }
+            | AttachmentKind::Audio => {
+                let mut req = self.bot.send_audio(chat_id, file);
+                req.payload_mut().caption = caption;
+                req.payload_mut().parse_mode = Some(ParseMode::Html);
+                req.payload_mut().reply_to_message_id = reply_to;
+                req.payload_mut().reply_markup = keyboard;
+                req.await
+            }
+            | AttachmentKind::Video => {
+                let mut req = self.bot.send_video(chat_id, file);
+                req.payload_mut().caption = caption;
+                req.payload_mut().parse_mode = Some(ParseMode::Html);
+                req.payload_mut().reply_to_message_id = reply_to;
+                req.payload_mut().reply_markup = keyboard;
+                req.await
+            }
+            | AttachmentKind::Image => {
+                let mut req = self.bot.send_photo(chat_id, file);
+                req.payload_mut().caption = caption;
+                req.payload_mut().parse_mode = Some(ParseMode::Html);
+                req.payload_mut().reply_to_message_id = reply_to;
+                req.payload_mut().reply_markup = keyboard;
+                req.await
+            }
+            | AttachmentKind::Voice => {
+                let mut req = self.bot.send_voice(chat_id, file);
+                req.payload_mut().caption = caption;
+                req.payload_mut().parse_mode = Some(ParseMode::Html);
+                req.payload_mut().reply_to_message_id = reply_to;
+                req.payload_mut().reply_markup = keyboard;
+                req.await
+            }
+        };
+
+        Ok(ret.map_err(map_request_error)?.id)
+    }
+
+    /// Converts a telegram update, returning `None` if it was sent in a group
+    /// and should be ignored as per the group mode of the channel.
+    async fn convert_from_telegram_update(
         &self,
         update: Update,
-    ) -> AppResult<IncomingChannelUpdateKind> {
+    ) -> AppResult<Option<IncomingChannelUpdateKind>> {
         match update.kind {
             | UpdateKind::Message(msg) => {
-                self.convert_from_telegram_message(msg)
+                self.convert_from_telegram_message(msg).await
+            }
+            // telegram does not notify bots of deleted messages, so only
+            // edits can be propagated
+            | UpdateKind::EditedMessage(msg) => {
+                self.convert_from_telegram_edit(msg).await
             }
+            | UpdateKind::CallbackQuery(query) => {
+                self.convert_from_telegram_callback(query).await
+            }
+            // updates that cannot be parsed fail the same way when refetched
             | UpdateKind::Error(err) => {
-                Err(LinkError::Communication(err.to_string()).into())
+                Err(LinkError::UnsupportedEvent(err.to_string()).into())
             }
             | _ => Err(LinkError::UnsupportedEvent(format!(
                 "unsupported telegram update: {update:#?}"
@@ -127,32 +611,342 @@ impl TelegramStream {
         }
     }
 
-    fn convert_from_telegram_message(
+    async fn convert_from_telegram_message(
         &self,
         message: Message,
-    ) -> AppResult<IncomingChannelUpdateKind> {
+    ) -> AppResult<Option<IncomingChannelUpdateKind>> {
+        let group = group_of(&message);
+
+        if group.is_some() && !self.accepts_group_message(&message).await? {
+            return Ok(None);
+        }
+
         let MessageKind::Common(inner) = message.kind else {
             return Err(LinkError::UnsupportedEvent(format!("unsupported telegram update: {:?}", message.kind)).into());
         };
 
         let Some(from) = inner.from else {
-            return Err(LinkError::UnsupportedEvent("only private chats are supported".into()).into());
+            return Err(LinkError::UnsupportedEvent("messages without a sender are not supported".into()).into());
         };
 
-        let MediaKind::Text(content) = inner.media_kind else {
-            return Err(LinkError::UnsupportedEvent("only text messages supported".into()).into());             
+        let mut profile = profile_of(&from);
+
+        let (content, attachments) = match inner.media_kind {
+            | MediaKind::Text(text) => (Some(text.text), Vec::new()),
+            | MediaKind::Contact(shared) => {
+                let contact = shared.contact;
+
+                // users may share their own contact to reveal their number
+                if contact.user_id == Some(from.id) {
+                    profile.phone_number = Some(contact.phone_number.clone());
+                }
+
+                let name = match contact.last_name {
+                    | Some(last_name) => {
+                        format!("{} {last_name}", contact.first_name)
+                    }
+                    | None => contact.first_name,
+                };
+
+                let content = format!("{name}: {}", contact.phone_number);
+
+                (Some(content), Vec::new())
+            }
+            | MediaKind::Photo(photo) => {
+                // photos are sent in multiple sizes, the last being the largest
+                let Some(size) = photo.photo.into_iter().last() else {
+                    return Err(LinkError::InvalidParams(
+                        "empty telegram photo".into(),
+                    )
+                    .into());
+                };
+
+                let attachment =
+                    attachment_of(AttachmentKind::Image, None, size.file);
+
+                (photo.caption, vec![attachment])
+            }
+            | MediaKind::Document(document) => {
+                let attachment = attachment_of(
+                    AttachmentKind::Document,
+                    document.document.file_name,
+                    document.document.file,
+                );
+
+                (document.caption, vec![attachment])
+            }
+            | MediaKind::Audio(audio) => {
+                let attachment = attachment_of(
+                    AttachmentKind::Audio,
+                    audio.audio.file_name,
+                    audio.audio.file,
+                );
+
+                (audio.caption, vec![attachment])
+            }
+            | MediaKind::Voice(voice) => {
+                let attachment = attachment_of(
+                    AttachmentKind::Voice,
+                    None,
+                    voice.voice.file,
+                );
+
+                (voice.caption, vec![attachment])
+            }
+            | MediaKind::Video(video) => {
+                let attachment = attachment_of(
+                    AttachmentKind::Video,
+                    video.video.file_name,
+                    video.video.file,
+                );
+
+                (video.caption, vec![attachment])
+            }
+            | _ => {
+                return Err(LinkError::UnsupportedEvent(
+                    "unsupported telegram message kind".into(),
+                )
+                .into())
+            }
         };
 
         let platform_user_id = from.id.0 as i64;
         let timestamp = message.date;
         let kind = IncomingMessageUpdateKind::New {
-            content: Some(content.text),
+            platform_message_id: message.id.0.to_string(),
+            content,
+            attachments,
+            reply_to: inner.reply_to_message.map(|m| m.id.0.to_string()),
+        };
+
+        Ok(Some(IncomingChannelUpdateKind::Message {
+            platform_user_id,
+            group,
+            profile,
+            kind,
+            timestamp,
+        }))
+    }
+
+    async fn convert_from_telegram_edit(
+        &self,
+        message: Message,
+    ) -> AppResult<Option<IncomingChannelUpdateKind>> {
+        let group = group_of(&message);
+
+        if group.is_some() && !self.accepts_group_message(&message).await? {
+            return Ok(None);
+        }
+
+        let Some(from) = message.from() else {
+            return Err(LinkError::UnsupportedEvent(
+                "messages without a sender are not supported".into(),
+            )
+            .into());
         };
 
-        Ok(IncomingChannelUpdateKind::Message {
+        let platform_user_id = from.id.0 as i64;
+        let timestamp = message.edit_date().copied().unwrap_or(message.date);
+        let kind = IncomingMessageUpdateKind::Edited {
+            platform_message_id: message.id.0.to_string(),
+            content: message.text().or(message.caption()).map(str::to_owned),
+        };
+
+        Ok(Some(IncomingChannelUpdateKind::Message {
             platform_user_id,
+            group,
+            profile: profile_of(from),
             kind,
             timestamp,
+        }))
+    }
+
+    async fn convert_from_telegram_callback(
+        &self,
+        query: CallbackQuery,
+    ) -> AppResult<Option<IncomingChannelUpdateKind>> {
+        // the press is acknowledged right away, so that the button stops
+        // showing a loading indicator
+        if let Err(err) = self.bot.answer_callback_query(query.id.clone()).await
+        {
+            warn!("could not answer telegram callback query: {err}");
+        }
+
+        let Some(data) = query.data else {
+            return Err(LinkError::UnsupportedEvent(
+                "callback queries without data are not supported".into(),
+            )
+            .into());
+        };
+
+        // presses are addressed to the bot, so they need no mention
+        let group = query.message.as_ref().and_then(group_of);
+
+        if group.is_some() && self.group_mode == ChannelGroupMode::Disabled {
+            return Ok(None);
+        }
+
+        let text = query
+            .message
+            .as_ref()
+            .and_then(|message| button_text_of(message, &data));
+
+        let kind = IncomingMessageUpdateKind::ButtonPressed {
+            callback_id: query.id,
+            platform_message_id: query.message.map(|m| m.id.0.to_string()),
+            text,
+            data,
+        };
+
+        Ok(Some(IncomingChannelUpdateKind::Message {
+            platform_user_id: query.from.id.0 as i64,
+            group,
+            profile: profile_of(&query.from),
+            kind,
+            timestamp: Utc::now(),
+        }))
+    }
+
+    /// Whether a message sent in a group should be handled, as per the group
+    /// mode of the channel.
+    async fn accepts_group_message(
+        &self,
+        message: &Message,
+    ) -> AppResult<bool> {
+        match self.group_mode {
+            | ChannelGroupMode::Disabled => return Ok(false),
+            | ChannelGroupMode::All => return Ok(true),
+            | ChannelGroupMode::Mentions => {}
+        }
+
+        let me = self
+            .me
+            .get_or_try_init(|| async {
+                self.bot.get_me().await.map_err(map_request_error)
+            })
+            .await?;
+
+        let is_reply_to_me = message
+            .reply_to_message()
+            .and_then(|target| target.from())
+            .map_or(false, |sender| sender.id == me.user.id);
+
+        if is_reply_to_me {
+            return Ok(true);
+        }
+
+        let (Some(text), Some(entities)) = (
+            message.text().or(message.caption()),
+            message.entities().or(message.caption_entities()),
+        ) else {
+            return Ok(false);
+        };
+
+        let username = me.user.username.as_deref().unwrap_or_default();
+
+        Ok(entities.iter().any(|entity| match entity.kind {
+            | MessageEntityKind::TextMention { ref user } => {
+                user.id == me.user.id
+            }
+            | MessageEntityKind::Mention => entity_text(text, entity)
+                .trim_start_matches('@')
+                .eq_ignore_ascii_case(username),
+            // commands may be addressed to a specific bot of the group
+            | MessageEntityKind::BotCommand => {
+                match entity_text(text, entity).split_once('@') {
+                    | Some((_, target)) => {
+                        target.eq_ignore_ascii_case(username)
+                    }
+                    | None => true,
+                }
+            }
+            | _ => false,
+        }))
+    }
+}
+
+fn group_of(message: &Message) -> Option<PlatformGroup> {
+    if !message.chat.is_group() && !message.chat.is_supergroup() {
+        return None;
+    }
+
+    Some(PlatformGroup {
+        id: message.chat.id.0,
+        title: message.chat.title().map(str::to_owned),
+    })
+}
+
+/// Gets the text of an entity, whose offset and length are in utf-16 code
+/// units.
+fn entity_text(text: &str, entity: &MessageEntity) -> String {
+    let units = text
+        .encode_utf16()
+        .skip(entity.offset)
+        .take(entity.length)
+        .collect::<Vec<_>>();
+
+    String::from_utf16_lossy(&units)
+}
+
+/// Gets the label of the inline button of a message with the given data.
+fn button_text_of(message: &Message, data: &str) -> Option<String> {
+    message
+        .reply_markup()?
+        .inline_keyboard
+        .iter()
+        .flatten()
+        .find(|button| match button.kind {
+            | InlineKeyboardButtonKind::CallbackData(ref d) => d == data,
+            | _ => false,
         })
+        .map(|button| button.text.clone())
+}
+
+fn reply_markup_of(keyboard: MessageKeyboard) -> ReplyMarkup {
+    match keyboard {
+        | MessageKeyboard::Reply(rows) => {
+            ReplyMarkup::Keyboard(KeyboardMarkup::new(
+                rows.into_iter()
+                    .map(|row| row.into_iter().map(KeyboardButton::new)),
+            ))
+        }
+        | MessageKeyboard::Inline(rows) => ReplyMarkup::InlineKeyboard(
+            InlineKeyboardMarkup::new(rows.into_iter().map(|row| {
+                row.into_iter().map(|button| {
+                    InlineKeyboardButton::callback(button.text, button.data)
+                })
+            })),
+        ),
+    }
+}
+
+fn parse_message_id(platform_message_id: &str) -> AppResult<MessageId> {
+    platform_message_id.parse().map(MessageId).map_err(|err| {
+        LinkError::InvalidParams(format!(
+            "invalid telegram message id `{platform_message_id}`: {err}"
+        ))
+        .into()
+    })
+}
+
+fn profile_of(user: &User) -> SenderProfile {
+    SenderProfile {
+        username: user.username.clone(),
+        display_name: Some(user.full_name()),
+        phone_number: None,
+    }
+}
+
+fn attachment_of(
+    kind: AttachmentKind,
+    label: Option<String>,
+    file: FileMeta,
+) -> Attachment {
+    // the file id is only valid for this bot, and is used as-is when the
+    // attachment is sent back through the same channel
+    Attachment {
+        kind,
+        label,
+        uri: file.id,
     }
 }
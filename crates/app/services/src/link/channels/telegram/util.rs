@@ -1,5 +1,36 @@
 use kernel_services::{error::AppError, link::error::LinkError};
 
 pub(super) fn map_request_error(err: teloxide::RequestError) -> AppError {
-    LinkError::InternalError(err.into()).into()
+    match err {
+        | teloxide::RequestError::RetryAfter(delay) => {
+            LinkError::RateLimited(delay).into()
+        }
+        // connection failures and timeouts are worth retrying, while errors
+        // reported by the api are not
+        | teloxide::RequestError::Network(_)
+        | teloxide::RequestError::Io(_) => {
+            LinkError::Communication(err.to_string()).into()
+        }
+        | err => LinkError::InternalError(err.into()).into(),
+    }
+}
+
+/// Whether telegram rejected a request because of the markup of its text.
+pub(super) fn is_markup_error(err: &teloxide::RequestError) -> bool {
+    use teloxide::{ApiError, RequestError};
+
+    match err {
+        | RequestError::Api(ApiError::CantParseEntities { .. }) => true,
+        // details of the error are appended to its description, which makes
+        // it unknown to teloxide
+        | RequestError::Api(ApiError::Unknown(description)) => {
+            description.contains("can't parse entities")
+        }
+        | _ => false,
+    }
+}
+
+/// Downloads only fail on connection or io errors, which are transient.
+pub(super) fn map_download_error(err: teloxide::DownloadError) -> AppError {
+    LinkError::Communication(err.to_string()).into()
 }
@@ -0,0 +1,59 @@
+use kernel_services::link::rich_text::{RichSpan, RichText, TextStyle};
+
+/// Renders a text in the formatting markers of whatsapp, which offers no way
+/// of escaping them; links are rendered as their text followed by their url.
+pub(super) fn to_whatsapp_markup(text: &RichText) -> String {
+    let mut markup = String::new();
+
+    for span in text.spans.iter().filter(|span| !span.is_empty()) {
+        match span {
+            | RichSpan::Text { text, style } => {
+                push_styled(&mut markup, text, *style);
+            }
+            | RichSpan::Link { text, url, style } if text != url => {
+                push_styled(&mut markup, text, *style);
+                markup += &format!(" ({url})");
+            }
+            | RichSpan::Link { text, style, .. } => {
+                push_styled(&mut markup, text, *style);
+            }
+            | RichSpan::Code(code) => markup += &format!("`{code}`"),
+            | RichSpan::Pre { code, .. } => {
+                markup += &format!("```{code}```");
+            }
+        }
+    }
+
+    markup
+}
+
+fn push_styled(markup: &mut String, text: &str, style: TextStyle) {
+    // markers must enclose the text directly, without surrounding spaces
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+
+    if start >= end {
+        *markup += text;
+        return;
+    }
+
+    let markers = [
+        (style.bold, '*'),
+        (style.italic, '_'),
+        (style.strikethrough, '~'),
+    ];
+
+    *markup += &text[..start];
+
+    for (_, marker) in markers.iter().filter(|(enabled, _)| *enabled) {
+        markup.push(*marker);
+    }
+
+    *markup += &text[start..end];
+
+    for (_, marker) in markers.iter().rev().filter(|(enabled, _)| *enabled) {
+        markup.push(*marker);
+    }
+
+    *markup += &text[end..];
+}
@@ -0,0 +1,173 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{
+        AsyncBufRead,
+        AsyncBufReadExt,
+        AsyncReadExt,
+        AsyncWriteExt,
+        BufReader,
+    },
+    net::TcpListener,
+};
+
+/// A request received by the mock graph api.
+#[derive(Clone, Debug)]
+pub(super) struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub authorization: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// A canned response of the mock graph api.
+#[derive(Clone, Debug)]
+pub(super) struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+/// A local http server standing in for the graph api, answering each request
+/// with the response of the handler and recording it for later assertions.
+pub(super) struct MockGraphApi {
+    pub url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockResponse {
+    pub(super) fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type", "application/json".to_owned())],
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    pub(super) fn bytes(content_type: &str, body: &[u8]) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("content-type", content_type.to_owned())],
+            body: body.to_vec(),
+        }
+    }
+
+    pub(super) fn with_header(
+        mut self,
+        name: &'static str,
+        value: &str,
+    ) -> Self {
+        self.headers.push((name, value.to_owned()));
+        self
+    }
+}
+
+impl MockGraphApi {
+    pub(super) async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("mock graph api could not bind");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let handler = Arc::new(handler);
+
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let handler = handler.clone();
+
+                tokio::spawn(async move {
+                    let (reader, mut writer) = socket.into_split();
+                    let mut reader = BufReader::new(reader);
+
+                    // connections are kept alive by the client
+                    while let Some(request) = read_request(&mut reader).await {
+                        let response = handler(&request);
+                        recorded.lock().unwrap().push(request);
+
+                        if writer.write_all(&encode(response)).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub(super) fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request<R>(reader: &mut R) -> Option<MockRequest>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = String::new();
+
+    if reader.read_line(&mut line).await.ok()? == 0 {
+        return None;
+    }
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_owned();
+    let path = parts.next()?.to_owned();
+
+    let mut authorization = None;
+    let mut content_length = 0;
+
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await.ok()?;
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        let (name, value) = header.split_once(':')?;
+        let value = value.trim();
+
+        match name.to_ascii_lowercase().as_str() {
+            | "content-length" => content_length = value.parse().ok()?,
+            | "authorization" => authorization = Some(value.to_owned()),
+            | _ => {}
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some(MockRequest {
+        method,
+        path,
+        authorization,
+        body,
+    })
+}
+
+fn encode(response: MockResponse) -> Vec<u8> {
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\ncontent-length: {}\r\n",
+        response.status,
+        response.body.len()
+    );
+
+    for (name, value) in response.headers {
+        head += &format!("{name}: {value}\r\n");
+    }
+
+    head += "\r\n";
+
+    let mut encoded = head.into_bytes();
+    encoded.extend(response.body);
+    encoded
+}
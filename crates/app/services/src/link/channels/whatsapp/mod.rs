@@ -0,0 +1,7 @@
+mod markup;
+#[cfg(test)]
+mod mock_graph_api;
+mod models;
+mod util;
+
+pub(super) mod whatsapp_stream;
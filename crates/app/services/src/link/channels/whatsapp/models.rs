@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+
+pub(super) const WEBHOOK_OBJECT: &str = "whatsapp_business_account";
+pub(super) const MESSAGES_FIELD: &str = "messages";
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookPayload {
+    pub object: String,
+    #[serde(default)]
+    pub entry: Vec<WebhookEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookEntry {
+    #[serde(default)]
+    pub changes: Vec<WebhookChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookChange {
+    pub field: String,
+    pub value: WebhookValue,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookValue {
+    pub metadata: WebhookMetadata,
+    #[serde(default)]
+    pub contacts: Vec<WebhookContact>,
+    #[serde(default)]
+    pub messages: Vec<WebhookMessage>,
+    #[serde(default)]
+    pub statuses: Vec<WebhookStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookMetadata {
+    pub phone_number_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookContact {
+    pub wa_id: String,
+    pub profile: Option<WebhookContactProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookContactProfile {
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookMessage {
+    pub id: String,
+    pub from: String,
+    pub timestamp: String,
+    pub context: Option<WebhookContext>,
+    #[serde(flatten)]
+    pub content: WebhookMessageContent,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookContext {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(super) enum WebhookMessageContent {
+    Text {
+        text: TextObject,
+    },
+    Image {
+        image: MediaObject,
+    },
+    Document {
+        document: MediaObject,
+    },
+    Audio {
+        audio: MediaObject,
+    },
+    Video {
+        video: MediaObject,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct WebhookStatus {
+    pub id: String,
+    pub status: WebhookStatusKind,
+    pub timestamp: String,
+    pub recipient_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum WebhookStatusKind {
+    Sent,
+    Delivered,
+    Read,
+    Failed,
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct TextObject {
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct MediaObject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub voice: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct MediaUrlResponse {
+    pub url: String,
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct MediaUploadResponse {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct SendMessageResponse {
+    #[serde(default)]
+    pub messages: Vec<SentMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct SentMessage {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct SendMessageRequest {
+    pub messaging_product: &'static str,
+    pub recipient_type: &'static str,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<ContextObject>,
+    #[serde(flatten)]
+    pub content: SendMessageContent,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ContextObject {
+    pub message_id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(super) enum SendMessageContent {
+    Text { text: TextObject },
+    Image { image: MediaObject },
+    Document { document: MediaObject },
+    Audio { audio: MediaObject },
+    Video { video: MediaObject },
+}
+
+impl SendMessageRequest {
+    pub(super) fn new(to: String, content: SendMessageContent) -> Self {
+        Self {
+            messaging_product: "whatsapp",
+            recipient_type: "individual",
+            to,
+            context: None,
+            content,
+        }
+    }
+
+    pub(super) fn replying_to(mut self, message_id: Option<String>) -> Self {
+        self.context =
+            message_id.map(|message_id| ContextObject { message_id });
+        self
+    }
+}
+
+impl MediaObject {
+    pub(super) fn from_uri(
+        uri: String,
+        caption: Option<String>,
+        filename: Option<String>,
+    ) -> Self {
+        // media can either be referenced by a previously uploaded media id,
+        // or by a public link
+        let (id, link) =
+            if uri.starts_with("http://") || uri.starts_with("https://") {
+                (None, Some(uri))
+            } else {
+                (Some(uri), None)
+            };
+
+        Self {
+            id,
+            link,
+            caption,
+            filename,
+            voice: false,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use hmac::{Hmac, KeyInit, Mac};
+use kernel_services::{error::AppError, link::error::LinkError};
+use sha2::Sha256;
+
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// Maps a failed request to the whatsapp api, where connection failures,
+/// timeouts and server errors are transient, and the rest are not.
+pub(super) fn map_request_error(err: reqwest::Error) -> AppError {
+    let is_transient = err.is_connect()
+        || err.is_timeout()
+        || err.is_request()
+        || err
+            .status()
+            .map_or(false, |status| status.is_server_error());
+
+    match is_transient {
+        | true => LinkError::Communication(err.to_string()).into(),
+        | false => LinkError::InternalError(err.into()).into(),
+    }
+}
+
+/// Checks a `X-Hub-Signature-256` header value, which is the hex encoded
+/// HMAC-SHA256 of the webhook payload keyed with the app secret, comparing
+/// the digests in constant time.
+pub(super) fn is_valid_signature(
+    app_secret: &str,
+    signature: &str,
+    payload: &[u8],
+) -> bool {
+    let Some(digest) = signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|digest| hex::decode(digest).ok())
+    else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes())
+    else {
+        return false;
+    };
+
+    mac.update(payload);
+    mac.verify_slice(&digest).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_SECRET: &str = "6f1d2c3b4a5e";
+    const PAYLOAD: &[u8] = br#"{"object":"whatsapp_business_account"}"#;
+
+    fn sign(secret: &str, payload: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .expect("hmac accepts keys of any length");
+        mac.update(payload);
+
+        format!(
+            "{SIGNATURE_PREFIX}{}",
+            hex::encode(mac.finalize().into_bytes())
+        )
+    }
+
+    #[test]
+    fn accepts_signed_payloads() {
+        let signature = sign(APP_SECRET, PAYLOAD);
+
+        assert!(is_valid_signature(APP_SECRET, &signature, PAYLOAD));
+    }
+
+    #[test]
+    fn rejects_unsigned_payloads() {
+        assert!(!is_valid_signature(APP_SECRET, "", PAYLOAD));
+        assert!(!is_valid_signature(APP_SECRET, SIGNATURE_PREFIX, PAYLOAD));
+    }
+
+    #[test]
+    fn rejects_payloads_signed_with_other_secrets() {
+        let signature = sign("another secret", PAYLOAD);
+
+        assert!(!is_valid_signature(APP_SECRET, &signature, PAYLOAD));
+    }
+
+    #[test]
+    fn rejects_tampered_payloads() {
+        let signature = sign(APP_SECRET, PAYLOAD);
+
+        assert!(!is_valid_signature(APP_SECRET, &signature, b"{}"));
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        let signature = sign(APP_SECRET, PAYLOAD);
+        let unprefixed = signature.trim_start_matches(SIGNATURE_PREFIX);
+
+        assert!(!is_valid_signature(APP_SECRET, unprefixed, PAYLOAD));
+        assert!(!is_valid_signature(APP_SECRET, "sha256=zz", PAYLOAD));
+        assert!(!is_valid_signature(APP_SECRET, "sha1=00", PAYLOAD));
+    }
+}
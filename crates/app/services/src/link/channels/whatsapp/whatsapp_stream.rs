@@ -0,0 +1,1039 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeZone, Utc};
+use kernel_entities::entities::{
+    comm::{Attachment, AttachmentKind, MessageStatus},
+    link::Channel,
+};
+use kernel_services::{
+    error::{AppResult, AuthError},
+    link::{
+        channels::{
+            IncomingChannelUpdateKind,
+            IncomingMessageUpdateKind,
+            MessageKeyboard,
+            OutgoingChannelUpdateKind,
+            OutgoingMessageUpdateKind,
+            SenderProfile,
+        },
+        error::LinkError,
+        rich_text::{RichSpan, RichText, TextStyle},
+    },
+    storage::blob::{blob_key_of, Blob, BlobStorageService},
+};
+use reqwest::{
+    header::RETRY_AFTER,
+    multipart::{Form, Part},
+    Response,
+    StatusCode,
+};
+
+use super::{
+    markup::to_whatsapp_markup,
+    models::*,
+    util::{is_valid_signature, map_request_error},
+};
+use crate::link::channels::{
+    channel_stream::ChannelStream,
+    config::WhatsAppConfig,
+};
+
+const MAX_MESSAGE_LENGTH: usize = 4096;
+const MAX_CAPTION_LENGTH: usize = 1024;
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+pub(crate) struct WhatsAppStream {
+    client: reqwest::Client,
+    blobs: Arc<dyn BlobStorageService>,
+    access_token: String,
+    phone_number_id: String,
+    graph_url: String,
+    messages_url: String,
+    media_url: String,
+}
+
+#[async_trait::async_trait]
+impl ChannelStream for WhatsAppStream {
+    async fn recv(&self) -> AppResult<IncomingChannelUpdateKind> {
+        // whatsapp updates are delivered through webhooks, and are published
+        // directly to the channel pipe; see `parse_webhook_payload`
+        futures::future::pending().await
+    }
+
+    async fn send(
+        &self,
+        update: OutgoingChannelUpdateKind,
+    ) -> AppResult<Option<String>> {
+        self.send_update(update).await
+    }
+
+    async fn fetch_media(&self, uri: &str) -> AppResult<Blob> {
+        // media ids are first resolved into temporary download urls
+        let media: MediaUrlResponse = self
+            .execute(self.client.get(format!("{}/{uri}", self.graph_url)))
+            .await?
+            .json()
+            .await
+            .map_err(map_request_error)?;
+
+        let data = self
+            .execute(self.client.get(&media.url))
+            .await?
+            .bytes()
+            .await
+            .map_err(map_request_error)?;
+
+        Ok(Blob {
+            content_type: media.mime_type,
+            data: data.to_vec(),
+        })
+    }
+
+    async fn parse_webhook_payload(
+        &self,
+        payload: &[u8],
+    ) -> AppResult<Vec<IncomingChannelUpdateKind>> {
+        let payload: WebhookPayload = serde_json::from_slice(payload)
+            .map_err(|err| LinkError::InvalidParams(err.to_string()))?;
+
+        if payload.object != WEBHOOK_OBJECT {
+            return Err(LinkError::UnsupportedEvent(format!(
+                "unsupported whatsapp webhook object: {}",
+                payload.object
+            ))
+            .into());
+        }
+
+        let mut updates = Vec::new();
+        let mut statuses = Vec::new();
+
+        for change in payload.entry.into_iter().flat_map(|e| e.changes) {
+            if change.field != MESSAGES_FIELD {
+                debug!("skipping whatsapp webhook field: {}", change.field);
+                continue;
+            }
+
+            if change.value.metadata.phone_number_id != self.phone_number_id {
+                warn!(
+                    "skipping update of phone number #{}, expected #{}",
+                    change.value.metadata.phone_number_id, self.phone_number_id
+                );
+                continue;
+            }
+
+            let contacts = change.value.contacts;
+
+            for message in change.value.messages {
+                let contact = contacts.iter().find(|c| c.wa_id == message.from);
+
+                match convert_from_whatsapp_message(message, contact) {
+                    | Ok(update) => updates.push(update),
+                    | Err(err) => warn!("skipping whatsapp message: {err}"),
+                }
+            }
+
+            statuses.extend(change.value.statuses);
+        }
+
+        // statuses may be batched out of order, such as `read` before
+        // `delivered`, so they are applied in the order they happened
+        statuses.sort_by_key(|status| status.timestamp.parse::<i64>().ok());
+
+        for status in statuses {
+            match convert_from_whatsapp_status(status) {
+                | Ok(update) => updates.push(update),
+                | Err(err) => warn!("skipping whatsapp status: {err}"),
+            }
+        }
+
+        Ok(updates)
+    }
+}
+
+impl WhatsAppStream {
+    pub(crate) fn new(
+        channel: &Channel,
+        blobs: Arc<dyn BlobStorageService>,
+        config: &WhatsAppConfig,
+    ) -> AppResult<Arc<Self>> {
+        let phone_number_id = get_phone_number_id(channel)?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .map_err(|err| LinkError::Communication(err.to_string()))?;
+
+        let graph_url = format!(
+            "{}/{}",
+            config.api_url.trim_end_matches('/'),
+            config.api_version,
+        );
+
+        Ok(Arc::new(Self {
+            client,
+            blobs,
+            access_token: channel.api_key.clone(),
+            messages_url: format!("{graph_url}/{phone_number_id}/messages"),
+            media_url: format!("{graph_url}/{phone_number_id}/media"),
+            phone_number_id: phone_number_id.to_owned(),
+            graph_url,
+        }))
+    }
+
+    /// Verifies that a webhook payload was signed with the app secret of the
+    /// channel, rejecting all payloads of channels without one.
+    pub(crate) fn verify_webhook_signature(
+        channel: &Channel,
+        signature: Option<&str>,
+        payload: &[u8],
+    ) -> AppResult<()> {
+        match (&channel.app_secret, signature) {
+            | (Some(app_secret), Some(signature))
+                if is_valid_signature(app_secret, signature, payload) =>
+            {
+                Ok(())
+            }
+            | (None, _) => {
+                warn!("channel #{} has no whatsapp app secret", channel.id);
+
+                Err(AuthError::NotAuthenticated.into())
+            }
+            | _ => Err(AuthError::NotAuthenticated.into()),
+        }
+    }
+
+    #[inline]
+    async fn send_update(
+        &self,
+        update: OutgoingChannelUpdateKind,
+    ) -> AppResult<Option<String>> {
+        match update {
+            // whatsapp cloud api does not support groups
+            | OutgoingChannelUpdateKind::Message {
+                platform_user_id,
+                group_id: _,
+                kind,
+                timestamp: _,
+            } => match kind {
+                | OutgoingMessageUpdateKind::New {
+                    message_id: _,
+                    content,
+                    format,
+                    attachments,
+                    reply_to,
+                    keyboard,
+                } => {
+                    let content = content
+                        .map(|content| RichText::parse(&content, format));
+
+                    // buttons are not supported, so their labels are listed
+                    // instead, to be typed back by the user
+                    let content = match keyboard {
+                        | Some(keyboard) => {
+                            Some(with_button_labels(content, &keyboard))
+                        }
+                        | None => content,
+                    };
+
+                    self.send_new_message(
+                        platform_user_id.to_string(),
+                        content,
+                        attachments,
+                        reply_to,
+                    )
+                    .await
+                }
+                // the cloud api does not support modifying sent messages;
+                // such updates are dropped to avoid redelivering them
+                | OutgoingMessageUpdateKind::Edit { .. }
+                | OutgoingMessageUpdateKind::Delete { .. } => {
+                    warn!("whatsapp messages cannot be edited nor deleted");
+
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    async fn send_new_message(
+        &self,
+        to: String,
+        content: Option<RichText>,
+        attachments: Vec<Attachment>,
+        mut reply_to: Option<String>,
+    ) -> AppResult<Option<String>> {
+        // the first sent message identifies the whole update, and is the
+        // one replying to the target message, if any
+        let mut sent_id = None;
+
+        // captions are limited in length, so long texts are sent in separate
+        // messages before the attachments
+        let mut caption = match content {
+            | Some(text)
+                if attachments.is_empty()
+                    || text.len() > MAX_CAPTION_LENGTH =>
+            {
+                for chunk in text.split(MAX_MESSAGE_LENGTH) {
+                    let body = to_whatsapp_markup(&chunk);
+                    let id = self
+                        .send_text(to.clone(), body, reply_to.take())
+                        .await?;
+
+                    sent_id = sent_id.or(id);
+                }
+
+                None
+            }
+            | content => content.map(|text| to_whatsapp_markup(&text)),
+        };
+
+        for mut attachment in attachments {
+            if let Some(key) = blob_key_of(&attachment.uri).map(str::to_owned) {
+                attachment.uri =
+                    self.upload_media(&key, attachment.label.clone()).await?;
+            }
+
+            let content = convert_to_whatsapp_media(attachment, &mut caption);
+
+            let id = self
+                .send_request(
+                    SendMessageRequest::new(to.clone(), content)
+                        .replying_to(reply_to.take()),
+                )
+                .await?;
+
+            sent_id = sent_id.or(id);
+        }
+
+        // audio messages cannot have captions
+        if let Some(text) = caption {
+            let id = self.send_text(to, text, reply_to.take()).await?;
+
+            sent_id = sent_id.or(id);
+        }
+
+        Ok(sent_id)
+    }
+
+    async fn send_text(
+        &self,
+        to: String,
+        body: String,
+        reply_to: Option<String>,
+    ) -> AppResult<Option<String>> {
+        self.send_request(
+            SendMessageRequest::new(
+                to,
+                SendMessageContent::Text {
+                    text: TextObject { body },
+                },
+            )
+            .replying_to(reply_to),
+        )
+        .await
+    }
+
+    async fn upload_media(
+        &self,
+        key: &str,
+        file_name: Option<String>,
+    ) -> AppResult<String> {
+        let blob = self.blobs.get(key).await?;
+        let content_type = blob
+            .content_type
+            .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_owned());
+
+        let file = Part::bytes(blob.data)
+            .file_name(file_name.unwrap_or_else(|| {
+                key.rsplit('/').next().unwrap_or(key).to_owned()
+            }))
+            .mime_str(&content_type)
+            .map_err(map_request_error)?;
+
+        let form = Form::new()
+            .text("messaging_product", "whatsapp")
+            .text("type", content_type)
+            .part("file", file);
+
+        let media: MediaUploadResponse = self
+            .execute(self.client.post(&self.media_url).multipart(form))
+            .await?
+            .json()
+            .await
+            .map_err(map_request_error)?;
+
+        Ok(media.id)
+    }
+
+    async fn send_request(
+        &self,
+        request: SendMessageRequest,
+    ) -> AppResult<Option<String>> {
+        let response: SendMessageResponse = self
+            .execute(self.client.post(&self.messages_url).json(&request))
+            .await?
+            .json()
+            .await
+            .map_err(map_request_error)?;
+
+        Ok(response.messages.into_iter().next().map(|m| m.id))
+    }
+
+    async fn execute(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> AppResult<Response> {
+        let response = request
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(map_request_error)?;
+
+        let status = response.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let delay = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_RETRY_AFTER);
+
+            return Err(LinkError::RateLimited(delay).into());
+        }
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let message =
+                format!("whatsapp api responded with `{status}`: {body}");
+
+            // requests rejected by the api fail the same way when retried
+            return Err(match status.is_server_error() {
+                | true => LinkError::Communication(message),
+                | false => LinkError::InternalError(anyhow::anyhow!(message)),
+            }
+            .into());
+        }
+
+        Ok(response)
+    }
+}
+
+fn get_phone_number_id(channel: &Channel) -> AppResult<&str> {
+    channel.phone_number_id.as_deref().ok_or_else(|| {
+        LinkError::InvalidParams(format!(
+            "channel #{} has no whatsapp phone number id",
+            channel.id
+        ))
+        .into()
+    })
+}
+
+fn convert_from_whatsapp_message(
+    message: WebhookMessage,
+    contact: Option<&WebhookContact>,
+) -> AppResult<IncomingChannelUpdateKind> {
+    let (content, attachments) = match message.content {
+        | WebhookMessageContent::Text { text } => (Some(text.body), Vec::new()),
+        | WebhookMessageContent::Image { image } => {
+            convert_from_whatsapp_media(AttachmentKind::Image, image)?
+        }
+        | WebhookMessageContent::Document { document } => {
+            convert_from_whatsapp_media(AttachmentKind::Document, document)?
+        }
+        | WebhookMessageContent::Audio { audio } => {
+            let kind = match audio.voice {
+                | true => AttachmentKind::Voice,
+                | false => AttachmentKind::Audio,
+            };
+
+            convert_from_whatsapp_media(kind, audio)?
+        }
+        | WebhookMessageContent::Video { video } => {
+            convert_from_whatsapp_media(AttachmentKind::Video, video)?
+        }
+        | WebhookMessageContent::Unsupported => {
+            return Err(LinkError::UnsupportedEvent(
+                "unsupported whatsapp message type".into(),
+            )
+            .into())
+        }
+    };
+
+    let platform_user_id = parse_user_id(&message.from)?;
+    let timestamp = parse_timestamp(&message.timestamp)?;
+
+    // the sender id is its phone number, in international format
+    let profile = SenderProfile {
+        username: None,
+        display_name: contact
+            .and_then(|c| c.profile.as_ref())
+            .and_then(|p| p.name.clone()),
+        phone_number: Some(format!("+{}", message.from)),
+    };
+
+    Ok(IncomingChannelUpdateKind::Message {
+        platform_user_id,
+        group: None,
+        profile,
+        kind: IncomingMessageUpdateKind::New {
+            platform_message_id: message.id,
+            content,
+            attachments,
+            reply_to: message.context.map(|context| context.id),
+        },
+        timestamp,
+    })
+}
+
+fn convert_from_whatsapp_status(
+    status: WebhookStatus,
+) -> AppResult<IncomingChannelUpdateKind> {
+    let kind = match status.status {
+        | WebhookStatusKind::Sent => MessageStatus::Sent,
+        | WebhookStatusKind::Delivered => MessageStatus::Delivered,
+        | WebhookStatusKind::Read => MessageStatus::Seen,
+        | WebhookStatusKind::Failed => MessageStatus::Failed,
+        | WebhookStatusKind::Unsupported => {
+            return Err(LinkError::UnsupportedEvent(
+                "unsupported whatsapp message status".into(),
+            )
+            .into())
+        }
+    };
+
+    Ok(IncomingChannelUpdateKind::MessageStatus {
+        platform_user_id: parse_user_id(&status.recipient_id)?,
+        message_id: None,
+        platform_message_id: Some(status.id),
+        status: kind,
+        timestamp: parse_timestamp(&status.timestamp)?,
+    })
+}
+
+fn parse_user_id(value: &str) -> AppResult<i64> {
+    value.parse::<i64>().map_err(|err| {
+        LinkError::InvalidParams(format!(
+            "invalid whatsapp id `{value}`: {err}"
+        ))
+        .into()
+    })
+}
+
+fn parse_timestamp(value: &str) -> AppResult<DateTime<Utc>> {
+    value
+        .parse::<i64>()
+        .ok()
+        .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+        .ok_or_else(|| {
+            LinkError::InvalidParams(format!(
+                "invalid whatsapp timestamp: {value}"
+            ))
+            .into()
+        })
+}
+
+fn with_button_labels(
+    content: Option<RichText>,
+    keyboard: &MessageKeyboard,
+) -> RichText {
+    let mut text = content.unwrap_or_default();
+    let mut labels = String::from(if text.is_empty() { "" } else { "\n" });
+
+    for label in keyboard.labels().into_iter().flatten() {
+        labels += &format!("\n- {label}");
+    }
+
+    text.spans.push(RichSpan::Text {
+        text: labels,
+        style: TextStyle::default(),
+    });
+
+    text
+}
+
+fn convert_from_whatsapp_media(
+    kind: AttachmentKind,
+    media: MediaObject,
+) -> AppResult<(Option<String>, Vec<Attachment>)> {
+    let Some(id) = media.id else {
+        return Err(LinkError::InvalidParams(
+            "whatsapp media has no id".into(),
+        )
+        .into());
+    };
+
+    let attachment = Attachment {
+        kind,
+        label: media.filename,
+        uri: id,
+    };
+
+    Ok((media.caption, vec![attachment]))
+}
+
+fn convert_to_whatsapp_media(
+    attachment: Attachment,
+    caption: &mut Option<String>,
+) -> SendMessageContent {
+    let Attachment { kind, label, uri } = attachment;
+
+    match kind {
+        | AttachmentKind::Document => SendMessageContent::Document {
+            document: MediaObject::from_uri(uri, caption.take(), label),
+        },
+        | AttachmentKind::Image => SendMessageContent::Image {
+            image: MediaObject::from_uri(uri, caption.take(), None),
+        },
+        | AttachmentKind::Video => SendMessageContent::Video {
+            video: MediaObject::from_uri(uri, caption.take(), None),
+        },
+        | AttachmentKind::Audio | AttachmentKind::Voice => {
+            SendMessageContent::Audio {
+                audio: MediaObject::from_uri(uri, None, None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel_entities::{
+        entities::link::{ChannelGroupMode, ChannelPlatform},
+        traits::Key,
+    };
+    use kernel_services::{
+        error::AppError,
+        link::rich_text::TextFormat,
+        Service,
+    };
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::link::channels::whatsapp::mock_graph_api::{
+        MockGraphApi,
+        MockResponse,
+    };
+
+    const API_VERSION: &str = "v17.0";
+    const PHONE_NUMBER_ID: &str = "106540352242922";
+    const ACCESS_TOKEN: &str = "EAAJB3d1";
+
+    struct NoBlobs;
+
+    #[async_trait::async_trait]
+    impl Service for NoBlobs {
+        async fn initialize(self: Arc<Self>) -> AppResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl BlobStorageService for NoBlobs {
+        async fn put(&self, _key: &str, _blob: Blob) -> AppResult<()> {
+            unreachable!("blobs are not stored by these tests")
+        }
+
+        async fn get(&self, _key: &str) -> AppResult<Blob> {
+            unreachable!("blobs are not read by these tests")
+        }
+
+        async fn remove(&self, _key: &str) -> AppResult<()> {
+            unreachable!("blobs are not removed by these tests")
+        }
+
+        async fn exists(&self, _key: &str) -> AppResult<bool> {
+            unreachable!("blobs are not read by these tests")
+        }
+    }
+
+    fn channel() -> Channel {
+        Channel {
+            id: Key::new(Uuid::new_v4()),
+            name: "support".into(),
+            platform: ChannelPlatform::WhatsApp,
+            api_key: ACCESS_TOKEN.into(),
+            phone_number_id: Some(PHONE_NUMBER_ID.into()),
+            webhook_secret: None,
+            app_secret: None,
+            use_webhook: true,
+            group_mode: ChannelGroupMode::default(),
+            valid_until: None,
+            is_active: true,
+            max_instances: None,
+            user_id: Key::new(Uuid::new_v4()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn stream_of(api: &MockGraphApi) -> Arc<WhatsAppStream> {
+        let config = WhatsAppConfig {
+            api_url: api.url.clone(),
+            api_version: API_VERSION.into(),
+            timeout_seconds: 5,
+        };
+
+        WhatsAppStream::new(&channel(), Arc::new(NoBlobs), &config).unwrap()
+    }
+
+    // webhook payloads are parsed without calling the api
+    fn webhook_stream() -> Arc<WhatsAppStream> {
+        let config = WhatsAppConfig::default();
+
+        WhatsAppStream::new(&channel(), Arc::new(NoBlobs), &config).unwrap()
+    }
+
+    fn text_update(
+        content: &str,
+        reply_to: Option<&str>,
+    ) -> OutgoingChannelUpdateKind {
+        OutgoingChannelUpdateKind::Message {
+            platform_user_id: 491701234567,
+            kind: OutgoingMessageUpdateKind::New {
+                message_id: Key::new(Uuid::new_v4()),
+                content: Some(content.into()),
+                attachments: Vec::new(),
+                reply_to: reply_to.map(Into::into),
+                keyboard: None,
+                format: TextFormat::Plain,
+            },
+            timestamp: Utc::now(),
+            group_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_text_messages() {
+        let api = MockGraphApi::start(|_| {
+            MockResponse::json(
+                200,
+                json!({ "messages": [{ "id": "wamid.2" }] }),
+            )
+        })
+        .await;
+
+        let sent_id = stream_of(&api)
+            .send(text_update("hello", Some("wamid.1")))
+            .await
+            .unwrap();
+
+        assert_eq!(sent_id.as_deref(), Some("wamid.2"));
+
+        let requests = api.requests();
+        assert_eq!(requests.len(), 1);
+
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            request.path,
+            format!("/{API_VERSION}/{PHONE_NUMBER_ID}/messages")
+        );
+        assert_eq!(
+            request.authorization.as_deref(),
+            Some(format!("Bearer {ACCESS_TOKEN}").as_str())
+        );
+
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "messaging_product": "whatsapp",
+                "recipient_type": "individual",
+                "to": "491701234567",
+                "context": { "message_id": "wamid.1" },
+                "type": "text",
+                "text": { "body": "hello" },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn splits_long_texts() {
+        let api = MockGraphApi::start(|_| {
+            MockResponse::json(
+                200,
+                json!({ "messages": [{ "id": "wamid.2" }] }),
+            )
+        })
+        .await;
+
+        let content = "a".repeat(MAX_MESSAGE_LENGTH + 1);
+        stream_of(&api)
+            .send(text_update(&content, Some("wamid.1")))
+            .await
+            .unwrap();
+
+        let bodies = api
+            .requests()
+            .iter()
+            .map(|request| serde_json::from_slice(&request.body).unwrap())
+            .collect::<Vec<Value>>();
+
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0]["context"]["message_id"], "wamid.1");
+        assert_eq!(bodies[1].get("context"), None);
+    }
+
+    #[tokio::test]
+    async fn classifies_api_failures() {
+        // the outcome of each request is chosen by the text it sends
+        let api = MockGraphApi::start(|request| {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+
+            match body["text"]["body"].as_str() {
+                | Some("rejected") => {
+                    MockResponse::json(400, json!({ "error": {} }))
+                }
+                | Some("limited") => {
+                    MockResponse::json(429, json!({ "error": {} }))
+                        .with_header("retry-after", "7")
+                }
+                | _ => MockResponse::json(503, json!({ "error": {} })),
+            }
+        })
+        .await;
+        let stream = stream_of(&api);
+
+        let err = stream
+            .send(text_update("rejected", None))
+            .await
+            .unwrap_err();
+        assert!(!err.is_transient(), "{err}");
+
+        let err = stream
+            .send(text_update("unavailable", None))
+            .await
+            .unwrap_err();
+        assert!(err.is_transient(), "{err}");
+
+        let err = stream.send(text_update("limited", None)).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                AppError::Link(LinkError::RateLimited(delay))
+                    if delay == Duration::from_secs(7)
+            ),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_media_through_their_urls() {
+        let cdn = MockGraphApi::start(|_| {
+            MockResponse::bytes("image/jpeg", b"\xff\xd8\xff\xe0")
+        })
+        .await;
+
+        let download_url = format!("{}/download/media-1", cdn.url);
+        let api = MockGraphApi::start(move |_| {
+            MockResponse::json(
+                200,
+                json!({ "url": download_url, "mime_type": "image/jpeg" }),
+            )
+        })
+        .await;
+
+        let blob = stream_of(&api).fetch_media("media-1").await.unwrap();
+
+        assert_eq!(blob.content_type.as_deref(), Some("image/jpeg"));
+        assert_eq!(blob.data, b"\xff\xd8\xff\xe0");
+
+        let lookup = &api.requests()[0];
+        assert_eq!(lookup.method, "GET");
+        assert_eq!(lookup.path, format!("/{API_VERSION}/media-1"));
+
+        // downloads require the access token as well
+        let download = &cdn.requests()[0];
+        assert_eq!(download.path, "/download/media-1");
+        assert_eq!(
+            download.authorization.as_deref(),
+            Some(format!("Bearer {ACCESS_TOKEN}").as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_fetching_unknown_media() {
+        let api =
+            MockGraphApi::start(|_| MockResponse::json(404, json!({}))).await;
+
+        let err = stream_of(&api).fetch_media("missing").await.unwrap_err();
+
+        assert!(!err.is_transient(), "{err}");
+    }
+
+    fn webhook_payload(value: Value) -> Vec<u8> {
+        json!({
+            "object": WEBHOOK_OBJECT,
+            "entry": [{
+                "id": "102290129340398",
+                "changes": [{ "field": MESSAGES_FIELD, "value": value }],
+            }],
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn parses_webhook_messages() {
+        let payload = webhook_payload(json!({
+            "metadata": { "phone_number_id": PHONE_NUMBER_ID },
+            "contacts": [{
+                "wa_id": "491701234567",
+                "profile": { "name": "Jane" },
+            }],
+            "messages": [
+                {
+                    "id": "wamid.1",
+                    "from": "491701234567",
+                    "timestamp": "1700000000",
+                    "type": "text",
+                    "text": { "body": "hello" },
+                },
+                {
+                    "id": "wamid.2",
+                    "from": "491701234567",
+                    "timestamp": "1700000001",
+                    "context": { "id": "wamid.0" },
+                    "type": "image",
+                    "image": { "id": "media-1", "caption": "look" },
+                },
+                {
+                    "id": "wamid.3",
+                    "from": "491701234567",
+                    "timestamp": "1700000002",
+                    "type": "sticker",
+                    "sticker": { "id": "media-2" },
+                },
+            ],
+        }));
+
+        let updates = webhook_stream()
+            .parse_webhook_payload(&payload)
+            .await
+            .unwrap();
+
+        // the unsupported sticker is skipped
+        assert_eq!(updates.len(), 2);
+
+        let IncomingChannelUpdateKind::Message {
+            platform_user_id,
+            profile,
+            kind:
+                IncomingMessageUpdateKind::New {
+                    platform_message_id,
+                    content,
+                    reply_to,
+                    ..
+                },
+            ..
+        } = &updates[0]
+        else {
+            panic!("expected a new message, got {:?}", updates[0]);
+        };
+
+        assert_eq!(*platform_user_id, 491701234567);
+        assert_eq!(platform_message_id, "wamid.1");
+        assert_eq!(content.as_deref(), Some("hello"));
+        assert_eq!(reply_to, &None);
+        assert_eq!(profile.display_name.as_deref(), Some("Jane"));
+        assert_eq!(profile.phone_number.as_deref(), Some("+491701234567"));
+
+        let IncomingChannelUpdateKind::Message {
+            kind:
+                IncomingMessageUpdateKind::New {
+                    content,
+                    attachments,
+                    reply_to,
+                    ..
+                },
+            ..
+        } = &updates[1]
+        else {
+            panic!("expected a new message, got {:?}", updates[1]);
+        };
+
+        assert_eq!(content.as_deref(), Some("look"));
+        assert_eq!(reply_to.as_deref(), Some("wamid.0"));
+        assert_eq!(attachments.len(), 1);
+        assert!(matches!(attachments[0].kind, AttachmentKind::Image));
+        assert_eq!(attachments[0].uri, "media-1");
+    }
+
+    #[tokio::test]
+    async fn orders_webhook_statuses_by_time() {
+        let payload = webhook_payload(json!({
+            "metadata": { "phone_number_id": PHONE_NUMBER_ID },
+            "statuses": [
+                {
+                    "id": "wamid.1",
+                    "status": "read",
+                    "timestamp": "1700000002",
+                    "recipient_id": "491701234567",
+                },
+                {
+                    "id": "wamid.1",
+                    "status": "delivered",
+                    "timestamp": "1700000001",
+                    "recipient_id": "491701234567",
+                },
+            ],
+        }));
+
+        let updates = webhook_stream()
+            .parse_webhook_payload(&payload)
+            .await
+            .unwrap();
+
+        let statuses = updates
+            .iter()
+            .map(|update| match update {
+                | IncomingChannelUpdateKind::MessageStatus {
+                    status, ..
+                } => *status,
+                | update => panic!("expected a status, got {update:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(statuses, [MessageStatus::Delivered, MessageStatus::Seen]);
+    }
+
+    #[tokio::test]
+    async fn skips_webhook_updates_of_other_numbers() {
+        let payload = webhook_payload(json!({
+            "metadata": { "phone_number_id": "999" },
+            "messages": [{
+                "id": "wamid.1",
+                "from": "491701234567",
+                "timestamp": "1700000000",
+                "type": "text",
+                "text": { "body": "hello" },
+            }],
+        }));
+
+        let updates = webhook_stream()
+            .parse_webhook_payload(&payload)
+            .await
+            .unwrap();
+
+        assert!(updates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_webhooks_of_other_objects() {
+        let payload = json!({ "object": "page", "entry": [] }).to_string();
+
+        let err = webhook_stream()
+            .parse_webhook_payload(payload.as_bytes())
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(err, AppError::Link(LinkError::UnsupportedEvent(_))),
+            "{err}"
+        );
+    }
+}
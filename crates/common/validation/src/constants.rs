@@ -1,3 +1,5 @@
 pub(crate) const SUPPORTED_DATA_DRIVERS: [&str; 1] = ["postgres"];
 pub(crate) const SUPPORTED_DOC_STORE_DRIVERS: [&str; 1] = ["mongodb"];
-pub(crate) const SUPPORTED_MESSAGE_QUEUE_PROTOCOLS: [&str; 1] = ["amqp"];
+pub(crate) const SUPPORTED_MESSAGE_QUEUE_PROTOCOLS: [&str; 2] =
+    ["amqp", "memory"];
+pub(crate) const SUPPORTED_STORAGE_PROVIDERS: [&str; 2] = ["local", "s3"];
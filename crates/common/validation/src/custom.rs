@@ -5,6 +5,7 @@ use crate::{
     constants::{
         SUPPORTED_DATA_DRIVERS, 
         SUPPORTED_MESSAGE_QUEUE_PROTOCOLS, SUPPORTED_DOC_STORE_DRIVERS,
+        SUPPORTED_STORAGE_PROVIDERS,
     },
     helpers::{validate, validate_with},
     parse::*,
@@ -29,6 +30,10 @@ pub fn username(value: &str) -> Result<(), ValidationError> {
     validate::<Username>("username", value)
 }
 
+pub fn webhook_secret(value: &str) -> Result<(), ValidationError> {
+    validate::<WebhookSecret>("webhook_secret", value)
+}
+
 pub fn phone_number(value: &str) -> Result<(), ValidationError> {
     validate::<PhoneNumber>("phone_number", value)
 }
@@ -53,6 +58,12 @@ pub fn supported_message_queue_protocol(
     })
 }
 
+pub fn supported_storage_provider(value: &str) -> Result<(), ValidationError> {
+    validate_with("supported_storage_provider", value, |v| {
+        SUPPORTED_STORAGE_PROVIDERS.contains(&v)
+    })
+}
+
 pub fn in_future(value: &DateTime<Utc>) -> Result<(), ValidationError> {
     validate_with("in_future", value, |ts| ts < &Utc::now())
 }
@@ -37,6 +37,10 @@ pub struct Identifier(pub String);
 #[validator(regex(RE_USERNAME))]
 pub struct Username(pub String);
 
+#[derive(Validator)]
+#[validator(regex(RE_WEBHOOK_SECRET))]
+pub struct WebhookSecret(pub String);
+
 #[derive(Validator)]
 #[validator(phone)]
 pub struct PhoneNumber(pub validators::phonenumber::PhoneNumber);
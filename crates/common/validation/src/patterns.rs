@@ -6,4 +6,6 @@ lazy_static! {
         Regex::new(r#"^[_a-zA-Z][_a-zA-Z0-9]{0,30}$"#).unwrap();
     pub static ref RE_USERNAME: Regex =
         Regex::new(r#"^[_a-zA-Z][_a-zA-Z0-9]{2,30}$"#).unwrap();
+    pub static ref RE_WEBHOOK_SECRET: Regex =
+        Regex::new(r#"^[_a-zA-Z0-9-]{8,256}$"#).unwrap();
 }
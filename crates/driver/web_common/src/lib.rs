@@ -3,4 +3,5 @@ extern crate tracing;
 
 pub mod auth;
 pub mod state;
+pub mod storage;
 pub mod value_types;
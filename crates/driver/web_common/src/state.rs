@@ -8,14 +8,28 @@ use adapter_repositories_mongodb::{
 use adapter_repositories_postgres::*;
 use adapter_services::{
     config::TomlConfigService,
-    crypto::hash::Argon2CryptoHashService,
+    crypto::{hash::Argon2CryptoHashService, secrets::AesGcmSecretsService},
     entropy::SecureEntropyService,
-    link::message_passing::RabbitMqMessagePassingService,
+    link::message_passing::MessagePassingServiceImpl,
+    storage::create_blob_storage,
 };
+use anyhow::Context;
 use app_services::{
     auth::AppAuthService,
-    comm::{bots::AppBotsService, chats::AppChatsService},
-    link::channels::AppChannelsService,
+    comm::{
+        bots::{
+            config::{BotsConfig, BOTS_CONFIG_SECTION},
+            AppBotsService,
+        },
+        chats::{
+            config::{ChatsConfig, CHATS_CONFIG_SECTION},
+            AppChatsService,
+        },
+    },
+    link::channels::{
+        config::{ChannelsConfig, CHANNELS_CONFIG_SECTION},
+        AppChannelsService,
+    },
     setup::AppSetupService,
 };
 use kernel_repositories::{DataStore, DocumentStore};
@@ -27,18 +41,21 @@ use kernel_services::{
     entropy::EntropyService,
     link::{channels::ChannelsService, message_passing::MessagePassingService},
     setup::SetupService,
+    storage::blob::BlobStorageService,
     Service,
 };
+use serde::de::DeserializeOwned;
+use validator::Validate;
 
 pub type AppState = Arc<
     AppStateImpl<
         TomlConfigService,
         SecureEntropyService,
         Argon2CryptoHashService<'static>,
-        RabbitMqMessagePassingService,
+        MessagePassingServiceImpl,
         AppAuthService<TomlConfigService>,
         AppSetupService,
-        AppChannelsService<RabbitMqMessagePassingService>,
+        AppChannelsService<MessagePassingServiceImpl>,
         AppChatsService,
         AppBotsService,
     >,
@@ -57,6 +74,7 @@ pub struct AppStateImpl<
 > {
     pub data: Arc<dyn DataStore>,
     pub docs: Arc<dyn DocumentStore>,
+    pub blobs: Arc<dyn BlobStorageService>,
     pub config: Arc<Config>,
     pub entropy: Arc<Entropy>,
     pub hash: Arc<CryptoHash>,
@@ -75,21 +93,26 @@ pub async fn get_config_service() -> anyhow::Result<Arc<TomlConfigService>> {
 pub async fn create_state<'a>(
     config: Arc<TomlConfigService>,
 ) -> anyhow::Result<AppState> {
+    debug!("creating secrets service");
+    let secrets = init(AesGcmSecretsService::create(config.clone())?).await?;
+
     debug!("openning datastore");
     let conf = config.get_section::<DataConfig>(DATA_CONFIG_SECTION)?;
-    let data = create_datastore(conf).await?;
+    let data = create_datastore(conf, secrets).await?;
 
     debug!("openning document store");
     let conf =
         config.get_section::<DocumentStoreConfig>(DOC_STORE_CONFIG_SECTION)?;
     let docs = create_doc_store(conf).await?;
 
+    debug!("openning blob storage");
+    let blobs = create_blob_storage(config.clone()).await?;
+
     debug!("creating base services");
     let entropy = init(SecureEntropyService::default()).await?;
     let hash = init(Argon2CryptoHashService::default()).await?;
     let ipc =
-        init(RabbitMqMessagePassingService::create(config.clone()).await?)
-            .await?;
+        init(MessagePassingServiceImpl::create(config.clone()).await?).await?;
 
     debug!("creating app services");
     let auth = Arc::new(AppAuthService::new(
@@ -99,19 +122,38 @@ pub async fn create_state<'a>(
         entropy.clone(),
     ));
     let setup = init(AppSetupService::new(data.clone(), auth.clone())).await?;
-    let channels =
-        init(AppChannelsService::new(data.clone(), ipc.clone())).await?;
+    let conf =
+        get_valid_section::<ChannelsConfig>(&config, CHANNELS_CONFIG_SECTION)?;
+    let channels = init(AppChannelsService::new(
+        data.clone(),
+        ipc.clone(),
+        blobs.clone(),
+        conf,
+    ))
+    .await?;
+    let conf = get_valid_section::<ChatsConfig>(&config, CHATS_CONFIG_SECTION)?;
     let chats = init(
-        AppChatsService::create(data.clone(), docs.clone(), channels.clone())
+        AppChatsService::create(
+            data.clone(),
+            docs.clone(),
+            channels.clone(),
+            conf,
+        )
+        .await?,
+    )
+    .await?;
+    let conf = get_valid_section::<BotsConfig>(&config, BOTS_CONFIG_SECTION)?;
+    let bots = init(
+        AppBotsService::create(data.clone(), chats.clone(), &*ipc, conf)
             .await?,
     )
     .await?;
-    let bots = init(AppBotsService::new(data.clone(), chats.clone())).await?;
 
     debug!("building application state");
     Ok(Arc::new(AppStateImpl {
         data,
         docs,
+        blobs,
         config,
         entropy,
         hash,
@@ -124,6 +166,21 @@ pub async fn create_state<'a>(
     }))
 }
 
+/// Reads a section of the configuration, or its defaults when missing,
+/// rejecting invalid values at startup rather than once they are used.
+fn get_valid_section<T: DeserializeOwned + Validate + Default>(
+    config: &TomlConfigService,
+    section: &str,
+) -> anyhow::Result<T> {
+    let conf = config.get_section_or_default::<T>(section)?;
+
+    conf.validate().with_context(|| {
+        format!("invalid configuration section `{section}`")
+    })?;
+
+    Ok(conf)
+}
+
 async fn init<S: Service + Send + Sync>(svc: S) -> anyhow::Result<Arc<S>> {
     let svc = Arc::new(svc);
 
@@ -0,0 +1,17 @@
+use common_macros::into_fn;
+use serde::Deserialize;
+use validator::Validate;
+
+pub const BLOB_TOKEN_CONFIG_SECTION: &str = "storage.download";
+
+into_fn!(default_timeout_seconds: const i64 => 5 * 60);
+
+#[derive(Clone, Debug, Deserialize, Validate)]
+pub struct BlobTokenConfig {
+    #[validate(range(min = 1))]
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: i64,
+
+    #[validate(length(min = 32))]
+    pub signing_key: String,
+}
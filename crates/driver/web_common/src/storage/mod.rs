@@ -0,0 +1,4 @@
+pub mod config;
+pub mod token;
+
+pub const BLOB_DOWNLOAD_PATH: &str = "/api/blobs";
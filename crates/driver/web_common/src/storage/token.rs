@@ -0,0 +1,52 @@
+use chrono::Utc;
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use super::{config::BlobTokenConfig, BLOB_DOWNLOAD_PATH};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlobToken {
+    pub sub: String,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+impl BlobToken {
+    pub fn new(key: &str, config: &BlobTokenConfig) -> Self {
+        let iat = Utc::now().timestamp();
+
+        Self {
+            sub: key.to_owned(),
+            exp: iat + config.timeout_seconds,
+            iat,
+        }
+    }
+
+    pub fn encode(&self, config: &BlobTokenConfig) -> anyhow::Result<String> {
+        let jwt = jsonwebtoken::encode(
+            &Header::default(),
+            &self,
+            &EncodingKey::from_secret(config.signing_key.as_bytes()),
+        )?;
+
+        Ok(jwt)
+    }
+
+    pub fn decode(jwt: &str, config: &BlobTokenConfig) -> anyhow::Result<Self> {
+        let token = jsonwebtoken::decode::<Self>(
+            jwt,
+            &DecodingKey::from_secret(config.signing_key.as_bytes()),
+            &Validation::default(),
+        )
+        .map(|data| data.claims)?;
+
+        Ok(token)
+    }
+
+    pub fn download_path(
+        &self,
+        config: &BlobTokenConfig,
+    ) -> anyhow::Result<String> {
+        Ok(format!("{BLOB_DOWNLOAD_PATH}/{}", self.encode(config)?))
+    }
+}
@@ -24,10 +24,14 @@ fn main() -> anyhow::Result<()> {
                 "proto/value_types/pagination.proto",
                 // models
                 "proto/models/user.proto",
+                "proto/models/bot.proto",
+                "proto/models/channel.proto",
                 "proto/models/chat.proto",
                 "proto/models/instance.proto",
                 "proto/models/message.proto",
                 // services
+                "proto/services/bots.proto",
+                "proto/services/channels.proto",
                 "proto/services/chats.proto",
                 "proto/services/stats.proto",
             ],
@@ -2,8 +2,18 @@ use driver_web_common::state::AppState;
 use tonic::transport::{server::Router, Server};
 
 use crate::{
-    proto::services::{chats_server::ChatsServer, stats_server::StatsServer},
-    services::{GrpcChatsService, GrpcStatsService},
+    proto::services::{
+        bots_server::BotsServer,
+        channels_server::ChannelsServer,
+        chats_server::ChatsServer,
+        stats_server::StatsServer,
+    },
+    services::{
+        GrpcBotsService,
+        GrpcChannelsService,
+        GrpcChatsService,
+        GrpcStatsService,
+    },
 };
 
 pub fn add_grpc_services<const ENABLE_WEB: bool, T>(
@@ -16,6 +26,12 @@ where
     if ENABLE_WEB {
         server
             .accept_http1(true)
+            .add_service(tonic_web::enable(BotsServer::new(
+                GrpcBotsService::new(state.clone()),
+            )))
+            .add_service(tonic_web::enable(ChannelsServer::new(
+                GrpcChannelsService::new(state.clone()),
+            )))
             .add_service(tonic_web::enable(ChatsServer::new(
                 GrpcChatsService::new(state.clone()),
             )))
@@ -24,6 +40,10 @@ where
             )))
     } else {
         server
+            .add_service(BotsServer::new(GrpcBotsService::new(state.clone())))
+            .add_service(ChannelsServer::new(GrpcChannelsService::new(
+                state.clone(),
+            )))
             .add_service(ChatsServer::new(GrpcChatsService::new(state.clone())))
             .add_service(StatsServer::new(GrpcStatsService::new(state)))
     }
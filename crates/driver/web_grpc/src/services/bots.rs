@@ -0,0 +1,234 @@
+use derive_more::Constructor;
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{
+        auth::{Action, KnownRoles, Resource, User},
+        comm::Bot,
+    },
+    traits::Key,
+};
+use kernel_services::comm::bots::{BotStatus, BotsService};
+use tonic::{Request, Response};
+
+use crate::{
+    proto::{
+        models,
+        services::{
+            bots_server::Bots,
+            GetBotStatusOfResponse,
+            GetBotStatusResponse,
+        },
+        ProtoResult,
+    },
+    util::{
+        auth::token::{GrpcAuthToken, RequestExt},
+        convert::TryConvertInto,
+        error::IntoStatusResult,
+    },
+};
+
+#[derive(Constructor)]
+pub(crate) struct GrpcBotsService {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl Bots for GrpcBotsService {
+    async fn start(
+        &self,
+        req: Request<models::bot::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let bot = self
+            .get_bot_by_id(&auth, Action::Modify, req.into_inner())
+            .await?;
+
+        self.state
+            .bots
+            .start_bot(&bot.user_id, &bot.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn stop(
+        &self,
+        req: Request<models::bot::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let bot = self
+            .get_bot_by_id(&auth, Action::Modify, req.into_inner())
+            .await?;
+
+        self.state
+            .bots
+            .stop_bot(&bot.user_id, &bot.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn reload(
+        &self,
+        req: Request<models::bot::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let bot = self
+            .get_bot_by_id(&auth, Action::Modify, req.into_inner())
+            .await?;
+
+        self.state
+            .bots
+            .reload_bot(&bot.user_id, &bot.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn get_status(
+        &self,
+        req: Request<models::bot::Id>,
+    ) -> ProtoResult<Response<GetBotStatusResponse>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let bot = self
+            .get_bot_by_id(&auth, Action::View, req.into_inner())
+            .await?;
+
+        let status =
+            self.state.bots.status(&bot.id).await.into_status_result()?;
+
+        Ok(Response::new((bot.id, status).into()))
+    }
+
+    async fn start_all_of(
+        &self,
+        req: Request<models::user::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let user_id = req.into_inner().value.try_convert()?;
+
+        authorize_of(&auth, Action::Modify, &user_id)?;
+
+        self.state
+            .bots
+            .start_bots_of(&user_id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn stop_all_of(
+        &self,
+        req: Request<models::user::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let user_id = req.into_inner().value.try_convert()?;
+
+        authorize_of(&auth, Action::Modify, &user_id)?;
+
+        self.state
+            .bots
+            .stop_bots_of(&user_id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn reload_all_of(
+        &self,
+        req: Request<models::user::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let user_id = req.into_inner().value.try_convert()?;
+
+        authorize_of(&auth, Action::Modify, &user_id)?;
+
+        self.state
+            .bots
+            .reload_bots_of(&user_id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn get_status_of(
+        &self,
+        req: Request<models::user::Id>,
+    ) -> ProtoResult<Response<GetBotStatusOfResponse>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let user_id = req.into_inner().value.try_convert()?;
+
+        authorize_of(&auth, Action::View, &user_id)?;
+
+        let statuses = self
+            .state
+            .bots
+            .status_of(&user_id)
+            .await
+            .into_status_result()?
+            .into_iter()
+            .map(|(bot_id, status)| (bot_id, Some(status)).into())
+            .collect();
+
+        Ok(Response::new(GetBotStatusOfResponse { statuses }))
+    }
+}
+
+impl GrpcBotsService {
+    async fn get_bot_by_id(
+        &self,
+        auth: &GrpcAuthToken,
+        action: Action,
+        bot_id: models::bot::Id,
+    ) -> ProtoResult<Bot> {
+        let bot = self
+            .state
+            .data
+            .comm()
+            .bots()
+            .get(&bot_id.try_convert()?)
+            .await
+            .into_status_result()?;
+
+        authorize_of(auth, action, &bot.user_id)?;
+
+        Ok(bot)
+    }
+}
+
+fn authorize_of(
+    auth: &GrpcAuthToken,
+    action: Action,
+    user_id: &Key<User>,
+) -> ProtoResult<()> {
+    auth.can(&[(Resource::Bot, action)])?
+        .of(user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    Ok(())
+}
+
+impl From<(Key<Bot>, Option<BotStatus>)> for GetBotStatusResponse {
+    fn from((bot_id, status): (Key<Bot>, Option<BotStatus>)) -> Self {
+        let Some(status) = status else {
+            return Self {
+                bot_id: Some(bot_id.into()),
+                ..Default::default()
+            };
+        };
+
+        Self {
+            bot_id: Some(bot_id.into()),
+            is_loaded: true,
+            is_running: status.is_running,
+            loaded_at: Some(status.loaded_at.into()),
+            entry_menu_id: Some(status.entry_menu_id.to_string()),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use derive_more::Constructor;
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::entities::{
+    auth::{Action, Resource},
+    link::Channel,
+};
+use kernel_services::link::channels::{ChannelStatus, ChannelsService};
+use tonic::{Request, Response, Status};
+
+use crate::{
+    proto::{
+        models,
+        services::{channels_server::Channels, GetChannelStatusResponse},
+        ProtoResult,
+    },
+    util::{
+        auth::token::{GrpcAuthToken, RequestExt},
+        convert::TryConvertInto,
+        error::IntoStatusResult,
+    },
+};
+
+#[derive(Constructor)]
+pub(crate) struct GrpcChannelsService {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl Channels for GrpcChannelsService {
+    async fn start(
+        &self,
+        req: Request<models::channel::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let channel = self
+            .get_channel_by_id(&auth, Action::Modify, req.into_inner())
+            .await?;
+
+        if !channel.is_active {
+            return Err(Status::failed_precondition("channel is not active"));
+        }
+
+        self.state
+            .channels
+            .start_channel(&channel.user_id, &channel.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn stop(
+        &self,
+        req: Request<models::channel::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let channel = self
+            .get_channel_by_id(&auth, Action::Modify, req.into_inner())
+            .await?;
+
+        self.state
+            .channels
+            .stop_channel(&channel.user_id, &channel.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn restart(
+        &self,
+        req: Request<models::channel::Id>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let channel = self
+            .get_channel_by_id(&auth, Action::Modify, req.into_inner())
+            .await?;
+
+        if !channel.is_active {
+            return Err(Status::failed_precondition("channel is not active"));
+        }
+
+        self.state
+            .channels
+            .restart_channel(&channel.user_id, &channel.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn get_status(
+        &self,
+        req: Request<models::channel::Id>,
+    ) -> ProtoResult<Response<GetChannelStatusResponse>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let channel = self
+            .get_channel_by_id(&auth, Action::View, req.into_inner())
+            .await?;
+
+        let status = self
+            .state
+            .channels
+            .status(&channel.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(status.into()))
+    }
+}
+
+impl GrpcChannelsService {
+    async fn get_channel_by_id(
+        &self,
+        auth: &GrpcAuthToken,
+        action: Action,
+        channel_id: models::channel::Id,
+    ) -> ProtoResult<Channel> {
+        auth.can(&[(Resource::Channel, action)])?;
+
+        let channel = self
+            .state
+            .data
+            .link()
+            .channels()
+            .get(&channel_id.try_convert()?)
+            .await
+            .into_status_result()?;
+
+        auth.of(&channel.user_id)?;
+
+        Ok(channel)
+    }
+}
+
+impl From<Option<ChannelStatus>> for GetChannelStatusResponse {
+    fn from(value: Option<ChannelStatus>) -> Self {
+        let Some(status) = value else {
+            return Self::default();
+        };
+
+        Self {
+            is_running: true,
+            node_id: Some(status.node_id),
+            started_at: Some(status.started_at.into()),
+            is_degraded: status.is_degraded,
+            restarts: status.restarts,
+            last_error: status.last_error,
+            last_activity: status.last_activity.map(Into::into),
+        }
+    }
+}
@@ -1,15 +1,35 @@
+use chrono::{TimeZone, Utc};
 use derive_more::Constructor;
-use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use driver_web_common::{
+    auth::validator::AuthValidator,
+    state::AppState,
+    storage::{
+        config::{BlobTokenConfig, BLOB_TOKEN_CONFIG_SECTION},
+        token::BlobToken,
+    },
+};
 use futures::{StreamExt, TryStreamExt};
 use kernel_entities::entities::{
     auth::{Action, KnownRoles, Resource},
-    comm::{Chat, ChatState, Message, MessageDirection},
+    comm::{
+        Attachment,
+        AttachmentKind,
+        Chat,
+        ChatHandler,
+        ChatState,
+        Message,
+        MessageDirection,
+        MessageStatus,
+    },
 };
 use kernel_services::{
     self,
-    comm::chats::{ChatEventKind, ChatsService},
+    comm::chats::{ChatEventKind, ChatHandoff, ChatsService},
+    config::ConfigService,
+    link::rich_text::TextFormat,
+    storage::blob::{blob_key_of, channel_of_blob_key},
 };
-use tonic::{codegen::BoxStream, Request, Response};
+use tonic::{codegen::BoxStream, Request, Response, Status};
 
 use crate::{
     proto::{
@@ -17,7 +37,11 @@ use crate::{
         services::{
             self,
             chats_server::Chats,
+            HandlerChangedEvent,
             MessageAddedEvent,
+            MessageDeletedEvent,
+            MessageEditedEvent,
+            MessageStatusChangedEvent,
             WatchResponse,
         },
         ProtoResult,
@@ -144,7 +168,11 @@ impl Chats for GrpcChatsService {
                     | ChatEventKind::MessageAdded {
                         id,
                         text,
+                        attachments,
+                        reply_to,
+                        callback_data: _,
                         instance_id,
+                        channel_id: _,
                         direction,
                         created_at,
                     } => {
@@ -159,7 +187,85 @@ impl Chats for GrpcChatsService {
                                 direction: direction.into(),
                                 text: text.unwrap_or_default(),
                                 created_at: Some(created_at.into()),
+                                attachments: attachments
+                                    .into_iter()
+                                    .map(Into::into)
+                                    .collect(),
+                                reply_to: reply_to.map(Into::into),
+                            }),
+                            ..Default::default()
+                        });
+                    }
+
+                    | ChatEventKind::MessageEdited {
+                        id,
+                        text,
+                        instance_id,
+                        updated_at,
+                    } => {
+                        yield Ok(WatchResponse {
+                            message_edited: Some(MessageEditedEvent {
+                                id: Some(id.into()),
+                                chat_id: Some(event.chat_id.into()),
+                                instance_id: Some(instance_id.into()),
+                                text: text.unwrap_or_default(),
+                                updated_at: Some(updated_at.into()),
+                            }),
+                            ..Default::default()
+                        });
+                    }
+
+                    | ChatEventKind::MessageDeleted {
+                        id,
+                        instance_id,
+                        deleted_at,
+                    } => {
+                        yield Ok(WatchResponse {
+                            message_deleted: Some(MessageDeletedEvent {
+                                id: Some(id.into()),
+                                chat_id: Some(event.chat_id.into()),
+                                instance_id: Some(instance_id.into()),
+                                deleted_at: Some(deleted_at.into()),
                             }),
+                            ..Default::default()
+                        });
+                    }
+
+                    | ChatEventKind::MessageStatusChanged {
+                        id,
+                        instance_id,
+                        status,
+                        updated_at,
+                    } => {
+                        let status: models::message::Status = status.into();
+
+                        yield Ok(WatchResponse {
+                            message_status_changed: Some(
+                                MessageStatusChangedEvent {
+                                    id: Some(id.into()),
+                                    chat_id: Some(event.chat_id.into()),
+                                    instance_id: Some(instance_id.into()),
+                                    status: status.into(),
+                                    updated_at: Some(updated_at.into()),
+                                },
+                            ),
+                            ..Default::default()
+                        });
+                    }
+
+                    | ChatEventKind::HandlerChanged {
+                        handler,
+                        updated_at,
+                    } => {
+                        let handler: models::chat::Handler = handler.into();
+
+                        yield Ok(WatchResponse {
+                            handler_changed: Some(HandlerChangedEvent {
+                                chat_id: Some(event.chat_id.into()),
+                                handler: handler.into(),
+                                updated_at: Some(updated_at.into()),
+                            }),
+                            ..Default::default()
                         });
                     }
                 }
@@ -177,20 +283,184 @@ impl Chats for GrpcChatsService {
         req: Request<services::SendMessageRequest>,
     ) -> ProtoResult<Response<()>> {
         let auth = req.auth(self.state.config.clone())?;
-        let services::SendMessageRequest { chat_id, text } = req.into_inner();
+        let services::SendMessageRequest {
+            chat_id,
+            text,
+            attachments,
+            reply_to,
+            format,
+        } = req.into_inner();
 
         auth.can(&[(Resource::Message, Action::Add)])?;
 
+        let chat = self.get_chat_by_id(&auth, chat_id).await?;
+        let attachments = attachments
+            .into_iter()
+            .map(TryConvertInto::try_convert)
+            .collect::<Result<Vec<Attachment>, _>>()?;
+        let reply_to = reply_to.map(TryConvertInto::try_convert).transpose()?;
+
+        self.state
+            .chats
+            .send_message(
+                &chat.id,
+                text,
+                text_format_of(format)?,
+                attachments,
+                reply_to,
+                None,
+            )
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn edit(
+        &self,
+        req: Request<services::EditMessageRequest>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let services::EditMessageRequest {
+            chat_id,
+            message_id,
+            text,
+            format,
+        } = req.into_inner();
+
+        auth.can(&[(Resource::Message, Action::Modify)])?;
+
+        let chat = self.get_chat_by_id(&auth, chat_id).await?;
+
+        self.state
+            .chats
+            .edit_message(
+                &chat.id,
+                &message_id.try_convert()?,
+                text,
+                text_format_of(format)?,
+            )
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn delete(
+        &self,
+        req: Request<services::DeleteMessageRequest>,
+    ) -> ProtoResult<Response<()>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let services::DeleteMessageRequest {
+            chat_id,
+            message_id,
+        } = req.into_inner();
+
+        auth.can(&[(Resource::Message, Action::Remove)])?;
+
         let chat = self.get_chat_by_id(&auth, chat_id).await?;
 
         self.state
             .chats
-            .send_message(&chat.id, text)
+            .delete_message(&chat.id, &message_id.try_convert()?)
             .await
             .into_status_result()?;
 
         Ok(Response::new(()))
     }
+
+    async fn get_attachment_url(
+        &self,
+        req: Request<services::GetAttachmentUrlRequest>,
+    ) -> ProtoResult<Response<services::GetAttachmentUrlResponse>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let services::GetAttachmentUrlRequest {
+            chat_id,
+            message_id,
+            index,
+        } = req.into_inner();
+
+        auth.can(&[(Resource::Message, Action::View)])?;
+
+        let chat = self.get_chat_by_id(&auth, chat_id).await?;
+        let message = self
+            .state
+            .docs
+            .messages()
+            .get_of(&chat.id, &message_id.try_convert()?)
+            .await
+            .into_status_result()?;
+
+        let Some(attachment) = message.attachments.get(index as usize) else {
+            return Err(Status::not_found("attachment not found"));
+        };
+
+        let Some(key) = blob_key_of(&attachment.uri) else {
+            return Err(Status::failed_precondition(
+                "attachment content is not stored",
+            ));
+        };
+
+        let config = self
+            .state
+            .config
+            .get_section::<BlobTokenConfig>(BLOB_TOKEN_CONFIG_SECTION)
+            .into_status_result()?;
+
+        let token = BlobToken::new(key, &config);
+        let url = token.download_path(&config).map_err(|err| {
+            error!("could not encode blob token: {err:?}");
+            Status::internal("could not create download url")
+        })?;
+
+        Ok(Response::new(services::GetAttachmentUrlResponse {
+            url,
+            expires_at: Utc
+                .timestamp_opt(token.exp, 0)
+                .single()
+                .map(Into::into),
+        }))
+    }
+
+    async fn get_handoff(
+        &self,
+        req: Request<models::chat::Id>,
+    ) -> ProtoResult<Response<services::ChatHandoff>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let chat = self.get_chat_by_id(&auth, Some(req.into_inner())).await?;
+
+        let handoff = self
+            .state
+            .chats
+            .get_handoff(&chat.id)
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new((chat, handoff).into()))
+    }
+
+    async fn set_handoff(
+        &self,
+        req: Request<services::SetHandoffRequest>,
+    ) -> ProtoResult<Response<services::ChatHandoff>> {
+        let auth = req.auth(self.state.config.clone())?;
+        let services::SetHandoffRequest { chat_id, handler } = req.into_inner();
+
+        auth.can(&[(Resource::Chat, Action::Modify)])?;
+
+        let chat = self.get_chat_by_id(&auth, chat_id).await?;
+        let handler = models::chat::Handler::from_i32(handler)
+            .ok_or_else(|| Status::invalid_argument("invalid chat handler"))?;
+
+        let handoff = self
+            .state
+            .chats
+            .set_handler(&chat.id, handler.into())
+            .await
+            .into_status_result()?;
+
+        Ok(Response::new((chat, handoff).into()))
+    }
 }
 
 impl GrpcChatsService {
@@ -225,6 +495,18 @@ impl From<MessageDirection> for models::message::Direction {
     }
 }
 
+impl From<MessageStatus> for models::message::Status {
+    fn from(value: MessageStatus) -> Self {
+        match value {
+            | MessageStatus::Pending => Self::Pending,
+            | MessageStatus::Sent => Self::Sent,
+            | MessageStatus::Delivered => Self::Delivered,
+            | MessageStatus::Seen => Self::Seen,
+            | MessageStatus::Failed => Self::Failed,
+        }
+    }
+}
+
 impl From<ChatState> for models::chat::State {
     fn from(value: ChatState) -> Self {
         match value {
@@ -235,9 +517,40 @@ impl From<ChatState> for models::chat::State {
     }
 }
 
+impl From<ChatHandler> for models::chat::Handler {
+    fn from(value: ChatHandler) -> Self {
+        match value {
+            | ChatHandler::Bot => Self::Bot,
+            | ChatHandler::Human => Self::Human,
+        }
+    }
+}
+
+impl From<models::chat::Handler> for ChatHandler {
+    fn from(value: models::chat::Handler) -> Self {
+        match value {
+            | models::chat::Handler::Bot => Self::Bot,
+            | models::chat::Handler::Human => Self::Human,
+        }
+    }
+}
+
+impl From<(Chat, ChatHandoff)> for services::ChatHandoff {
+    fn from((chat, handoff): (Chat, ChatHandoff)) -> Self {
+        let handler: models::chat::Handler = handoff.handler.into();
+
+        Self {
+            chat_id: Some(chat.id.into()),
+            handler: handler.into(),
+            resumes_at: handoff.resumes_at.map(Into::into),
+        }
+    }
+}
+
 impl From<Message> for models::Message {
     fn from(value: Message) -> Self {
         let direction: models::message::Direction = value.direction.into();
+        let status: models::message::Status = value.status.into();
 
         Self {
             id: Some(value.id.into()),
@@ -246,26 +559,115 @@ impl From<Message> for models::Message {
             user_id: Some(value.user_id.into()),
             chat_id: Some(value.chat_id.into()),
             instance_id: Some(value.instance_id.into()),
-            delivered_at: Some(value.delivered_at.into()),
+            delivered_at: value.delivered_at.map(Into::into),
             seen_at: value.seen_at.map(Into::into),
             deleted_at_at: value.deleted_at.map(Into::into),
             created_at: Some(value.created_at.into()),
             updated_at: Some(value.updated_at.into()),
+            attachments: value
+                .attachments
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            changes: value.changes,
+            platform_message_id: value.platform_message_id,
+            status: status.into(),
+            reply_to: value.reply_to.map(Into::into),
+        }
+    }
+}
+
+impl From<AttachmentKind> for models::message::attachment::Kind {
+    fn from(value: AttachmentKind) -> Self {
+        match value {
+            | AttachmentKind::Document => Self::Document,
+            | AttachmentKind::Audio => Self::Audio,
+            | AttachmentKind::Video => Self::Video,
+            | AttachmentKind::Image => Self::Image,
+            | AttachmentKind::Voice => Self::Voice,
+        }
+    }
+}
+
+impl From<models::message::attachment::Kind> for AttachmentKind {
+    fn from(value: models::message::attachment::Kind) -> Self {
+        match value {
+            | models::message::attachment::Kind::Document => Self::Document,
+            | models::message::attachment::Kind::Audio => Self::Audio,
+            | models::message::attachment::Kind::Video => Self::Video,
+            | models::message::attachment::Kind::Image => Self::Image,
+            | models::message::attachment::Kind::Voice => Self::Voice,
         }
     }
 }
 
+impl From<models::message::TextFormat> for TextFormat {
+    fn from(value: models::message::TextFormat) -> Self {
+        match value {
+            | models::message::TextFormat::Plain => Self::Plain,
+            | models::message::TextFormat::Markdown => Self::Markdown,
+        }
+    }
+}
+
+impl From<Attachment> for models::message::Attachment {
+    fn from(value: Attachment) -> Self {
+        let kind: models::message::attachment::Kind = value.kind.into();
+
+        Self {
+            kind: kind.into(),
+            label: value.label,
+            uri: value.uri,
+        }
+    }
+}
+
+impl TryConvertInto<Attachment> for models::message::Attachment {
+    fn try_convert(self) -> Result<Attachment, Status> {
+        let Some(kind) = models::message::attachment::Kind::from_i32(self.kind)
+        else {
+            return Err(Status::invalid_argument("invalid attachment kind"));
+        };
+
+        if self.uri.is_empty() {
+            return Err(Status::invalid_argument("empty attachment uri"));
+        }
+
+        // only blobs stored for channels can be referenced, which the chats
+        // service further limits to those of the chat owner
+        if blob_key_of(&self.uri)
+            .map_or(false, |key| channel_of_blob_key(key).is_none())
+        {
+            return Err(Status::invalid_argument("invalid attachment uri"));
+        }
+
+        Ok(Attachment {
+            kind: kind.into(),
+            label: self.label,
+            uri: self.uri,
+        })
+    }
+}
+
 impl From<Chat> for models::Chat {
     fn from(value: Chat) -> Self {
         let state: models::chat::State = value.state.into();
+        let handler: models::chat::Handler = value.handler.into();
 
         Self {
             id: Some(value.id.into()),
             label: value.label,
             state: state.into(),
+            handler: handler.into(),
             user_id: Some(value.user_id.into()),
             created_at: Some(value.created_at.into()),
             updated_at: Some(value.updated_at.into()),
         }
     }
 }
+
+fn text_format_of(value: i32) -> Result<TextFormat, Status> {
+    models::message::TextFormat::from_i32(value)
+        .map(Into::into)
+        .ok_or_else(|| Status::invalid_argument("invalid text format"))
+}
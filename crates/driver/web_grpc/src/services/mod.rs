@@ -1,5 +1,9 @@
+mod bots;
+mod channels;
 mod chats;
 mod stats;
 
+pub(super) use bots::GrpcBotsService;
+pub(super) use channels::GrpcChannelsService;
 pub(super) use chats::GrpcChatsService;
 pub(super) use stats::GrpcStatsService;
@@ -4,7 +4,8 @@ use driver_web_common::value_types::Pagination;
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Chat, Message}, link::Instance,
+        comm::{Bot, Chat, Message},
+        link::{Channel, Instance},
     },
     traits::Key,
 };
@@ -31,6 +32,8 @@ macro_rules! impl_into_proto_id {
 }
 
 impl_into_proto_id!(User => crate::proto::models::user::Id);
+impl_into_proto_id!(Channel => crate::proto::models::channel::Id);
+impl_into_proto_id!(Bot => crate::proto::models::bot::Id);
 impl_into_proto_id!(Chat => crate::proto::models::chat::Id);
 impl_into_proto_id!(Instance => crate::proto::models::instance::Id);
 impl_into_proto_id!(Message => crate::proto::models::message::Id);
@@ -2,8 +2,11 @@ use std::fmt::Debug;
 
 use kernel_repositories::error::RepoError;
 use kernel_services::{
+    comm::error::CommError,
     error::{AppError, AuthError},
+    link::error::LinkError,
     setup::error::SetupError,
+    storage::error::StorageError,
 };
 use tonic::Status;
 
@@ -82,6 +85,45 @@ impl IntoStatus for SetupError {
     }
 }
 
+impl IntoStatus for CommError {
+    fn into_status(self) -> Status {
+        match self {
+            | CommError::InvalidBotState(err) => {
+                Status::failed_precondition(err)
+            }
+            | CommError::UnknownMessage(err) => Status::not_found(err),
+        }
+    }
+}
+
+impl IntoStatus for LinkError {
+    fn into_status(self) -> Status {
+        match self {
+            | LinkError::InvalidParams(err) => Status::invalid_argument(err),
+            | LinkError::UnsupportedEvent(err) => Status::unimplemented(err),
+            | LinkError::InvalidChannelState(err) => {
+                Status::failed_precondition(err)
+            }
+            | _ => Status::internal("internal error"),
+        }
+    }
+}
+
+impl IntoStatus for StorageError {
+    fn into_status(self) -> Status {
+        match self {
+            | StorageError::NotFound => Status::not_found("blob not found"),
+            | StorageError::InvalidKey(_) => {
+                Status::invalid_argument("invalid blob key")
+            }
+            | StorageError::TooLarge { .. } => {
+                Status::invalid_argument("blob is too large")
+            }
+            | _ => Status::internal("internal error"),
+        }
+    }
+}
+
 impl IntoStatus for AppError {
     fn into_status(self) -> Status {
         match self {
@@ -89,6 +131,9 @@ impl IntoStatus for AppError {
             | AppError::Setup(err) => err.into_status(),
             | AppError::Auth(err) => err.into_status(),
             | AppError::Repo(err) => err.into_status(),
+            | AppError::Comm(err) => err.into_status(),
+            | AppError::Link(err) => err.into_status(),
+            | AppError::Storage(err) => err.into_status(),
             | _ => Status::internal("internal error"),
         }
     }
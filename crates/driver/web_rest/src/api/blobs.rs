@@ -0,0 +1,44 @@
+use aide::axum::ApiRouter;
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing,
+};
+use driver_web_common::{
+    state::AppState,
+    storage::{
+        config::{BlobTokenConfig, BLOB_TOKEN_CONFIG_SECTION},
+        token::BlobToken,
+    },
+};
+use kernel_services::config::ConfigService;
+
+use crate::error::{ApiError, ApiResult};
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+pub async fn download(
+    Path(token): Path<String>,
+    state: State<AppState>,
+) -> ApiResult<Response> {
+    let config = state
+        .config
+        .get_section::<BlobTokenConfig>(BLOB_TOKEN_CONFIG_SECTION)?;
+
+    let token = BlobToken::decode(&token, &config).map_err(|err| {
+        warn!("invalid blob download token: {err}");
+        ApiError::Authorization("invalid download token".into())
+    })?;
+
+    let blob = state.blobs.get(&token.sub).await?;
+    let content_type = blob
+        .content_type
+        .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_owned());
+
+    Ok(([(header::CONTENT_TYPE, content_type)], blob.data).into_response())
+}
+
+pub fn routes() -> ApiRouter<AppState> {
+    ApiRouter::new().route("/:token", routing::get(download))
+}
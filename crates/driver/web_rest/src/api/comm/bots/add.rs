@@ -2,6 +2,7 @@ use axum::extract::State;
 use driver_web_common::{auth::validator::AuthValidator, state::AppState};
 use kernel_entities::entities::{auth::*, comm::Bot};
 use kernel_repositories::comm::InsertBot;
+use kernel_services::comm::bots::BotsService;
 
 use super::dtos::{AddBotDto, BotDto};
 use crate::{
@@ -26,8 +27,28 @@ pub async fn add(
         .data
         .comm()
         .bots()
-        .create(InsertBot::new(form.name, form.is_active, form.user_id))
+        .create(InsertBot::new(
+            form.name,
+            form.is_active,
+            form.user_id,
+            form.navigation.fallback_reply,
+            form.navigation.back_trigger,
+            form.navigation.home_trigger,
+            form.navigation.help_trigger,
+            form.navigation.reprompt_after,
+        ))
         .await?;
 
+    state
+        .bots
+        .reload_bot(&bot.user_id, &bot.id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!(
+                "could not load bot #{} of user #{}: {err}",
+                bot.id, bot.user_id
+            )
+        });
+
     Ok(Created::new("/api/comm/bots", bot).into())
 }
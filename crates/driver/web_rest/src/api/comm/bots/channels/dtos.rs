@@ -0,0 +1,35 @@
+use aide::OperationIo;
+use chrono::{DateTime, Utc};
+use kernel_entities::{
+    entities::{
+        comm::{Bot, BotChannel},
+        link::Channel,
+    },
+    traits::Key,
+};
+use mapper::Mapper;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Mapper, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[from(BotChannel)]
+#[aide(output)]
+pub struct BotChannelDto {
+    pub id: Key<BotChannel>,
+    pub bot_id: Key<Bot>,
+    pub channel_id: Key<Channel>,
+    pub priority: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(input)]
+pub struct BindChannelDto {
+    /// Bots with lower priorities are tried first on the channel
+    #[serde(default)]
+    pub priority: i32,
+}
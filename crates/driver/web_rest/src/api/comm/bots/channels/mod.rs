@@ -0,0 +1,16 @@
+mod dtos;
+mod remove;
+mod update;
+mod view;
+
+use aide::axum::{
+    routing::{get, put},
+    ApiRouter,
+};
+use driver_web_common::state::AppState;
+
+pub fn routes() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route("/", get(view::get_all))
+        .api_route("/:channel_id", put(update::bind).delete(remove::unbind))
+}
@@ -0,0 +1,36 @@
+use axum::extract::{Path, State};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::*, comm::Bot, link::Channel},
+    traits::Key,
+};
+use kernel_services::comm::bots::BotsService;
+
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub async fn unbind(
+    auth: RestAuthToken,
+    Path((bot_id, channel_id)): Path<(Key<Bot>, Key<Channel>)>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let bot = state.data.comm().bots().get(&bot_id).await?;
+
+    auth.can(&[(Resource::Bot, Action::Modify)])?
+        .of(&bot.user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    state
+        .data
+        .comm()
+        .bots()
+        .unbind_channel(&bot.id, &channel_id)
+        .await?;
+
+    state
+        .bots
+        .reload_bot(&bot.user_id, &bot.id)
+        .await
+        .unwrap_or_else(|err| warn!("could not reload bot #{}: {err}", bot.id));
+
+    Ok(())
+}
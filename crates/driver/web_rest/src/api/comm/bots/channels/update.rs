@@ -0,0 +1,53 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::*, comm::Bot, link::Channel},
+    traits::Key,
+};
+use kernel_services::comm::bots::BotsService;
+
+use super::dtos::{BindChannelDto, BotChannelDto};
+use crate::{
+    error::ApiResult,
+    extractors::validated_json::ValidatedJson,
+    util::auth::token::RestAuthToken,
+};
+
+pub async fn bind(
+    auth: RestAuthToken,
+    Path((bot_id, channel_id)): Path<(Key<Bot>, Key<Channel>)>,
+    state: State<AppState>,
+    ValidatedJson(form): ValidatedJson<BindChannelDto>,
+) -> ApiResult<Json<BotChannelDto>> {
+    let bot = state.data.comm().bots().get(&bot_id).await?;
+
+    auth.can(&[(Resource::Bot, Action::Modify)])?
+        .of(&bot.user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    // bots can only be bound to the channels of their users
+    let channel = state
+        .data
+        .link()
+        .channels()
+        .get_of(&bot.user_id, &channel_id)
+        .await?;
+
+    let binding = state
+        .data
+        .comm()
+        .bots()
+        .bind_channel(&bot.id, &channel.id, form.priority)
+        .await?;
+
+    state
+        .bots
+        .reload_bot(&bot.user_id, &bot.id)
+        .await
+        .unwrap_or_else(|err| warn!("could not reload bot #{}: {err}", bot.id));
+
+    Ok(Json(binding.into()))
+}
@@ -0,0 +1,37 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use itertools::Itertools;
+use kernel_entities::{
+    entities::{auth::*, comm::Bot},
+    traits::Key,
+};
+
+use super::dtos::BotChannelDto;
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub async fn get_all(
+    auth: RestAuthToken,
+    bot_id: Path<Key<Bot>>,
+    state: State<AppState>,
+) -> ApiResult<Json<Vec<BotChannelDto>>> {
+    let bot = state.data.comm().bots().get(&bot_id).await?;
+
+    auth.can(&[(Resource::Bot, Action::View)])?
+        .of(&bot.user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    let channels = state
+        .data
+        .comm()
+        .bots()
+        .get_channels_of(&bot.id)
+        .await?
+        .into_iter()
+        .map(BotChannelDto::from)
+        .collect_vec();
+
+    Ok(Json(channels))
+}
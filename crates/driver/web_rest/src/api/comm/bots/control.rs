@@ -0,0 +1,139 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::*, comm::Bot},
+    traits::Key,
+};
+use kernel_services::comm::bots::BotsService;
+
+use super::dtos::BotStatusDto;
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub async fn start(
+    auth: RestAuthToken,
+    bot_id: Path<Key<Bot>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let bot = get_bot(&auth, &bot_id, Action::Modify, &state).await?;
+
+    state.bots.start_bot(&bot.user_id, &bot.id).await?;
+
+    Ok(())
+}
+
+pub async fn stop(
+    auth: RestAuthToken,
+    bot_id: Path<Key<Bot>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let bot = get_bot(&auth, &bot_id, Action::Modify, &state).await?;
+
+    state.bots.stop_bot(&bot.user_id, &bot.id).await?;
+
+    Ok(())
+}
+
+pub async fn reload(
+    auth: RestAuthToken,
+    bot_id: Path<Key<Bot>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let bot = get_bot(&auth, &bot_id, Action::Modify, &state).await?;
+
+    state.bots.reload_bot(&bot.user_id, &bot.id).await?;
+
+    Ok(())
+}
+
+pub async fn status(
+    auth: RestAuthToken,
+    bot_id: Path<Key<Bot>>,
+    state: State<AppState>,
+) -> ApiResult<Json<BotStatusDto>> {
+    let bot = get_bot(&auth, &bot_id, Action::View, &state).await?;
+    let status = state.bots.status(&bot.id).await?;
+
+    Ok(Json(BotStatusDto::new(bot.id, status)))
+}
+
+pub async fn start_all_of(
+    auth: RestAuthToken,
+    user_id: Path<Key<User>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    authorize_of(&auth, &user_id, Action::Modify)?;
+
+    state.bots.start_bots_of(&user_id).await?;
+
+    Ok(())
+}
+
+pub async fn stop_all_of(
+    auth: RestAuthToken,
+    user_id: Path<Key<User>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    authorize_of(&auth, &user_id, Action::Modify)?;
+
+    state.bots.stop_bots_of(&user_id).await?;
+
+    Ok(())
+}
+
+pub async fn reload_all_of(
+    auth: RestAuthToken,
+    user_id: Path<Key<User>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    authorize_of(&auth, &user_id, Action::Modify)?;
+
+    state.bots.reload_bots_of(&user_id).await?;
+
+    Ok(())
+}
+
+pub async fn status_of(
+    auth: RestAuthToken,
+    user_id: Path<Key<User>>,
+    state: State<AppState>,
+) -> ApiResult<Json<Vec<BotStatusDto>>> {
+    authorize_of(&auth, &user_id, Action::View)?;
+
+    let statuses = state
+        .bots
+        .status_of(&user_id)
+        .await?
+        .into_iter()
+        .map(|(bot_id, status)| BotStatusDto::new(bot_id, Some(status)))
+        .collect();
+
+    Ok(Json(statuses))
+}
+
+async fn get_bot(
+    auth: &RestAuthToken,
+    bot_id: &Key<Bot>,
+    action: Action,
+    state: &AppState,
+) -> ApiResult<Bot> {
+    let bot = state.data.comm().bots().get(bot_id).await?;
+
+    authorize_of(auth, &bot.user_id, action)?;
+
+    Ok(bot)
+}
+
+fn authorize_of(
+    auth: &RestAuthToken,
+    user_id: &Key<User>,
+    action: Action,
+) -> ApiResult<()> {
+    auth.can(&[(Resource::Bot, action)])?
+        .of(user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    Ok(())
+}
@@ -1,11 +1,24 @@
 use aide::OperationIo;
 use chrono::{DateTime, Utc};
-use kernel_entities::{entities::{comm::Bot, auth::User}, traits::Key};
+use common_macros::into_fn;
+use kernel_entities::{
+    entities::{
+        auth::User,
+        comm::{Bot, Menu},
+    },
+    traits::Key,
+};
+use kernel_services::comm::bots::BotStatus;
 use mapper::Mapper;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+into_fn!(default_back_trigger: Option<String> => "back".to_owned());
+into_fn!(default_home_trigger: Option<String> => "home".to_owned());
+into_fn!(default_help_trigger: Option<String> => "help".to_owned());
+into_fn!(default_reprompt_after: Option<i32> => 3);
+
 #[derive(Debug, Deserialize, Mapper, Serialize, JsonSchema, OperationIo)]
 #[serde(rename_all = "camelCase")]
 #[from(Bot)]
@@ -15,6 +28,11 @@ pub struct BotDto {
     pub name: String,
     pub is_active: bool,
     pub user_id: Key<User>,
+    pub fallback_reply: Option<String>,
+    pub back_trigger: Option<String>,
+    pub home_trigger: Option<String>,
+    pub help_trigger: Option<String>,
+    pub reprompt_after: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -26,4 +44,77 @@ pub struct AddBotDto {
     pub name: String,
     pub is_active: bool,
     pub user_id: Key<User>,
+    #[serde(flatten)]
+    #[validate]
+    pub navigation: BotNavigationDto,
+}
+
+/// Replaces all settings of a bot, where omitted built-in triggers are reset
+/// to their defaults.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(input)]
+pub struct UpdateBotDto {
+    pub name: String,
+    pub is_active: bool,
+    #[serde(flatten)]
+    #[validate]
+    pub navigation: BotNavigationDto,
+}
+
+/// How a bot replies to messages that match no menu trigger. Built-in
+/// triggers default to `back`, `home` and `help`, and are disabled when
+/// set to `null`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct BotNavigationDto {
+    #[validate(length(min = 1, max = 4096))]
+    pub fallback_reply: Option<String>,
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default = "default_back_trigger")]
+    pub back_trigger: Option<String>,
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default = "default_home_trigger")]
+    pub home_trigger: Option<String>,
+    #[validate(length(min = 1, max = 64))]
+    #[serde(default = "default_help_trigger")]
+    pub help_trigger: Option<String>,
+    /// Number of unmatched messages after which the current menu is sent
+    /// again, or `null` to never re-prompt
+    #[validate(range(min = 1))]
+    #[serde(default = "default_reprompt_after")]
+    pub reprompt_after: Option<i32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(output)]
+pub struct BotStatusDto {
+    pub bot_id: Key<Bot>,
+    pub is_loaded: bool,
+    pub is_running: bool,
+    pub loaded_at: Option<DateTime<Utc>>,
+    pub entry_menu_id: Option<Key<Menu>>,
+}
+
+impl BotStatusDto {
+    pub fn new(bot_id: Key<Bot>, status: Option<BotStatus>) -> Self {
+        let Some(status) = status else {
+            return Self {
+                bot_id,
+                is_loaded: false,
+                is_running: false,
+                loaded_at: None,
+                entry_menu_id: None,
+            };
+        };
+
+        Self {
+            bot_id,
+            is_loaded: true,
+            is_running: status.is_running,
+            loaded_at: Some(status.loaded_at),
+            entry_menu_id: Some(status.entry_menu_id),
+        }
+    }
 }
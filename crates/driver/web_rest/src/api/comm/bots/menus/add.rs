@@ -2,6 +2,7 @@ use axum::extract::State;
 use driver_web_common::{auth::validator::AuthValidator, state::AppState};
 use kernel_entities::entities::{auth::*, comm::Menu};
 use kernel_repositories::comm::InsertMenu;
+use kernel_services::comm::bots::BotsService;
 
 use super::dtos::{AddMenuDto, MenuDto};
 use crate::{
@@ -37,8 +38,18 @@ pub async fn add(
             form.is_active,
             form.parent_menu_id,
             form.bot_id,
+            form.hands_off,
+            form.content_format,
         ))
         .await?;
 
+    state
+        .bots
+        .reload_bot(&menu_bot.user_id, &menu_bot.id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("could not reload bot #{}: {err}", menu_bot.id)
+        });
+
     Ok(Created::new("/api/comm/bots/menus", menu).into())
 }
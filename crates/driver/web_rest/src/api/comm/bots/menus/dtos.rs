@@ -1,7 +1,7 @@
 use aide::OperationIo;
 use chrono::{DateTime, Utc};
 use kernel_entities::{
-    entities::comm::{Bot, Menu, TriggerMatchingStrategy},
+    entities::comm::{Bot, Menu, MenuContentFormat, TriggerMatchingStrategy},
     traits::Key,
 };
 use mapper::Mapper;
@@ -22,6 +22,8 @@ pub struct MenuDto {
     pub is_active: bool,
     pub parent_menu_id: Key<Menu>,
     pub bot_id: Key<Bot>,
+    pub hands_off: bool,
+    pub content_format: MenuContentFormat,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -37,4 +39,8 @@ pub struct AddMenuDto {
     pub is_active: bool,
     pub parent_menu_id: Option<Key<Menu>>,
     pub bot_id: Key<Bot>,
+    #[serde(default)]
+    pub hands_off: bool,
+    #[serde(default)]
+    pub content_format: MenuContentFormat,
 }
@@ -7,6 +7,7 @@ use kernel_entities::{
     },
     traits::Key,
 };
+use kernel_services::comm::bots::BotsService;
 
 use crate::{error::ApiResult, util::auth::token::RestAuthToken};
 
@@ -30,5 +31,13 @@ pub async fn remove(
         .remove_of(&bot_id, &menu_id)
         .await?;
 
+    state
+        .bots
+        .reload_bot(&menu_bot.user_id, &menu_bot.id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("could not reload bot #{}: {err}", menu_bot.id)
+        });
+
     Ok(())
 }
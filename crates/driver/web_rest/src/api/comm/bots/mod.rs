@@ -1,15 +1,39 @@
 mod add;
+mod channels;
+mod control;
 mod dtos;
 mod menus;
 mod remove;
+mod update;
 mod view;
 
-use aide::axum::{routing::get, ApiRouter};
+use aide::axum::{
+    routing::{get, post},
+    ApiRouter,
+};
 use driver_web_common::state::AppState;
 
 pub fn routes() -> ApiRouter<AppState> {
     ApiRouter::new()
         .api_route("/", get(view::get_all).post(add::add))
-        .api_route("/:bot_id", get(view::get_by_id).delete(remove::remove))
+        .api_route(
+            "/:bot_id",
+            get(view::get_by_id)
+                .put(update::update)
+                .delete(remove::remove),
+        )
+        .api_route("/:bot_id/start", post(control::start))
+        .api_route("/:bot_id/stop", post(control::stop))
+        .api_route("/:bot_id/reload", post(control::reload))
+        .api_route("/:bot_id/status", get(control::status))
+        .nest("/:bot_id/channels", channels::routes())
         .nest("/:bot_id/menus", menus::routes())
 }
+
+pub fn user_routes() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route("/start", post(control::start_all_of))
+        .api_route("/stop", post(control::stop_all_of))
+        .api_route("/reload", post(control::reload_all_of))
+        .api_route("/status", get(control::status_of))
+}
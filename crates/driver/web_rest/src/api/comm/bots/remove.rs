@@ -4,6 +4,7 @@ use kernel_entities::{
     entities::{auth::*, comm::Bot},
     traits::Key,
 };
+use kernel_services::comm::bots::BotsService;
 
 use crate::{error::ApiResult, util::auth::token::RestAuthToken};
 
@@ -15,19 +16,32 @@ pub async fn remove(
 ) -> ApiResult<()> {
     auth.can(&[(Resource::Bot, Action::Remove)])?;
 
-    match user_id {
+    let bot = match user_id {
         | Some(user_id) => {
             auth.of(&user_id)
                 .or_else(|_| auth.in_role(KnownRoles::Admin))?;
 
-            state.data.comm().bots().remove_of(&user_id, &bot_id).await
+            state.data.comm().bots().get_of(&user_id, &bot_id).await
         }
         | None => {
             auth.in_role(KnownRoles::Admin)?;
 
-            state.data.comm().bots().remove(&bot_id).await
+            state.data.comm().bots().get(&bot_id).await
         }
     }?;
 
+    state
+        .data
+        .comm()
+        .bots()
+        .remove_of(&bot.user_id, &bot.id)
+        .await?;
+
+    state
+        .bots
+        .stop_bot(&bot.user_id, &bot.id)
+        .await
+        .unwrap_or_else(|err| warn!("could not stop bot #{}: {err}", bot.id));
+
     Ok(())
 }
@@ -0,0 +1,55 @@
+use axum::extract::{Path, State};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::*, comm::Bot},
+    traits::Key,
+};
+use kernel_repositories::comm::UpdateBot;
+use kernel_services::comm::bots::BotsService;
+
+use super::dtos::UpdateBotDto;
+use crate::{
+    error::ApiResult,
+    extractors::validated_json::ValidatedJson,
+    util::auth::token::RestAuthToken,
+};
+
+pub async fn update(
+    auth: RestAuthToken,
+    bot_id: Path<Key<Bot>>,
+    state: State<AppState>,
+    ValidatedJson(form): ValidatedJson<UpdateBotDto>,
+) -> ApiResult<()> {
+    let bot = state.data.comm().bots().get(&bot_id).await?;
+
+    auth.can(&[(Resource::Bot, Action::Modify)])?
+        .of(&bot.user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    state
+        .data
+        .comm()
+        .bots()
+        .update(
+            &bot.id,
+            UpdateBot::new(
+                form.name,
+                form.is_active,
+                form.navigation.fallback_reply,
+                form.navigation.back_trigger,
+                form.navigation.home_trigger,
+                form.navigation.help_trigger,
+                form.navigation.reprompt_after,
+            ),
+        )
+        .await?;
+
+    // deactivated bots are stopped by the reload
+    state
+        .bots
+        .reload_bot(&bot.user_id, &bot.id)
+        .await
+        .unwrap_or_else(|err| warn!("could not reload bot #{}: {err}", bot.id));
+
+    Ok(())
+}
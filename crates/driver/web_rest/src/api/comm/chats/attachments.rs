@@ -0,0 +1,63 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{TimeZone, Utc};
+use driver_web_common::{
+    auth::validator::AuthValidator,
+    state::AppState,
+    storage::{
+        config::{BlobTokenConfig, BLOB_TOKEN_CONFIG_SECTION},
+        token::BlobToken,
+    },
+};
+use kernel_entities::{
+    entities::{
+        auth::{Action, Resource},
+        comm::{Chat, Message},
+    },
+    traits::Key,
+};
+use kernel_repositories::error::RepoError;
+use kernel_services::{
+    config::ConfigService,
+    error::AppError,
+    link::error::LinkError,
+    storage::blob::blob_key_of,
+};
+
+use super::{dtos::AttachmentUrlDto, get_chat};
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub async fn get_url(
+    auth: RestAuthToken,
+    Path((chat_id, message_id, index)): Path<(Key<Chat>, Key<Message>, usize)>,
+    state: State<AppState>,
+) -> ApiResult<Json<AttachmentUrlDto>> {
+    auth.can(&[(Resource::Message, Action::View)])?;
+
+    let chat = get_chat(&auth, &chat_id, Action::View, &state).await?;
+    let message = state.docs.messages().get_of(&chat.id, &message_id).await?;
+
+    let Some(attachment) = message.attachments.get(index) else {
+        return Err(RepoError::NotFound.into());
+    };
+
+    let Some(key) = blob_key_of(&attachment.uri) else {
+        return Err(AppError::from(LinkError::InvalidParams(
+            "attachment content is not stored".into(),
+        ))
+        .into());
+    };
+
+    let config = state
+        .config
+        .get_section::<BlobTokenConfig>(BLOB_TOKEN_CONFIG_SECTION)?;
+
+    let token = BlobToken::new(key, &config);
+
+    Ok(Json(AttachmentUrlDto {
+        url: token.download_path(&config)?,
+        expires_at: Utc.timestamp_opt(token.exp, 0).single(),
+    }))
+}
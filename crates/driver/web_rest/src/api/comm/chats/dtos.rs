@@ -0,0 +1,46 @@
+use aide::OperationIo;
+use chrono::{DateTime, Utc};
+use kernel_entities::{
+    entities::comm::{Chat, ChatHandler},
+    traits::Key,
+};
+use kernel_services::comm::chats::ChatHandoff;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(output)]
+pub struct ChatHandoffDto {
+    pub chat_id: Key<Chat>,
+    pub handler: ChatHandler,
+    /// When bots resume answering, if handed off to a human operator
+    pub resumes_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Validate, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(input)]
+pub struct SetHandoffDto {
+    pub handler: ChatHandler,
+}
+
+#[derive(Debug, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(output)]
+pub struct AttachmentUrlDto {
+    /// Path of the download url, which works without authentication
+    pub url: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ChatHandoffDto {
+    pub fn new(chat_id: Key<Chat>, handoff: ChatHandoff) -> Self {
+        Self {
+            chat_id,
+            handler: handoff.handler,
+            resumes_at: handoff.resumes_at,
+        }
+    }
+}
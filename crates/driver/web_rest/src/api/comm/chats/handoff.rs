@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::state::AppState;
+use kernel_entities::{
+    entities::{auth::Action, comm::Chat},
+    traits::Key,
+};
+use kernel_services::comm::chats::ChatsService;
+
+use super::{
+    dtos::{ChatHandoffDto, SetHandoffDto},
+    get_chat,
+};
+use crate::{
+    error::ApiResult,
+    extractors::validated_json::ValidatedJson,
+    util::auth::token::RestAuthToken,
+};
+
+pub async fn get_handoff(
+    auth: RestAuthToken,
+    chat_id: Path<Key<Chat>>,
+    state: State<AppState>,
+) -> ApiResult<Json<ChatHandoffDto>> {
+    let chat = get_chat(&auth, &chat_id, Action::View, &state).await?;
+    let handoff = state.chats.get_handoff(&chat.id).await?;
+
+    Ok(Json(ChatHandoffDto::new(chat.id, handoff)))
+}
+
+pub async fn set_handoff(
+    auth: RestAuthToken,
+    chat_id: Path<Key<Chat>>,
+    state: State<AppState>,
+    ValidatedJson(form): ValidatedJson<SetHandoffDto>,
+) -> ApiResult<Json<ChatHandoffDto>> {
+    let chat = get_chat(&auth, &chat_id, Action::Modify, &state).await?;
+    let handoff = state.chats.set_handler(&chat.id, form.handler).await?;
+
+    Ok(Json(ChatHandoffDto::new(chat.id, handoff)))
+}
@@ -0,0 +1,39 @@
+mod attachments;
+mod dtos;
+mod handoff;
+
+use aide::axum::{routing::get, ApiRouter};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::*, comm::Chat},
+    traits::Key,
+};
+
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub fn routes() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route(
+            "/:chat_id/handoff",
+            get(handoff::get_handoff).put(handoff::set_handoff),
+        )
+        .api_route(
+            "/:chat_id/messages/:message_id/attachments/:index/url",
+            get(attachments::get_url),
+        )
+}
+
+async fn get_chat(
+    auth: &RestAuthToken,
+    chat_id: &Key<Chat>,
+    action: Action,
+    state: &AppState,
+) -> ApiResult<Chat> {
+    let chat = state.docs.chats().get(chat_id).await?;
+
+    auth.can(&[(Resource::Chat, action)])?
+        .of(&chat.user_id)
+        .or_else(|_| auth.in_role(KnownRoles::Admin))?;
+
+    Ok(chat)
+}
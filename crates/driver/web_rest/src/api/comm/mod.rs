@@ -1,8 +1,12 @@
 mod bots;
+mod chats;
 
 use aide::axum::ApiRouter;
 use driver_web_common::state::AppState;
 
 pub fn routes() -> ApiRouter<AppState> {
-    ApiRouter::new().nest("/bots", bots::routes())
+    ApiRouter::new()
+        .nest("/bots", bots::routes())
+        .nest("/users/:user_id/bots", bots::user_routes())
+        .nest("/chats", chats::routes())
 }
@@ -4,7 +4,10 @@ use kernel_entities::entities::{auth::*, link::Channel};
 use kernel_repositories::link::InsertChannel;
 use kernel_services::link::channels::ChannelsService;
 
-use super::dtos::{AddChannelDto, ChannelDto};
+use super::{
+    dtos::{AddChannelDto, ChannelDto},
+    webhook::webhook_secret_of,
+};
 use crate::{
     error::ApiResult,
     extractors::validated_json::ValidatedJson,
@@ -22,6 +25,13 @@ pub async fn add(
     auth.of(&form.user_id)?
         .can(&[(Resource::Channel, Action::Add)])?;
 
+    let webhook_secret = webhook_secret_of(
+        &*state.entropy,
+        form.platform,
+        form.use_webhook,
+        form.webhook_secret,
+    )?;
+
     let channel = state
         .data
         .link()
@@ -31,6 +41,11 @@ pub async fn add(
             form.name,
             form.platform,
             form.api_key,
+            form.phone_number_id,
+            webhook_secret,
+            form.app_secret,
+            form.use_webhook,
+            form.group_mode,
             form.valid_until,
             form.is_active,
         ))
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{
+        auth::{Action, Resource},
+        link::Channel,
+    },
+    traits::Key,
+};
+use kernel_services::{
+    error::AppError,
+    link::{channels::ChannelsService, error::LinkError},
+};
+
+use super::dtos::ChannelStatusDto;
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub async fn start(
+    auth: RestAuthToken,
+    channel_id: Path<Key<Channel>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let channel = get_channel(&auth, &channel_id, &state).await?;
+
+    if !channel.is_active {
+        return Err(AppError::from(LinkError::InvalidChannelState(
+            "channel is not active".into(),
+        ))
+        .into());
+    }
+
+    state
+        .channels
+        .start_channel(&channel.user_id, &channel.id)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn stop(
+    auth: RestAuthToken,
+    channel_id: Path<Key<Channel>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let channel = get_channel(&auth, &channel_id, &state).await?;
+
+    state
+        .channels
+        .stop_channel(&channel.user_id, &channel.id)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn restart(
+    auth: RestAuthToken,
+    channel_id: Path<Key<Channel>>,
+    state: State<AppState>,
+) -> ApiResult<()> {
+    let channel = get_channel(&auth, &channel_id, &state).await?;
+
+    if !channel.is_active {
+        return Err(AppError::from(LinkError::InvalidChannelState(
+            "channel is not active".into(),
+        ))
+        .into());
+    }
+
+    state
+        .channels
+        .restart_channel(&channel.user_id, &channel.id)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn status(
+    auth: RestAuthToken,
+    channel_id: Path<Key<Channel>>,
+    state: State<AppState>,
+) -> ApiResult<Json<ChannelStatusDto>> {
+    let channel = state.data.link().channels().get(&channel_id).await?;
+
+    auth.can(&[(Resource::Channel, Action::View)])?
+        .of(&channel.user_id)?;
+
+    let status = state.channels.status(&channel.id).await?;
+
+    Ok(Json(status.into()))
+}
+
+async fn get_channel(
+    auth: &RestAuthToken,
+    channel_id: &Key<Channel>,
+    state: &AppState,
+) -> ApiResult<Channel> {
+    let channel = state.data.link().channels().get(channel_id).await?;
+
+    auth.can(&[(Resource::Channel, Action::Modify)])?
+        .of(&channel.user_id)?;
+
+    Ok(channel)
+}
@@ -4,25 +4,30 @@ use common_validation::*;
 use kernel_entities::{
     entities::{
         auth::User,
-        link::{Channel, ChannelPlatform},
+        link::{Channel, ChannelGroupMode, ChannelPlatform},
     },
     traits::Key,
 };
-use kernel_repositories::link::UpdateChannel;
-use mapper::Mapper;
+use kernel_services::link::channels::ChannelStatus;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-#[derive(Debug, Deserialize, Mapper, Serialize, JsonSchema, OperationIo)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, OperationIo)]
 #[serde(rename_all = "camelCase")]
-#[from(Channel)]
 #[aide(output)]
 pub struct ChannelDto {
     pub id: Key<Channel>,
     pub name: String,
     pub platform: ChannelPlatform,
+    /// Redacted api key, with only its last characters shown
     pub api_key: String,
+    pub phone_number_id: Option<String>,
+    /// Redacted secret token of webhook requests, with only its last
+    /// characters shown
+    pub webhook_secret: Option<String>,
+    pub use_webhook: bool,
+    pub group_mode: ChannelGroupMode,
     pub valid_until: Option<DateTime<Utc>>,
     pub is_active: bool,
     pub max_instances: Option<i64>,
@@ -33,6 +38,7 @@ pub struct ChannelDto {
 
 #[derive(Debug, Deserialize, Serialize, Validate, JsonSchema, OperationIo)]
 #[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_platform_params"))]
 #[aide(input)]
 pub struct AddChannelDto {
     pub user_id: Key<User>,
@@ -40,20 +46,152 @@ pub struct AddChannelDto {
     pub name: String,
     pub platform: ChannelPlatform,
     pub api_key: String,
+    #[validate(length(min = 1, max = 64))]
+    pub phone_number_id: Option<String>,
+    /// Secret token of webhook requests, generated for Telegram channels in
+    /// webhook mode when omitted
+    #[validate(custom = "webhook_secret")]
+    pub webhook_secret: Option<String>,
+    /// Secret of the WhatsApp app, used to verify the signatures of webhook
+    /// payloads
+    #[validate(length(min = 1, max = 256))]
+    pub app_secret: Option<String>,
+    #[serde(default)]
+    pub use_webhook: bool,
+    /// How messages sent in group chats are handled, defaults to `Mentions`
+    #[serde(default)]
+    pub group_mode: ChannelGroupMode,
     #[validate(custom = "in_future")]
     pub valid_until: Option<DateTime<Utc>>,
     pub is_active: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, Validate, JsonSchema, OperationIo, Mapper)]
+#[derive(Debug, Deserialize, Serialize, Validate, JsonSchema, OperationIo)]
 #[serde(rename_all = "camelCase")]
-#[from(UpdateChannel)]
 #[aide(input)]
 pub struct UpdateChannelDto {
     #[validate(length(min = 4, max = 32))]
     pub name: String,
-    pub api_key: String,
+    /// New api key of the channel, the current one is kept when omitted
+    pub api_key: Option<String>,
+    #[validate(length(min = 1, max = 64))]
+    pub phone_number_id: Option<String>,
+    /// New secret token of webhook requests, the current one is kept when
+    /// omitted
+    #[validate(custom = "webhook_secret")]
+    pub webhook_secret: Option<String>,
+    /// New app secret of the channel, the current one is kept when omitted
+    #[validate(length(min = 1, max = 256))]
+    pub app_secret: Option<String>,
+    #[serde(default)]
+    pub use_webhook: bool,
+    /// How messages sent in group chats are handled, defaults to `Mentions`
+    #[serde(default)]
+    pub group_mode: ChannelGroupMode,
     #[validate(custom = "in_future")]
     pub valid_until: Option<DateTime<Utc>>,
     pub is_active: bool,
 }
+
+#[derive(Debug, Deserialize, JsonSchema, OperationIo)]
+#[aide(input)]
+pub struct WebhookVerificationQuery {
+    #[serde(rename = "hub.mode")]
+    pub mode: String,
+    #[serde(rename = "hub.verify_token")]
+    pub verify_token: String,
+    #[serde(rename = "hub.challenge")]
+    pub challenge: String,
+}
+
+impl From<Channel> for ChannelDto {
+    fn from(value: Channel) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            platform: value.platform,
+            api_key: redact(&value.api_key),
+            phone_number_id: value.phone_number_id,
+            webhook_secret: value.webhook_secret.as_deref().map(redact),
+            use_webhook: value.use_webhook,
+            group_mode: value.group_mode,
+            valid_until: value.valid_until,
+            is_active: value.is_active,
+            max_instances: value.max_instances,
+            user_id: value.user_id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+/// Masks all but the last 4 characters of a secret, or all of it when it is
+/// too short for that to be safe.
+fn redact(secret: &str) -> String {
+    const REDACTED: &str = "********";
+
+    let chars = secret.chars().collect::<Vec<_>>();
+
+    if chars.len() < 16 {
+        return REDACTED.to_owned();
+    }
+
+    let tail = chars[chars.len() - 4..].iter().collect::<String>();
+
+    format!("{REDACTED}{tail}")
+}
+
+fn validate_platform_params(
+    form: &AddChannelDto,
+) -> Result<(), ValidationError> {
+    match form.platform {
+        | ChannelPlatform::WhatsApp if form.phone_number_id.is_none() => {
+            Err(ValidationError::new("missing_phone_number_id"))
+        }
+        | ChannelPlatform::WhatsApp if form.app_secret.is_none() => {
+            Err(ValidationError::new("missing_app_secret"))
+        }
+        | _ => Ok(()),
+    }
+}
+
+/// Status of a channel, where the health of a channel running on another node
+/// is unknown.
+#[derive(Debug, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(output)]
+pub struct ChannelStatusDto {
+    pub is_running: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub node_id: Option<String>,
+    pub is_degraded: Option<bool>,
+    pub restarts: Option<u32>,
+    pub last_error: Option<String>,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+impl From<Option<ChannelStatus>> for ChannelStatusDto {
+    fn from(value: Option<ChannelStatus>) -> Self {
+        let Some(status) = value else {
+            return Self {
+                is_running: false,
+                started_at: None,
+                node_id: None,
+                is_degraded: None,
+                restarts: None,
+                last_error: None,
+                last_activity: None,
+            };
+        };
+
+        Self {
+            is_running: true,
+            started_at: Some(status.started_at),
+            node_id: Some(status.node_id),
+            is_degraded: status.is_degraded,
+            restarts: status.restarts,
+            last_error: status.last_error,
+            last_activity: status.last_activity,
+        }
+    }
+}
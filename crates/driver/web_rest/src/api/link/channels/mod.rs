@@ -1,10 +1,16 @@
 mod add;
+mod control;
 mod dtos;
 mod remove;
 mod update;
 mod view;
+mod webhook;
 
-use aide::axum::{routing::get, ApiRouter};
+use aide::axum::{
+    routing::{get, post},
+    ApiRouter,
+};
+use axum::routing;
 use driver_web_common::state::AppState;
 
 pub fn routes() -> ApiRouter<AppState> {
@@ -16,4 +22,12 @@ pub fn routes() -> ApiRouter<AppState> {
                 .delete(remove::remove)
                 .patch(update::update),
         )
+        .api_route("/:channel_id/start", post(control::start))
+        .api_route("/:channel_id/stop", post(control::stop))
+        .api_route("/:channel_id/restart", post(control::restart))
+        .api_route("/:channel_id/status", get(control::status))
+        .route(
+            "/:channel_id/webhook",
+            routing::get(webhook::verify).post(webhook::receive),
+        )
 }
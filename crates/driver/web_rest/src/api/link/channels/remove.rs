@@ -7,7 +7,10 @@ use kernel_entities::{
     },
     traits::Key,
 };
-use kernel_services::link::channels::ChannelsService;
+use kernel_services::{
+    comm::bots::BotsService,
+    link::channels::ChannelsService,
+};
 
 use crate::{error::ApiResult, util::auth::token::RestAuthToken};
 
@@ -24,5 +27,14 @@ pub async fn remove(
     state.data.link().channels().remove(&channel.id).await?;
     state.channels.stop_channel(&channel.user_id, &channel.id).await?;
 
+    // bots drop the bindings of the removed channel
+    state
+        .bots
+        .reload_bots_of(&channel.user_id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("could not reload bots of #{}: {err}", channel.user_id)
+        });
+
     Ok(())
 }
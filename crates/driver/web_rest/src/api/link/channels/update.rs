@@ -3,13 +3,17 @@ use driver_web_common::{auth::validator::AuthValidator, state::AppState};
 use kernel_entities::{
     entities::{
         auth::{Action, Resource},
-        link::Channel,
+        link::{Channel, ChannelPlatform},
     },
     traits::Key,
 };
 use kernel_repositories::link::UpdateChannel;
+use kernel_services::{
+    error::AppError,
+    link::{channels::ChannelsService, error::LinkError},
+};
 
-use super::dtos::UpdateChannelDto;
+use super::{dtos::UpdateChannelDto, webhook::webhook_secret_of};
 use crate::{
     error::ApiResult,
     extractors::validated_json::ValidatedJson,
@@ -27,6 +31,41 @@ pub async fn update(
     auth.can(&[(Resource::Channel, Action::Modify)])?
         .of(&channel.user_id)?;
 
+    let api_key = form.api_key.unwrap_or_else(|| channel.api_key.clone());
+    let app_secret = form.app_secret.or_else(|| channel.app_secret.clone());
+    let webhook_secret = webhook_secret_of(
+        &*state.entropy,
+        channel.platform,
+        form.use_webhook,
+        form.webhook_secret
+            .or_else(|| channel.webhook_secret.clone()),
+    )?;
+
+    if let ChannelPlatform::WhatsApp = channel.platform {
+        if form.phone_number_id.is_none() {
+            return Err(AppError::from(LinkError::InvalidParams(
+                "whatsapp channels require a phone number id".into(),
+            ))
+            .into());
+        }
+
+        if app_secret.is_none() {
+            return Err(AppError::from(LinkError::InvalidParams(
+                "whatsapp channels require an app secret".into(),
+            ))
+            .into());
+        }
+    }
+
+    // changes to any of these require reloading the running channel
+    let needs_restart = channel.api_key != api_key
+        || channel.phone_number_id != form.phone_number_id
+        || channel.webhook_secret != webhook_secret
+        || channel.use_webhook != form.use_webhook
+        || channel.group_mode != form.group_mode
+        || channel.valid_until != form.valid_until;
+    let is_active = form.is_active;
+
     state
         .data
         .link()
@@ -35,12 +74,39 @@ pub async fn update(
             &channel_id,
             UpdateChannel {
                 name: form.name,
-                api_key: form.api_key,
+                api_key,
+                phone_number_id: form.phone_number_id,
+                webhook_secret,
+                app_secret,
+                use_webhook: form.use_webhook,
+                group_mode: form.group_mode,
                 valid_until: form.valid_until,
                 is_active: form.is_active,
             },
         )
         .await?;
 
+    match (channel.is_active, is_active) {
+        | (_, false) => {
+            state
+                .channels
+                .stop_channel(&channel.user_id, &channel.id)
+                .await?
+        }
+        | (false, true) => {
+            state
+                .channels
+                .start_channel(&channel.user_id, &channel.id)
+                .await?
+        }
+        | (true, true) if needs_restart => {
+            state
+                .channels
+                .restart_channel(&channel.user_id, &channel.id)
+                .await?
+        }
+        | _ => {}
+    }
+
     Ok(())
 }
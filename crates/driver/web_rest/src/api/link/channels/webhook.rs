@@ -0,0 +1,96 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+};
+use driver_web_common::state::AppState;
+use kernel_entities::{
+    entities::link::{Channel, ChannelPlatform},
+    traits::Key,
+};
+use kernel_services::{
+    entropy::{CharacterCase, EntropyService, RandomStringOptions},
+    error::AppResult,
+    link::channels::ChannelsService,
+};
+use subtle::ConstantTimeEq;
+
+use super::dtos::WebhookVerificationQuery;
+use crate::error::{ApiError, ApiResult};
+
+const SUBSCRIBE_MODE: &str = "subscribe";
+const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+const GENERATED_SECRET_LENGTH: usize = 64;
+
+pub async fn verify(
+    channel_id: Path<Key<Channel>>,
+    Query(query): Query<WebhookVerificationQuery>,
+    state: State<AppState>,
+) -> ApiResult<String> {
+    let channel = state.data.link().channels().get(&channel_id).await?;
+
+    // the token is compared in constant time, as it is a secret
+    let is_verified = query.mode == SUBSCRIBE_MODE
+        && channel.webhook_secret.as_deref().map_or(false, |secret| {
+            secret
+                .as_bytes()
+                .ct_eq(query.verify_token.as_bytes())
+                .into()
+        });
+
+    if !is_verified {
+        warn!("invalid webhook verification of channel #{}", channel.id);
+
+        return Err(ApiError::Authorization("invalid verify token".into()));
+    }
+
+    info!("webhook of channel #{} was verified", channel.id);
+
+    Ok(query.challenge)
+}
+
+pub async fn receive(
+    channel_id: Path<Key<Channel>>,
+    state: State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<()> {
+    let secret = headers
+        .get(SECRET_TOKEN_HEADER)
+        .or_else(|| headers.get(SIGNATURE_HEADER))
+        .and_then(|value| value.to_str().ok());
+
+    state
+        .channels
+        .push_webhook_update(&channel_id, secret, &body)
+        .await?;
+
+    Ok(())
+}
+
+/// Generates a webhook secret for telegram channels in webhook mode that were
+/// not given one, as their webhook updates cannot be authenticated otherwise.
+pub(super) fn webhook_secret_of(
+    entropy: &impl EntropyService,
+    platform: ChannelPlatform,
+    use_webhook: bool,
+    secret: Option<String>,
+) -> AppResult<Option<String>> {
+    match (platform, use_webhook, secret) {
+        | (ChannelPlatform::Telegram, true, None) => {
+            // telegram only accepts `A-Z`, `a-z`, `0-9`, `_` and `-` in secrets
+            let secret = entropy.next_string_with(
+                GENERATED_SECRET_LENGTH,
+                RandomStringOptions {
+                    alpha: Some(CharacterCase::Mixed),
+                    numeric: true,
+                    special: false,
+                },
+            )?;
+
+            Ok(Some(secret))
+        }
+        | (_, _, secret) => Ok(secret),
+    }
+}
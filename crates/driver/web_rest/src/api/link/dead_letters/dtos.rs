@@ -0,0 +1,47 @@
+use aide::OperationIo;
+use chrono::{DateTime, Utc};
+use common_macros::into_fn;
+use kernel_services::link::message_passing::DeadLetter;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::error::ApiResult;
+
+into_fn!(default_limit: const usize => 32);
+
+#[derive(Debug, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(output)]
+pub struct DeadLetterDto {
+    pub body: serde_json::Value,
+    pub attempts: u32,
+    pub dead_lettered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, OperationIo)]
+#[serde(rename_all = "camelCase")]
+#[aide(output)]
+pub struct DeadLettersCountDto {
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize, Validate, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLettersQuery {
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 256))]
+    pub limit: usize,
+}
+
+impl DeadLetterDto {
+    pub fn try_from_dead_letter<T: Serialize>(
+        letter: DeadLetter<T>,
+    ) -> ApiResult<Self> {
+        Ok(Self {
+            body: serde_json::to_value(letter.body)?,
+            attempts: letter.attempts,
+            dead_lettered_at: letter.dead_lettered_at,
+        })
+    }
+}
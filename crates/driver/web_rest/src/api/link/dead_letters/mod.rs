@@ -0,0 +1,27 @@
+mod dtos;
+mod purge;
+mod replay;
+mod view;
+
+use aide::axum::{
+    routing::{get, post},
+    ApiRouter,
+};
+use driver_web_common::state::AppState;
+
+pub fn routes() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route(
+            "/incoming",
+            get(view::get_incoming).delete(purge::purge_incoming),
+        )
+        .api_route("/incoming/replay", post(replay::replay_incoming))
+        .api_route(
+            "/outgoing/:channel_id",
+            get(view::get_outgoing).delete(purge::purge_outgoing),
+        )
+        .api_route(
+            "/outgoing/:channel_id/replay",
+            post(replay::replay_outgoing),
+        )
+}
@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::KnownRoles, link::Channel},
+    traits::Key,
+};
+use kernel_services::link::channels::ChannelsService;
+
+use super::dtos::DeadLettersCountDto;
+use crate::{error::ApiResult, util::auth::token::RestAuthToken};
+
+pub async fn purge_incoming(
+    auth: RestAuthToken,
+    state: State<AppState>,
+) -> ApiResult<Json<DeadLettersCountDto>> {
+    auth.in_role(KnownRoles::Admin)?;
+
+    let pipe = state.channels.get_pipe_of_all().await?;
+    let count = pipe.rx.purge_dead_letters().await?;
+
+    Ok(Json(DeadLettersCountDto { count }))
+}
+
+pub async fn purge_outgoing(
+    auth: RestAuthToken,
+    channel_id: Path<Key<Channel>>,
+    state: State<AppState>,
+) -> ApiResult<Json<DeadLettersCountDto>> {
+    auth.in_role(KnownRoles::Admin)?;
+
+    let channel = state.data.link().channels().get(&channel_id).await?;
+    let pipe = state
+        .channels
+        .get_reverse_pipe_of(&channel.user_id, &channel.id)
+        .await?;
+    let count = pipe.rx.purge_dead_letters().await?;
+
+    Ok(Json(DeadLettersCountDto { count }))
+}
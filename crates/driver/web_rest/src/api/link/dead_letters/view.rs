@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use driver_web_common::{auth::validator::AuthValidator, state::AppState};
+use kernel_entities::{
+    entities::{auth::KnownRoles, link::Channel},
+    traits::Key,
+};
+use kernel_services::link::channels::ChannelsService;
+
+use super::dtos::{DeadLetterDto, DeadLettersQuery};
+use crate::{
+    error::ApiResult,
+    extractors::validated_query::ValidatedQuery,
+    util::auth::token::RestAuthToken,
+};
+
+pub async fn get_incoming(
+    auth: RestAuthToken,
+    ValidatedQuery(query): ValidatedQuery<DeadLettersQuery>,
+    state: State<AppState>,
+) -> ApiResult<Json<Vec<DeadLetterDto>>> {
+    auth.in_role(KnownRoles::Admin)?;
+
+    let pipe = state.channels.get_pipe_of_all().await?;
+
+    pipe.rx
+        .dead_letters(query.limit)
+        .await?
+        .into_iter()
+        .map(DeadLetterDto::try_from_dead_letter)
+        .collect::<ApiResult<_>>()
+        .map(Json)
+}
+
+pub async fn get_outgoing(
+    auth: RestAuthToken,
+    channel_id: Path<Key<Channel>>,
+    ValidatedQuery(query): ValidatedQuery<DeadLettersQuery>,
+    state: State<AppState>,
+) -> ApiResult<Json<Vec<DeadLetterDto>>> {
+    auth.in_role(KnownRoles::Admin)?;
+
+    let channel = state.data.link().channels().get(&channel_id).await?;
+    let pipe = state
+        .channels
+        .get_reverse_pipe_of(&channel.user_id, &channel.id)
+        .await?;
+
+    pipe.rx
+        .dead_letters(query.limit)
+        .await?
+        .into_iter()
+        .map(DeadLetterDto::try_from_dead_letter)
+        .collect::<ApiResult<_>>()
+        .map(Json)
+}
@@ -20,6 +20,7 @@ use validator::Validate;
 pub struct InstanceDto {
     pub id: Key<Instance>,
     pub platform_identifier: i64,
+    pub platform_group_id: Option<i64>,
     pub username: Option<String>,
     pub display_name: Option<String>,
     pub phone_number: Option<String>,
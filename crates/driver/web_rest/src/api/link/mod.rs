@@ -1,4 +1,5 @@
 mod channels;
+mod dead_letters;
 mod instances;
 
 use aide::axum::ApiRouter;
@@ -7,5 +8,6 @@ use driver_web_common::state::AppState;
 pub fn routes() -> ApiRouter<AppState> {
     ApiRouter::new()
         .nest("/channels", channels::routes())
+        .nest("/dead_letters", dead_letters::routes())
         .nest("/instances", instances::routes())
 }
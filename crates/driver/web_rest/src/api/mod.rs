@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod blobs;
 pub mod comm;
 pub mod diag;
 pub mod link;
@@ -16,5 +17,6 @@ pub fn api_routes() -> AppResult<ApiRouter<AppState>> {
         .nest("/setup", setup::routes())
         .nest("/auth", auth::routes())
         .nest("/link", link::routes())
-        .nest("/comm", comm::routes()))
+        .nest("/comm", comm::routes())
+        .nest("/blobs", blobs::routes()))
 }
@@ -3,7 +3,12 @@ use axum::{
     extract::rejection::*, http::StatusCode, response::IntoResponse, Json,
 };
 use kernel_repositories::error::RepoError;
-use kernel_services::error::AppError;
+use kernel_services::{
+    comm::error::CommError,
+    error::AppError,
+    link::error::LinkError,
+    storage::error::StorageError,
+};
 use serde_json::json;
 use thiserror::Error;
 
@@ -74,6 +79,39 @@ impl IntoResponse for ApiError {
                     (StatusCode::UNAUTHORIZED, err.to_string())
                 }
 
+                AppError::Comm(err) => match err {
+                    CommError::InvalidBotState(_) => {
+                        (StatusCode::CONFLICT, err.to_string())
+                    }
+                    CommError::UnknownMessage(_) => {
+                        (StatusCode::NOT_FOUND, err.to_string())
+                    }
+                },
+
+                AppError::Link(err) => match err {
+                    LinkError::InvalidParams(_)
+                    | LinkError::UnsupportedEvent(_) => {
+                        (StatusCode::BAD_REQUEST, err.to_string())
+                    }
+                    LinkError::InvalidChannelState(_) => {
+                        (StatusCode::CONFLICT, err.to_string())
+                    }
+                    _ => status_tuple(StatusCode::INTERNAL_SERVER_ERROR),
+                },
+
+                AppError::Storage(err) => match err {
+                    StorageError::NotFound => {
+                        (StatusCode::NOT_FOUND, err.to_string())
+                    }
+                    StorageError::InvalidKey(_) => {
+                        (StatusCode::BAD_REQUEST, err.to_string())
+                    }
+                    StorageError::TooLarge { .. } => {
+                        (StatusCode::PAYLOAD_TOO_LARGE, err.to_string())
+                    }
+                    _ => status_tuple(StatusCode::INTERNAL_SERVER_ERROR),
+                },
+
                 _ => status_tuple(StatusCode::INTERNAL_SERVER_ERROR),
             },
         };
@@ -6,6 +6,8 @@ pub enum AttachmentKind {
     Document,
     Audio,
     Video,
+    Image,
+    Voice,
 }
 
 #[derive(Clone, Debug, JsonSchema, Serialize, Deserialize)]
@@ -2,7 +2,10 @@ use derive_more::{From, Into};
 use kernel_proc_macros::*;
 use schemars::JsonSchema;
 
-use crate::{entities::auth::User, traits::*};
+use crate::{
+    entities::{auth::User, link::Channel},
+    traits::*,
+};
 
 #[entity]
 #[derive(Clone, Debug, From, Into, JsonSchema)]
@@ -10,4 +13,26 @@ pub struct Bot {
     pub name: String,
     pub is_active: bool,
     pub user_id: Key<User>,
+    /// Reply to messages that match no trigger, if any. Bots with a fallback
+    /// reply answer all messages, before bots of lower priority get to.
+    pub fallback_reply: Option<String>,
+    /// Trigger to go back to the parent menu, if enabled
+    pub back_trigger: Option<String>,
+    /// Trigger to go back to the entry menu, if enabled
+    pub home_trigger: Option<String>,
+    /// Trigger to list the available triggers of the current menu, if enabled
+    pub help_trigger: Option<String>,
+    /// Number of unmatched messages after which the current menu is sent
+    /// again, if enabled
+    pub reprompt_after: Option<i32>,
+}
+
+/// Binds a bot to a channel of its user. Messages from a channel are handled
+/// by its bots in ascending order of priority, until one of them responds.
+#[entity]
+#[derive(Clone, Debug, From, Into, JsonSchema)]
+pub struct BotChannel {
+    pub bot_id: Key<Bot>,
+    pub channel_id: Key<Channel>,
+    pub priority: i32,
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use derive_more::Display;
 use kernel_proc_macros::entity;
 use schemars::JsonSchema;
@@ -12,6 +13,25 @@ pub enum ChatState {
     Closed,
 }
 
+/// Who is currently answering the messages of a chat.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Display,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum ChatHandler {
+    #[default]
+    Bot,
+    Human,
+}
+
 #[serde_with::serde_as]
 #[entity(bson_compat = true)]
 #[derive(Clone, Debug, JsonSchema, Serialize, Deserialize)]
@@ -19,4 +39,10 @@ pub struct Chat {
     pub label: Option<String>,
     pub state: ChatState,
     pub user_id: Key<User>,
+    #[serde(default)]
+    pub handler: ChatHandler,
+    /// Last activity of the human operator, while the chat is handed off
+    #[serde(default)]
+    #[serde_as(as = "Option<bson::DateTime>")]
+    pub human_active_at: Option<DateTime<Utc>>,
 }
@@ -14,6 +14,18 @@ pub enum TriggerMatchingStrategy {
     SubString = 1,
 }
 
+/// Format of the content of a menu. Plain content is sent as-is, while
+/// markdown content is sent with its formatting.
+#[EnumRepr(type = "i32")]
+#[derive(
+    Clone, Copy, Debug, Default, JsonSchema_repr, Deserialize, Serialize,
+)]
+pub enum MenuContentFormat {
+    #[default]
+    Plain = 0,
+    Markdown = 1,
+}
+
 #[entity]
 #[derive(Clone, Debug, From, Into, JsonSchema)]
 pub struct Menu {
@@ -24,6 +36,9 @@ pub struct Menu {
     pub is_active: bool,
     pub parent_menu_id: Key<Menu>,
     pub bot_id: Key<Bot>,
+    /// Whether reaching the menu hands the chat over to a human operator
+    pub hands_off: bool,
+    pub content_format: MenuContentFormat,
 }
 
 impl From<i32> for TriggerMatchingStrategy {
@@ -37,3 +52,15 @@ impl From<TriggerMatchingStrategy> for i32 {
         val.repr()
     }
 }
+
+impl From<i32> for MenuContentFormat {
+    fn from(value: i32) -> Self {
+        Self::from_repr(value).unwrap_or_default()
+    }
+}
+
+impl From<MenuContentFormat> for i32 {
+    fn from(val: MenuContentFormat) -> Self {
+        val.repr()
+    }
+}
@@ -1,11 +1,15 @@
 use chrono::{DateTime, Utc};
+use derive_more::Display;
 use kernel_proc_macros::entity;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::{attachment::Attachment, chat::Chat};
 use crate::{
-    entities::{auth::User, link::Instance},
+    entities::{
+        auth::User,
+        link::{Channel, Instance},
+    },
     traits::*,
 };
 
@@ -15,6 +19,27 @@ pub enum MessageDirection {
     Outgoing,
 }
 
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Display,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum MessageStatus {
+    Pending,
+    Sent,
+    #[default]
+    Delivered,
+    Seen,
+    Failed,
+}
+
 #[entity(bson_compat = true)]
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde_with::serde_as]
@@ -26,10 +51,43 @@ pub struct Message {
     pub user_id: Key<User>,
     pub chat_id: Key<Chat>,
     pub instance_id: Key<Instance>,
-    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
-    pub delivered_at: DateTime<Utc>,
+    /// Channel of the instance, which is missing on older messages
+    #[serde(default)]
+    pub channel_id: Option<Key<Channel>>,
+    pub platform_message_id: Option<String>,
+    pub reply_to: Option<Key<Message>>,
+    /// Data of the pressed button, if the message is a button press
+    #[serde(default)]
+    pub callback_data: Option<String>,
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub status: MessageStatus,
+    #[serde_as(as = "Option<bson::DateTime>")]
+    pub delivered_at: Option<DateTime<Utc>>,
     #[serde_as(as = "Option<bson::DateTime>")]
     pub seen_at: Option<DateTime<Utc>>,
     #[serde_as(as = "Option<bson::DateTime>")]
     pub deleted_at: Option<DateTime<Utc>>,
 }
+
+impl MessageStatus {
+    /// Statuses only move forward, e.g. a late `Sent` report never
+    /// overrides a `Seen` one; failures are only valid before delivery.
+    pub fn can_transition_to(self, next: MessageStatus) -> bool {
+        match (self, next) {
+            | (Self::Pending | Self::Sent, Self::Failed) => true,
+            | (Self::Failed, _) | (_, Self::Failed) => false,
+            | (current, next) => next.rank() > current.rank(),
+        }
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            | Self::Pending => 0,
+            | Self::Sent => 1,
+            | Self::Delivered => 2,
+            | Self::Seen => 3,
+            | Self::Failed => 4,
+        }
+    }
+}
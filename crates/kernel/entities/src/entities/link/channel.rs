@@ -11,7 +11,20 @@ use crate::{entities::auth::User, traits::*};
 #[derive(Clone, Copy, Debug, JsonSchema_repr, Deserialize, Serialize)]
 pub enum ChannelPlatform {
     Telegram = 0,
-    // WhatsApp = 1,
+    WhatsApp = 1,
+}
+
+/// How a channel reacts to messages sent in group chats; either ignoring
+/// them, handling only commands, mentions of the channel and replies to its
+/// messages, or handling all of them.
+#[EnumRepr(type = "i32")]
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, JsonSchema_repr, Deserialize, Serialize,
+)]
+pub enum ChannelGroupMode {
+    Disabled = 0,
+    Mentions = 1,
+    All = 2,
 }
 
 #[entity]
@@ -20,6 +33,11 @@ pub struct Channel {
     pub name: String,
     pub platform: ChannelPlatform,
     pub api_key: String,
+    pub phone_number_id: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub app_secret: Option<String>,
+    pub use_webhook: bool,
+    pub group_mode: ChannelGroupMode,
     pub valid_until: Option<DateTime<Utc>>,
     pub is_active: bool,
     pub max_instances: Option<i64>,
@@ -38,3 +56,22 @@ impl From<ChannelPlatform> for i32 {
         val.repr()
     }
 }
+
+impl Default for ChannelGroupMode {
+    fn default() -> Self {
+        Self::Mentions
+    }
+}
+
+impl From<i32> for ChannelGroupMode {
+    fn from(value: i32) -> Self {
+        Self::from_repr(value)
+            .unwrap_or_else(|| panic!("invalid channel group mode: {value}"))
+    }
+}
+
+impl From<ChannelGroupMode> for i32 {
+    fn from(val: ChannelGroupMode) -> Self {
+        val.repr()
+    }
+}
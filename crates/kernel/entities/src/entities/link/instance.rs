@@ -10,6 +10,9 @@ use crate::{entities::comm::Chat, traits::*};
 #[derive(Clone, Debug, From, Into, JsonSchema)]
 pub struct Instance {
     pub platform_identifier: i64,
+    /// Group chat of the platform the instance is a participant of, if any.
+    /// Instances of private chats have none.
+    pub platform_group_id: Option<i64>,
     pub username: Option<String>,
     pub display_name: Option<String>,
     pub phone_number: Option<String>,
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use kernel_entities::{
+    entities::{
+        comm::{Bot, Menu},
+        link::Instance,
+    },
+    traits::Key,
+};
+
+use crate::error::RepoResult;
+
+#[async_trait::async_trait]
+pub trait BotConversationsRepo: Send + Sync {
+    async fn get_of(
+        &self,
+        bot_id: &Key<Bot>,
+        instance_id: &Key<Instance>,
+    ) -> RepoResult<Option<BotConversation>>;
+
+    /// Creates or replaces the conversation of the instance with the bot,
+    /// unless the stored one was changed since it was last updated at
+    /// `loaded_at`, in which case `false` is returned.
+    async fn save(
+        &self,
+        conversation: &BotConversation,
+        loaded_at: &DateTime<Utc>,
+    ) -> RepoResult<bool>;
+
+    async fn remove_of(
+        &self,
+        bot_id: &Key<Bot>,
+        instance_id: &Key<Instance>,
+    ) -> RepoResult<()>;
+}
+
+/// Where an instance is in the menus of a bot, along with the data the bot
+/// collected from it so far.
+#[derive(Clone, Debug)]
+pub struct BotConversation {
+    pub bot_id: Key<Bot>,
+    pub instance_id: Key<Instance>,
+    pub menu_id: Key<Menu>,
+    pub data: HashMap<String, String>,
+    /// Number of messages in a row that matched no trigger
+    pub unmatched_count: i32,
+    pub updated_at: DateTime<Utc>,
+}
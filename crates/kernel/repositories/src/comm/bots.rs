@@ -1,7 +1,11 @@
 use derive_more::Constructor;
 use futures::stream::BoxStream;
 use kernel_entities::{
-    entities::{auth::User, comm::Bot},
+    entities::{
+        auth::User,
+        comm::{Bot, BotChannel},
+        link::Channel,
+    },
     traits::Key,
 };
 
@@ -17,6 +21,39 @@ pub trait BotsRepo:
     + Sync
 {
     fn stream_active(&self) -> BoxStream<'_, RepoResult<Bot>>;
+    fn stream_active_of(
+        &self,
+        user_id: Key<User>,
+    ) -> BoxStream<'_, RepoResult<Bot>>;
+
+    async fn get_channels_of(
+        &self,
+        bot_id: &Key<Bot>,
+    ) -> RepoResult<Vec<BotChannel>>;
+
+    /// Binds the bot to a channel, or updates the priority of an existing
+    /// binding.
+    async fn bind_channel(
+        &self,
+        bot_id: &Key<Bot>,
+        channel_id: &Key<Channel>,
+        priority: i32,
+    ) -> RepoResult<BotChannel>;
+
+    async fn unbind_channel(
+        &self,
+        bot_id: &Key<Bot>,
+        channel_id: &Key<Channel>,
+    ) -> RepoResult<()>;
+
+    async fn update(&self, id: &Key<Bot>, model: UpdateBot) -> RepoResult<()>;
+
+    /// Activates or deactivates all bots of a user.
+    async fn set_active_of(
+        &self,
+        user_id: &Key<User>,
+        is_active: bool,
+    ) -> RepoResult<()>;
 }
 
 #[derive(Constructor)]
@@ -24,4 +61,20 @@ pub struct InsertBot {
     pub name: String,
     pub is_active: bool,
     pub user_id: Key<User>,
+    pub fallback_reply: Option<String>,
+    pub back_trigger: Option<String>,
+    pub home_trigger: Option<String>,
+    pub help_trigger: Option<String>,
+    pub reprompt_after: Option<i32>,
+}
+
+#[derive(Constructor)]
+pub struct UpdateBot {
+    pub name: String,
+    pub is_active: bool,
+    pub fallback_reply: Option<String>,
+    pub back_trigger: Option<String>,
+    pub home_trigger: Option<String>,
+    pub help_trigger: Option<String>,
+    pub reprompt_after: Option<i32>,
 }
@@ -1,9 +1,10 @@
+use chrono::{DateTime, Utc};
 use derive_more::Constructor;
 use futures::stream::BoxStream;
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Chat, ChatState, Message},
+        comm::{Chat, ChatHandler, ChatState, Message},
     },
     traits::Key,
 };
@@ -22,12 +23,41 @@ pub trait ChatsRepo:
     async fn watch(
         &self,
         id: &Key<Chat>,
-    ) -> RepoResult<BoxStream<'_, RepoResult<Message>>>;
+    ) -> RepoResult<BoxStream<'_, RepoResult<MessageChange>>>;
 
     async fn watch_all_of(
         &self,
         user_id: &Key<User>,
-    ) -> RepoResult<BoxStream<'static, RepoResult<Message>>>;
+    ) -> RepoResult<BoxStream<'static, RepoResult<MessageChange>>>;
+
+    /// Sets the handler of a chat, refreshing the activity of the human
+    /// operator at `at` if handed off. Returns whether the handler changed.
+    async fn set_handler(
+        &self,
+        id: &Key<Chat>,
+        handler: ChatHandler,
+        at: DateTime<Utc>,
+    ) -> RepoResult<bool>;
+
+    /// Hands the chats whose human operator was last active at or before
+    /// `idle_since` back to bots, returning the number of such chats.
+    async fn resume_idle_handoffs(
+        &self,
+        idle_since: DateTime<Utc>,
+    ) -> RepoResult<u64>;
+
+    async fn watch_handlers_of(
+        &self,
+        user_id: &Key<User>,
+    ) -> RepoResult<BoxStream<'static, RepoResult<Chat>>>;
+}
+
+#[derive(Debug)]
+pub enum MessageChange {
+    Created(Message),
+    Edited(Message),
+    Deleted(Message),
+    StatusChanged(Message),
 }
 
 #[derive(Constructor)]
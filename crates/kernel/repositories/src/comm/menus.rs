@@ -1,6 +1,6 @@
 use derive_more::Constructor;
 use kernel_entities::{
-    entities::comm::{Bot, Menu, TriggerMatchingStrategy},
+    entities::comm::{Bot, Menu, MenuContentFormat, TriggerMatchingStrategy},
     traits::Key,
 };
 
@@ -29,4 +29,6 @@ pub struct InsertMenu {
     pub is_active: bool,
     pub parent_menu_id: Option<Key<Menu>>,
     pub bot_id: Key<Bot>,
+    pub hands_off: bool,
+    pub content_format: MenuContentFormat,
 }
@@ -3,8 +3,8 @@ use derive_more::Constructor;
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Chat, Message, MessageDirection},
-        link::Instance,
+        comm::{Attachment, Chat, Message, MessageDirection, MessageStatus},
+        link::{Channel, Instance},
     },
     traits::Key,
 };
@@ -19,19 +19,46 @@ pub trait MessagesRepo:
     + Send
     + Sync
 {
+    async fn get_by_platform_id(
+        &self,
+        instance_id: &Key<Instance>,
+        platform_message_id: &str,
+    ) -> RepoResult<Message>;
+
     async fn update_text(
         &self,
         id: &Key<Message>,
         new_text: Option<String>,
     ) -> RepoResult<Message>;
+
+    async fn mark_deleted(
+        &self,
+        id: &Key<Message>,
+        deleted_at: DateTime<Utc>,
+    ) -> RepoResult<Message>;
+
+    async fn update_status(
+        &self,
+        id: &Key<Message>,
+        status: MessageStatus,
+        platform_message_id: Option<String>,
+        timestamp: DateTime<Utc>,
+    ) -> RepoResult<Message>;
 }
 
 #[derive(Clone, Debug, Constructor)]
 pub struct InsertMessage {
     pub text: Option<String>,
+    pub attachments: Vec<Attachment>,
     pub direction: MessageDirection,
-    pub delivered_at: DateTime<Utc>,
+    pub status: MessageStatus,
+    pub delivered_at: Option<DateTime<Utc>>,
     pub user_id: Key<User>,
     pub chat_id: Key<Chat>,
     pub instance_id: Key<Instance>,
+    pub channel_id: Key<Channel>,
+    pub platform_message_id: Option<String>,
+    pub reply_to: Option<Key<Message>>,
+    pub callback_data: Option<String>,
+    pub idempotency_key: Option<String>,
 }
@@ -1,8 +1,10 @@
+mod bot_conversations;
 mod bots;
 mod chats;
 mod menus;
 mod messages;
 
+pub use bot_conversations::*;
 pub use bots::*;
 pub use chats::*;
 pub use menus::*;
@@ -11,4 +13,5 @@ pub use messages::*;
 pub trait CommDataStore: Send + Sync {
     fn bots(&self) -> &dyn BotsRepo;
     fn menus(&self) -> &dyn MenusRepo;
+    fn bot_conversations(&self) -> &dyn BotConversationsRepo;
 }
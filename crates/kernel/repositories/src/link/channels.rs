@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
 use derive_more::Constructor;
 use futures::stream::BoxStream;
@@ -28,6 +30,57 @@ pub trait ChannelsRepo:
         id: &Key<Channel>,
         model: UpdateChannel,
     ) -> RepoResult<()>;
+
+    async fn deactivate(&self, id: &Key<Channel>) -> RepoResult<()>;
+
+    /// Marks the channel as stopped, or clears the mark. Stopped channels
+    /// are not streamed as active ones, so that they are not started again
+    /// until they are explicitly started.
+    async fn set_stopped(
+        &self,
+        id: &Key<Channel>,
+        stopped: bool,
+    ) -> RepoResult<()>;
+
+    async fn is_stopped(&self, id: &Key<Channel>) -> RepoResult<bool>;
+
+    async fn get_update_offset(
+        &self,
+        id: &Key<Channel>,
+    ) -> RepoResult<Option<i64>>;
+
+    async fn set_update_offset(
+        &self,
+        id: &Key<Channel>,
+        offset: i64,
+    ) -> RepoResult<()>;
+
+    /// Acquires the lease of the channel for `node_id`, or renews it if the
+    /// node already holds it. Returns `false` when another node holds an
+    /// unexpired lease of the channel.
+    async fn acquire_lease(
+        &self,
+        id: &Key<Channel>,
+        node_id: &str,
+        ttl: Duration,
+    ) -> RepoResult<bool>;
+
+    async fn release_lease(
+        &self,
+        id: &Key<Channel>,
+        node_id: &str,
+    ) -> RepoResult<()>;
+
+    async fn get_lease(
+        &self,
+        id: &Key<Channel>,
+    ) -> RepoResult<Option<ChannelLease>>;
+
+    /// Re-encrypts the api keys, webhook secrets and app secrets of channels
+    /// that are not encrypted with the current master key, returning the
+    /// number of re-encrypted channels. Secrets that cannot be decrypted are
+    /// skipped.
+    async fn reencrypt_secrets(&self) -> RepoResult<usize>;
 }
 
 #[derive(Constructor)]
@@ -36,14 +89,31 @@ pub struct InsertChannel {
     pub name: String,
     pub platform: ChannelPlatform,
     pub api_key: String,
+    pub phone_number_id: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub app_secret: Option<String>,
+    pub use_webhook: bool,
+    pub group_mode: ChannelGroupMode,
     pub valid_until: Option<DateTime<Utc>>,
     pub is_active: bool,
 }
 
+#[derive(Debug)]
+pub struct ChannelLease {
+    pub node_id: String,
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
 #[derive(Constructor)]
 pub struct UpdateChannel {
     pub name: String,
     pub api_key: String,
+    pub phone_number_id: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub app_secret: Option<String>,
+    pub use_webhook: bool,
+    pub group_mode: ChannelGroupMode,
     pub valid_until: Option<DateTime<Utc>>,
     pub is_active: bool,
 }
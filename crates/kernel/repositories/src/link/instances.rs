@@ -16,12 +16,45 @@ pub trait InstancesRepo:
         chat_id: &Key<Chat>,
     ) -> RepoResult<Vec<Instance>>;
 
+    /// Gets the instance of a platform user, either in its private chat or
+    /// as a participant of the group `group_id`.
     async fn get_by_platform_identifier(
         &self,
         channel_id: &Key<Channel>,
+        group_id: Option<i64>,
         identifier: i64,
     ) -> RepoResult<Instance>;
 
+    /// Gets the chat shared by the participants of a platform group.
+    async fn get_group_chat(
+        &self,
+        channel_id: &Key<Channel>,
+        group_id: i64,
+    ) -> RepoResult<Key<Chat>>;
+
+    /// Sets the chat shared by the participants of a platform group, unless
+    /// it was already set, returning the chat the group ends up with.
+    async fn set_group_chat(
+        &self,
+        channel_id: &Key<Channel>,
+        group_id: i64,
+        chat_id: &Key<Chat>,
+    ) -> RepoResult<Key<Chat>>;
+
+    async fn get_count_of(
+        &self,
+        channel_id: &Key<Channel>,
+    ) -> RepoResult<usize>;
+
+    /// Creates an instance, unless its channel already has `max_instances`
+    /// instances, in which case `None` is returned. Concurrent creations on
+    /// the same channel are serialized, so that they cannot exceed the quota.
+    async fn create_within_quota(
+        &self,
+        model: InsertInstance,
+        max_instances: i64,
+    ) -> RepoResult<Option<Instance>>;
+
     async fn get_of_user(
         &self,
         user_id: &Key<User>,
@@ -40,14 +73,24 @@ pub trait InstancesRepo:
         id: &Key<Instance>,
         model: UpdateInstance,
     ) -> RepoResult<()>;
+
+    /// Refreshes the profile of an instance with the one reported by its
+    /// platform, keeping the current values of missing fields.
+    async fn refresh(
+        &self,
+        id: &Key<Instance>,
+        model: RefreshInstance,
+    ) -> RepoResult<()>;
 }
 
 #[derive(Constructor)]
 pub struct InsertInstance {
     pub platform_identifier: i64,
+    pub platform_group_id: Option<i64>,
     pub username: Option<String>,
     pub display_name: Option<String>,
     pub phone_number: Option<String>,
+    pub last_active: Option<DateTime<Utc>>,
     pub chat_id: Key<Chat>,
     pub channel_id: Key<Channel>,
 }
@@ -57,3 +100,11 @@ pub struct UpdateInstance {
     pub display_name: Option<String>,
     pub phone_number: Option<String>,
 }
+
+#[derive(Constructor)]
+pub struct RefreshInstance {
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub phone_number: Option<String>,
+    pub last_active: DateTime<Utc>,
+}
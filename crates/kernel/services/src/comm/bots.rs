@@ -1,2 +1,61 @@
+use chrono::{DateTime, Utc};
+use kernel_entities::{
+    entities::{
+        auth::User,
+        comm::{Bot, Menu},
+    },
+    traits::Key,
+};
+use serde::Serialize;
+
+use crate::error::AppResult;
+
 #[async_trait::async_trait]
-pub trait BotsService: Send + Sync {}
+pub trait BotsService: Send + Sync {
+    async fn status(&self, id: &Key<Bot>) -> AppResult<Option<BotStatus>>;
+
+    async fn status_of(
+        &self,
+        user_id: &Key<User>,
+    ) -> AppResult<Vec<(Key<Bot>, BotStatus)>>;
+
+    /// Activates and starts all bots of a user.
+    async fn start_bots_of(&self, user_id: &Key<User>) -> AppResult<()>;
+    /// Stops and deactivates all bots of a user, so that they are not started
+    /// again on reloads and restarts.
+    async fn stop_bots_of(&self, user_id: &Key<User>) -> AppResult<()>;
+    async fn reload_bots_of(&self, user_id: &Key<User>) -> AppResult<()>;
+
+    /// Activates and starts a bot.
+    async fn start_bot(
+        &self,
+        user_id: &Key<User>,
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()>;
+
+    /// Stops and deactivates a bot, so that it is not started again on reloads
+    /// and restarts.
+    async fn stop_bot(
+        &self,
+        user_id: &Key<User>,
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()>;
+
+    /// Reloads a bot from the data store, so that changes to it or its menus
+    /// take effect. Bots that were removed, deactivated or left without an
+    /// entry menu are stopped.
+    async fn reload_bot(
+        &self,
+        user_id: &Key<User>,
+        bot_id: &Key<Bot>,
+    ) -> AppResult<()>;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BotStatus {
+    pub loaded_at: DateTime<Utc>,
+    pub entry_menu_id: Key<Menu>,
+    // whether the messages of the bot owner are being watched
+    pub is_running: bool,
+}
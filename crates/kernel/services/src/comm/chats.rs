@@ -3,25 +3,82 @@ use futures::stream::BoxStream;
 use kernel_entities::{
     entities::{
         auth::User,
-        comm::{Chat, Message, MessageDirection}, link::Instance,
+        comm::{
+            Attachment,
+            Chat,
+            ChatHandler,
+            Message,
+            MessageDirection,
+            MessageStatus,
+        },
+        link::{Channel, Instance},
     },
     traits::Key,
 };
 
-use crate::error::AppResult;
+use crate::{
+    error::AppResult,
+    link::{channels::MessageKeyboard, rich_text::TextFormat},
+};
 
 #[async_trait::async_trait]
 pub trait ChatsService: Send + Sync {
+    /// Sends a message on behalf of a human operator, which hands the chat
+    /// off from bots until the operator is inactive for a while.
     async fn send_message(
         &self,
         chat_id: &Key<Chat>,
+        text: Option<String>,
+        format: TextFormat,
+        attachments: Vec<Attachment>,
+        reply_to: Option<Key<Message>>,
+        keyboard: Option<MessageKeyboard>,
+    ) -> AppResult<()>;
+
+    /// Sends a message on behalf of a bot, without handing the chat off.
+    async fn send_bot_message(
+        &self,
+        chat_id: &Key<Chat>,
+        text: String,
+        format: TextFormat,
+        keyboard: Option<MessageKeyboard>,
+    ) -> AppResult<()>;
+
+    async fn edit_message(
+        &self,
+        chat_id: &Key<Chat>,
+        message_id: &Key<Message>,
         text: String,
+        format: TextFormat,
+    ) -> AppResult<()>;
+
+    async fn delete_message(
+        &self,
+        chat_id: &Key<Chat>,
+        message_id: &Key<Message>,
     ) -> AppResult<()>;
 
     async fn watch_user_chats(
         &self,
         user_id: &Key<User>,
     ) -> AppResult<BoxStream<'static, AppResult<ChatEvent>>>;
+
+    /// Gets who is handling a chat, handing it back to bots if the human
+    /// operator has been inactive for too long.
+    async fn get_handoff(&self, chat_id: &Key<Chat>) -> AppResult<ChatHandoff>;
+
+    async fn set_handler(
+        &self,
+        chat_id: &Key<Chat>,
+        handler: ChatHandler,
+    ) -> AppResult<ChatHandoff>;
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatHandoff {
+    pub handler: ChatHandler,
+    /// When bots resume answering, if handed off to a human operator
+    pub resumes_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug)]
@@ -29,10 +86,37 @@ pub enum ChatEventKind {
     MessageAdded {
         id: Key<Message>,
         text: Option<String>,
+        attachments: Vec<Attachment>,
+        reply_to: Option<Key<Message>>,
+        /// Data of the pressed button, if the message is a button press
+        callback_data: Option<String>,
         instance_id: Key<Instance>,
+        /// Channel of the instance, which is missing on older messages
+        channel_id: Option<Key<Channel>>,
         direction: MessageDirection,
         created_at: DateTime<Utc>,
     },
+    MessageEdited {
+        id: Key<Message>,
+        text: Option<String>,
+        instance_id: Key<Instance>,
+        updated_at: DateTime<Utc>,
+    },
+    MessageDeleted {
+        id: Key<Message>,
+        instance_id: Key<Instance>,
+        deleted_at: DateTime<Utc>,
+    },
+    MessageStatusChanged {
+        id: Key<Message>,
+        instance_id: Key<Instance>,
+        status: MessageStatus,
+        updated_at: DateTime<Utc>,
+    },
+    HandlerChanged {
+        handler: ChatHandler,
+        updated_at: DateTime<Utc>,
+    },
 }
 
 #[derive(Debug)]
@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CommError {
+    #[error("invalid bot state: {0}")]
+    InvalidBotState(String),
+
+    #[error("unknown message: {0}")]
+    UnknownMessage(String),
+}
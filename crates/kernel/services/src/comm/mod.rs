@@ -1,2 +1,3 @@
 pub mod bots;
 pub mod chats;
+pub mod error;
@@ -2,7 +2,11 @@ use std::collections::HashMap;
 
 use serde::de::DeserializeOwned;
 
-use crate::{error::AppResult, Service};
+use self::error::ConfigError;
+use crate::{
+    error::{AppError, AppResult},
+    Service,
+};
 
 #[async_trait::async_trait]
 pub trait ConfigService: Service + Send + Sync {
@@ -12,6 +16,21 @@ pub trait ConfigService: Service + Send + Sync {
         &self,
         section: &str,
     ) -> AppResult<T>;
+
+    /// Reads a section of the configuration, falling back to its defaults
+    /// when the section is missing.
+    fn get_section_or_default<T: DeserializeOwned + Default>(
+        &self,
+        section: &str,
+    ) -> AppResult<T> {
+        match self.get_section(section) {
+            | Err(AppError::Config(ConfigError::NotFound(_))) => {
+                Ok(T::default())
+            }
+            | ret => ret,
+        }
+    }
+
     fn get(&self, key: &str) -> AppResult<ConfigValue>;
     fn get_bool(&self, key: &str) -> AppResult<bool>;
     fn get_int(&self, key: &str) -> AppResult<i64>;
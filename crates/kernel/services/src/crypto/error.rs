@@ -34,6 +34,12 @@ pub enum CryptoError {
     #[error("hash verification failure: {0}")]
     Verification(String),
 
+    #[error("cipher error: {0}")]
+    Cipher(String),
+
+    #[error("unknown key: {0}")]
+    UnknownKey(String),
+
     #[error("unsupported")]
     Unsupported,
 }
@@ -0,0 +1,16 @@
+use crate::error::AppResult;
+
+/// Encrypts credentials that have to be stored at rest, such as the api keys
+/// of channels, with a master key that can be rotated.
+pub trait SecretsService: Send + Sync {
+    /// Encrypts `plain` with the current master key.
+    fn encrypt(&self, plain: &str) -> AppResult<String>;
+
+    /// Decrypts a secret that was encrypted with any of the known master
+    /// keys. Values that were stored before encryption are returned as-is.
+    fn decrypt(&self, sealed: &str) -> AppResult<String>;
+
+    /// Whether `sealed` is not encrypted with the current master key, and
+    /// should therefore be re-encrypted.
+    fn is_stale(&self, sealed: &str) -> bool;
+}
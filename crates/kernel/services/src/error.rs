@@ -3,8 +3,10 @@ use thiserror::Error;
 pub use crate::auth::error::AuthError;
 pub use crate::config::error::ConfigError;
 pub use crate::crypto::error::CryptoError;
+use crate::comm::error::CommError;
 use crate::link::error::LinkError;
 use crate::setup::error::SetupError;
+use crate::storage::error::StorageError;
 
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -25,9 +27,32 @@ pub enum AppError {
     #[error("auth error: {0}")]
     Auth(#[from] AuthError),
 
+    #[error("comm error: {0}")]
+    Comm(#[from] CommError),
+
     #[error("link error: {0}")]
     Link(#[from] LinkError),
 
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+
     #[error("repo error: {0}")]
     Repo(#[from] kernel_repositories::error::RepoError),
 }
+
+impl AppError {
+    /// Whether the error is likely to go away on its own, such that the
+    /// failed operation is worth retrying later.
+    pub fn is_transient(&self) -> bool {
+        use kernel_repositories::error::RepoError;
+
+        matches!(
+            self,
+            AppError::Repo(RepoError::Io(_) | RepoError::Data(_))
+                | AppError::Link(
+                    LinkError::Communication(_) | LinkError::MessagePassing(_)
+                )
+                | AppError::Comm(CommError::UnknownMessage(_))
+        )
+    }
+}
@@ -10,6 +10,7 @@ pub mod entropy;
 pub mod error;
 pub mod link;
 pub mod setup;
+pub mod storage;
 
 #[async_trait::async_trait]
 pub trait Service {
@@ -1,14 +1,22 @@
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
+use derive_more::Display;
 use futures::stream::BoxStream;
 use kernel_entities::{
-    entities::{auth::User, link::Channel},
+    entities::{
+        auth::User,
+        comm::{Attachment, Message, MessageStatus},
+        link::Channel,
+    },
     traits::Key,
 };
 use serde::{Deserialize, Serialize};
 
-use super::message_passing::{ScopedTopicReader, ScopedTopicWriter};
+use super::{
+    message_passing::{ScopedTopicReader, ScopedTopicWriter},
+    rich_text::TextFormat,
+};
 use crate::error::AppResult;
 
 #[async_trait::async_trait]
@@ -36,18 +44,27 @@ pub trait ChannelsService: Send + Sync {
         user_id: Key<User>,
     ) -> BoxStream<'_, AppResult<()>>;
 
+    /// Starts the channel, clearing an earlier stop of it.
     async fn start_channel(
         &self,
         user_id: &Key<User>,
         channel_id: &Key<Channel>,
     ) -> AppResult<()>;
 
+    /// Stops the channel wherever it runs, and keeps it stopped, even across
+    /// restarts, until it is started again.
     async fn stop_channel(
         &self,
         user_id: &Key<User>,
         channel_id: &Key<Channel>,
     ) -> AppResult<()>;
 
+    async fn restart_channel(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+    ) -> AppResult<()>;
+
     async fn get_pipe_of(
         &self,
         user_id: &Key<User>,
@@ -55,6 +72,22 @@ pub trait ChannelsService: Send + Sync {
     ) -> AppResult<ChannelPipe>;
 
     async fn get_pipe_of_all(&self) -> AppResult<ChannelPipe>;
+
+    async fn get_reverse_pipe_of(
+        &self,
+        user_id: &Key<User>,
+        channel_id: &Key<Channel>,
+    ) -> AppResult<ReverseChannelPipe>;
+
+    /// Verifies and publishes a webhook payload of a channel, where `secret`
+    /// is either the secret token sent by Telegram, or the payload signature
+    /// sent by WhatsApp.
+    async fn push_webhook_update(
+        &self,
+        channel_id: &Key<Channel>,
+        secret: Option<&str>,
+        payload: &[u8],
+    ) -> AppResult<()>;
 }
 
 #[derive(Clone)]
@@ -69,46 +102,261 @@ pub struct ReverseChannelPipe {
     pub rx: Arc<dyn ScopedTopicReader<OutgoingChannelUpdate>>,
 }
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChannelStatus {
     pub started_at: DateTime<Utc>,
+    pub node_id: String,
+    // health of the channel, which is only known to the node running it, and
+    // is `None` when the channel runs on another node
+    pub is_degraded: Option<bool>,
+    pub restarts: Option<u32>,
+    pub last_error: Option<String>,
+    pub last_activity: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum OutgoingMessageUpdateKind {
-    New { content: String },
+    New {
+        message_id: Key<Message>,
+        content: Option<String>,
+        #[serde(default)]
+        attachments: Vec<Attachment>,
+        reply_to: Option<String>,
+        #[serde(default)]
+        keyboard: Option<MessageKeyboard>,
+        #[serde(default)]
+        format: TextFormat,
+    },
+    Edit {
+        platform_message_id: String,
+        content: String,
+        #[serde(default)]
+        format: TextFormat,
+    },
+    Delete {
+        platform_message_id: String,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum IncomingMessageUpdateKind {
-    New { content: Option<String> },
+    New {
+        platform_message_id: String,
+        content: Option<String>,
+        #[serde(default)]
+        attachments: Vec<Attachment>,
+        reply_to: Option<String>,
+    },
+    Edited {
+        platform_message_id: String,
+        content: Option<String>,
+    },
+    Deleted {
+        platform_message_id: String,
+    },
+    /// A button of an inline keyboard was pressed.
+    ButtonPressed {
+        /// Platform id of the press, which is unique per press
+        callback_id: String,
+        /// Message the pressed button is attached to
+        platform_message_id: Option<String>,
+        /// Label of the pressed button, if known
+        text: Option<String>,
+        data: String,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Buttons to be shown along with an outgoing message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum MessageKeyboard {
+    /// Rows of buttons that replace the keyboard of the user, and send their
+    /// labels as plain messages when pressed
+    Reply(Vec<Vec<String>>),
+    /// Rows of buttons attached to the message, which report their data
+    /// when pressed
+    Inline(Vec<Vec<KeyboardButton>>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyboardButton {
+    pub text: String,
+    pub data: String,
+}
+
+impl MessageKeyboard {
+    /// Labels of the buttons, row by row.
+    pub fn labels(&self) -> Vec<Vec<&str>> {
+        match self {
+            | Self::Reply(rows) => rows
+                .iter()
+                .map(|row| row.iter().map(String::as_str).collect())
+                .collect(),
+            | Self::Inline(rows) => rows
+                .iter()
+                .map(|row| row.iter().map(|b| b.text.as_str()).collect())
+                .collect(),
+        }
+    }
+}
+
+// see `IncomingChannelUpdateKind` on adding fields
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum OutgoingChannelUpdateKind {
     Message {
         platform_user_id: i64,
         kind: OutgoingMessageUpdateKind,
         timestamp: DateTime<Utc>,
+        /// Group chat to send to, instead of the private chat of the user
+        #[serde(default)]
+        group_id: Option<i64>,
     },
 }
 
+/// A group chat of the platform, shared by multiple users.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlatformGroup {
+    pub id: i64,
+    pub title: Option<String>,
+}
+
+/// Information about the sender of an update, as reported by the platform.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SenderProfile {
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub phone_number: Option<String>,
+}
+
+// updates are encoded as arrays when passed between nodes, so new fields of
+// their variants must be appended with a default to keep decoding the updates
+// of nodes running older versions
 #[derive(Debug, Serialize, Deserialize)]
 pub enum IncomingChannelUpdateKind {
     Message {
         platform_user_id: i64,
         kind: IncomingMessageUpdateKind,
         timestamp: DateTime<Utc>,
+        #[serde(default)]
+        profile: SenderProfile,
+        /// Group chat the message was sent in, if not a private chat
+        #[serde(default)]
+        group: Option<PlatformGroup>,
+    },
+    MessageStatus {
+        platform_user_id: i64,
+        message_id: Option<Key<Message>>,
+        platform_message_id: Option<String>,
+        status: MessageStatus,
+        timestamp: DateTime<Utc>,
+    },
+    Lifecycle {
+        event: ChannelLifecycleEvent,
+        timestamp: DateTime<Utc>,
     },
 }
 
+/// Changes in the state of a channel that are not caused by the platform.
+#[derive(Clone, Copy, Debug, Display, Serialize, Deserialize)]
+pub enum ChannelLifecycleEvent {
+    /// The channel was stopped because its `valid_until` has passed.
+    Expired,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChannelUpdate<Kind> {
     pub user_id: Key<User>,
     pub channel_id: Key<Channel>,
     pub kind: Kind,
+    // kept last, as updates are encoded positionally
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
 
 pub type OutgoingChannelUpdate = ChannelUpdate<OutgoingChannelUpdateKind>;
 pub type IncomingChannelUpdate = ChannelUpdate<IncomingChannelUpdateKind>;
+
+impl IncomingChannelUpdate {
+    pub fn new(
+        user_id: Key<User>,
+        channel_id: Key<Channel>,
+        kind: IncomingChannelUpdateKind,
+    ) -> Self {
+        let idempotency_key = kind.idempotency_key(&channel_id);
+
+        Self {
+            user_id,
+            channel_id,
+            kind,
+            idempotency_key: Some(idempotency_key),
+        }
+    }
+}
+
+impl IncomingChannelUpdateKind {
+    /// A key identifying the platform event behind this update, which stays
+    /// the same when the platform redelivers the event.
+    pub fn idempotency_key(&self, channel_id: &Key<Channel>) -> String {
+        let (platform_user_id, event) = match self {
+            | IncomingChannelUpdateKind::Message {
+                platform_user_id,
+                group,
+                kind,
+                timestamp,
+                ..
+            } => {
+                let event = match kind {
+                    | IncomingMessageUpdateKind::New {
+                        platform_message_id,
+                        ..
+                    } => format!("new:{platform_message_id}"),
+                    | IncomingMessageUpdateKind::Edited {
+                        platform_message_id,
+                        ..
+                    } => format!(
+                        "edited:{platform_message_id}:{}",
+                        timestamp.timestamp_millis()
+                    ),
+                    | IncomingMessageUpdateKind::Deleted {
+                        platform_message_id,
+                    } => format!("deleted:{platform_message_id}"),
+                    | IncomingMessageUpdateKind::ButtonPressed {
+                        callback_id,
+                        ..
+                    } => format!("pressed:{callback_id}"),
+                };
+
+                // message ids are only unique within their chat
+                let event = match group {
+                    | Some(group) => format!("group:{}:{event}", group.id),
+                    | None => event,
+                };
+
+                (platform_user_id, event)
+            }
+            | IncomingChannelUpdateKind::MessageStatus {
+                platform_user_id,
+                message_id,
+                platform_message_id,
+                status,
+                ..
+            } => {
+                let target = message_id
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .or_else(|| platform_message_id.clone())
+                    .unwrap_or_default();
+
+                (platform_user_id, format!("status:{target}:{status}"))
+            }
+            | IncomingChannelUpdateKind::Lifecycle { event, timestamp } => {
+                return format!(
+                    "{channel_id}:lifecycle:{event}:{}",
+                    timestamp.timestamp_millis()
+                );
+            }
+        };
+
+        format!("{channel_id}:{platform_user_id}:{event}")
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -19,4 +21,7 @@ pub enum LinkError {
 
     #[error("ipc error: {0}")]
     MessagePassing(anyhow::Error),
+
+    #[error("rate limited, retry after {}ms", .0.as_millis())]
+    RateLimited(Duration),
 }
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use futures::stream::BoxStream;
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -26,7 +27,16 @@ pub trait MessagePassingService: Send + Sync {
 #[async_trait]
 pub trait MessageConfirmation: Send + Sync + 'static {
     async fn ack(&self) -> AppResult<()>;
+
+    /// Rejects the message. When `requeue` is set, the message is redelivered
+    /// later according to the topic's retry policy, until its attempts are
+    /// exhausted. Otherwise, or once exhausted, it is moved to the topic's
+    /// dead-letter exchange.
     async fn nack(&self, requeue: bool) -> AppResult<()>;
+
+    /// Whether the attempts of the message are exhausted, so that rejecting
+    /// it moves it to the dead-letter exchange even when requeued.
+    fn is_last_attempt(&self) -> bool;
 }
 
 #[async_trait]
@@ -34,6 +44,10 @@ pub trait TopicWriter<T>: Send + Sync {
     async fn publish(&self, key: &str, body: &T) -> AppResult<()>;
     async fn publish_confirmed(&self, key: &str, body: &T) -> AppResult<()>;
 
+    /// Publishes a message to the current mirrors of the key only, such as
+    /// to notify all nodes, without keeping it for later subscribers.
+    async fn broadcast(&self, key: &str, body: &T) -> AppResult<()>;
+
     fn scoped(&self, key: &str) -> Arc<dyn ScopedTopicWriter<T>>;
 }
 
@@ -58,6 +72,20 @@ pub trait TopicReader<T>: Send + Sync {
     async fn mirror(&self, key: &str)
         -> AppResult<BoxStream<'_, AppResult<T>>>;
 
+    async fn dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<Vec<DeadLetter<T>>>;
+
+    async fn replay_dead_letters(
+        &self,
+        key: &str,
+        limit: usize,
+    ) -> AppResult<usize>;
+
+    async fn purge_dead_letters(&self, key: &str) -> AppResult<usize>;
+
     fn scoped(&self, key: &str) -> Arc<dyn ScopedTopicReader<T>>;
 }
 
@@ -70,6 +98,20 @@ pub trait ScopedTopicReader<T>: Send + Sync {
     ) -> AppResult<BoxStream<'_, AppResult<(T, Arc<dyn MessageConfirmation>)>>>;
 
     async fn mirror(&self) -> AppResult<BoxStream<'_, AppResult<T>>>;
+
+    async fn dead_letters(&self, limit: usize)
+        -> AppResult<Vec<DeadLetter<T>>>;
+
+    async fn replay_dead_letters(&self, limit: usize) -> AppResult<usize>;
+
+    async fn purge_dead_letters(&self) -> AppResult<usize>;
+}
+
+#[derive(Debug)]
+pub struct DeadLetter<T> {
+    pub body: T,
+    pub attempts: u32,
+    pub dead_lettered_at: Option<DateTime<Utc>>,
 }
 
 #[async_trait]
@@ -2,3 +2,4 @@ pub mod channels;
 pub mod error;
 pub mod instances;
 pub mod message_passing;
+pub mod rich_text;
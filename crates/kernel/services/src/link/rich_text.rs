@@ -0,0 +1,628 @@
+use serde::{Deserialize, Serialize};
+
+/// How the text content of an outgoing message is interpreted.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum TextFormat {
+    /// The text is sent as-is
+    #[default]
+    Plain,
+    /// The text is a subset of markdown: `**bold**`, `_italic_`,
+    /// `~~strikethrough~~`, `` `code` ``, fenced code blocks and
+    /// `[links](https://example.com)`, where `\` escapes the next character
+    Markdown,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub strikethrough: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RichSpan {
+    Text {
+        text: String,
+        style: TextStyle,
+    },
+    Link {
+        text: String,
+        url: String,
+        style: TextStyle,
+    },
+    Code(String),
+    Pre {
+        language: Option<String>,
+        code: String,
+    },
+}
+
+/// A platform-neutral formatted text, which channels render in the markup
+/// of their platforms.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RichText {
+    pub spans: Vec<RichSpan>,
+}
+
+impl RichText {
+    pub fn plain(text: impl Into<String>) -> Self {
+        let text = text.into();
+
+        if text.is_empty() {
+            return Self::default();
+        }
+
+        Self {
+            spans: vec![RichSpan::Text {
+                text,
+                style: TextStyle::default(),
+            }],
+        }
+    }
+
+    pub fn parse(text: &str, format: TextFormat) -> Self {
+        match format {
+            | TextFormat::Plain => Self::plain(text),
+            | TextFormat::Markdown => Self::parse_markdown(text),
+        }
+    }
+
+    pub fn parse_markdown(text: &str) -> Self {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut parsed = Self::default();
+        let mut style = TextStyle::default();
+        let mut idx = 0;
+
+        while idx < chars.len() {
+            let rest = &chars[idx..];
+
+            match rest {
+                | ['\\', c, ..] if c.is_ascii_punctuation() => {
+                    parsed.push_char(*c, style);
+                    idx += 2;
+                }
+                | ['`', '`', '`', ..] => {
+                    let Some(end) = find(&chars, idx + 3, "```") else {
+                        parsed.push_str("```", style);
+                        idx += 3;
+                        continue;
+                    };
+
+                    let block = chars[idx + 3..end].iter().collect::<String>();
+
+                    // the language is given on the opening line, if any
+                    let (language, code) = match block.split_once('\n') {
+                        | Some((lang, code))
+                            if !lang.trim().contains(char::is_whitespace) =>
+                        {
+                            let lang = lang.trim();
+
+                            ((!lang.is_empty()).then(|| lang.to_owned()), code)
+                        }
+                        | _ => (None, block.as_str()),
+                    };
+
+                    parsed.spans.push(RichSpan::Pre {
+                        language,
+                        code: code.trim_end_matches('\n').to_owned(),
+                    });
+
+                    idx = end + 3;
+                }
+                | ['`', ..] => {
+                    let Some(end) = find(&chars, idx + 1, "`") else {
+                        parsed.push_char('`', style);
+                        idx += 1;
+                        continue;
+                    };
+
+                    let code = chars[idx + 1..end].iter().collect();
+
+                    parsed.spans.push(RichSpan::Code(code));
+                    idx = end + 1;
+                }
+                | ['*', '*', ..] => {
+                    if style.bold || find(&chars, idx + 2, "**").is_some() {
+                        style.bold = !style.bold;
+                    } else {
+                        parsed.push_str("**", style);
+                    }
+
+                    idx += 2;
+                }
+                | ['~', '~', ..] => {
+                    if style.strikethrough
+                        || find(&chars, idx + 2, "~~").is_some()
+                    {
+                        style.strikethrough = !style.strikethrough;
+                    } else {
+                        parsed.push_str("~~", style);
+                    }
+
+                    idx += 2;
+                }
+                // underscores within words, e.g. in identifiers, are kept
+                | ['_', ..] if is_italic_marker(&chars, idx, style.italic) => {
+                    style.italic = !style.italic;
+                    idx += 1;
+                }
+                | ['[', ..] => match parse_link(&chars, idx) {
+                    | Some((text, url, end)) => {
+                        parsed.spans.push(RichSpan::Link { text, url, style });
+                        idx = end;
+                    }
+                    | None => {
+                        parsed.push_char('[', style);
+                        idx += 1;
+                    }
+                },
+                | [c, ..] => {
+                    parsed.push_char(*c, style);
+                    idx += 1;
+                }
+                | [] => break,
+            }
+        }
+
+        parsed
+    }
+
+    /// Length of the visible text, in characters.
+    pub fn len(&self) -> usize {
+        self.spans.iter().map(RichSpan::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Renders the text without any formatting, as a fallback for platforms
+    /// that do not support it.
+    pub fn to_plain_text(&self) -> String {
+        self.spans
+            .iter()
+            .map(|span| match span {
+                | RichSpan::Link { text, url, .. } if text != url => {
+                    format!("{text} ({url})")
+                }
+                | span => span.text().to_owned(),
+            })
+            .collect()
+    }
+
+    /// Splits the text into chunks of at most `max_len` visible characters,
+    /// preferably at line breaks, then at spaces.
+    pub fn split(self, max_len: usize) -> Vec<RichText> {
+        let mut chunks = Vec::new();
+        let mut current = RichText::default();
+        let mut current_len = 0;
+
+        for mut span in self.spans {
+            loop {
+                let len = span.len();
+                let room = max_len - current_len;
+
+                if len <= room {
+                    current_len += len;
+                    current.spans.push(span);
+                    break;
+                }
+
+                // spans that fit in a chunk of their own are not broken
+                let at = match break_point(span.text(), room) {
+                    | Some(at) if len > max_len => at,
+                    | _ if current_len > 0 => {
+                        chunks.push(std::mem::take(&mut current));
+                        current_len = 0;
+                        continue;
+                    }
+                    | _ => room,
+                };
+
+                let (head, tail) = span.split_at(at);
+
+                current.spans.push(head);
+                chunks.push(std::mem::take(&mut current));
+                current_len = 0;
+                span = tail;
+            }
+        }
+
+        if !current.spans.is_empty() {
+            chunks.push(current);
+        }
+
+        chunks
+    }
+
+    fn push_char(&mut self, c: char, style: TextStyle) {
+        self.push_str(c.encode_utf8(&mut [0; 4]), style);
+    }
+
+    fn push_str(&mut self, value: &str, style: TextStyle) {
+        if let Some(RichSpan::Text {
+            text,
+            style: last_style,
+        }) = self.spans.last_mut()
+        {
+            if *last_style == style {
+                text.push_str(value);
+                return;
+            }
+        }
+
+        self.spans.push(RichSpan::Text {
+            text: value.to_owned(),
+            style,
+        });
+    }
+}
+
+impl RichSpan {
+    pub fn text(&self) -> &str {
+        match self {
+            | RichSpan::Text { text, .. } | RichSpan::Link { text, .. } => text,
+            | RichSpan::Code(code) | RichSpan::Pre { code, .. } => code,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.text().chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text().is_empty()
+    }
+
+    /// Splits the span after `at` characters; links are split as texts.
+    fn split_at(self, at: usize) -> (RichSpan, RichSpan) {
+        let split = |text: String| {
+            let idx = text
+                .char_indices()
+                .nth(at)
+                .map_or(text.len(), |(idx, _)| idx);
+
+            (text[..idx].to_owned(), text[idx..].to_owned())
+        };
+
+        match self {
+            | RichSpan::Text { text, style }
+            | RichSpan::Link { text, style, .. } => {
+                let (head, tail) = split(text);
+
+                (
+                    RichSpan::Text { text: head, style },
+                    RichSpan::Text { text: tail, style },
+                )
+            }
+            | RichSpan::Code(code) => {
+                let (head, tail) = split(code);
+
+                (RichSpan::Code(head), RichSpan::Code(tail))
+            }
+            | RichSpan::Pre { language, code } => {
+                let (head, tail) = split(code);
+
+                (
+                    RichSpan::Pre {
+                        language: language.clone(),
+                        code: head,
+                    },
+                    RichSpan::Pre {
+                        language,
+                        code: tail,
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// Escapes the markers of a text, so that it is shown as-is when parsed as
+/// markdown.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '~' | '`' | '[') {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Finds the last line break, or else space, within the first `room`
+/// characters of `text`, returning the number of characters before the
+/// break point.
+fn break_point(text: &str, room: usize) -> Option<usize> {
+    let head = text.chars().take(room).collect::<Vec<_>>();
+
+    ['\n', ' '].into_iter().find_map(|sep| {
+        head.iter()
+            .rposition(|c| *c == sep)
+            .map(|idx| idx + 1)
+            .filter(|at| *at > 0)
+    })
+}
+
+fn find(chars: &[char], from: usize, pattern: &str) -> Option<usize> {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+
+    chars
+        .get(from..)?
+        .windows(pattern.len())
+        .position(|window| window == pattern.as_slice())
+        .map(|idx| from + idx)
+}
+
+fn is_italic_marker(chars: &[char], idx: usize, is_italic: bool) -> bool {
+    let is_word_char =
+        |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric());
+
+    if is_italic {
+        !is_word_char(chars.get(idx + 1))
+    } else {
+        !is_word_char(idx.checked_sub(1).and_then(|i| chars.get(i)))
+            && find(chars, idx + 1, "_").is_some()
+    }
+}
+
+/// Parses a `[text](url)` link starting at `idx`, returning its text, url
+/// and the index right after it.
+fn parse_link(chars: &[char], idx: usize) -> Option<(String, String, usize)> {
+    let text_end = find(chars, idx + 1, "]")?;
+
+    if chars.get(text_end + 1) != Some(&'(') {
+        return None;
+    }
+
+    let url_end = find(chars, text_end + 2, ")")?;
+    let text = chars[idx + 1..text_end].iter().collect::<String>();
+    let url = chars[text_end + 2..url_end].iter().collect::<String>();
+
+    if text.is_empty() || url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((text, url, url_end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: TextStyle = TextStyle {
+        bold: false,
+        italic: false,
+        strikethrough: false,
+    };
+
+    const BOLD: TextStyle = TextStyle {
+        bold: true,
+        ..PLAIN
+    };
+
+    const ITALIC: TextStyle = TextStyle {
+        italic: true,
+        ..PLAIN
+    };
+
+    fn text(text: &str, style: TextStyle) -> RichSpan {
+        RichSpan::Text {
+            text: text.to_owned(),
+            style,
+        }
+    }
+
+    fn spans(markdown: &str) -> Vec<RichSpan> {
+        RichText::parse_markdown(markdown).spans
+    }
+
+    #[test]
+    fn parses_styles() {
+        assert_eq!(
+            spans("a **b** _c_ ~~d~~"),
+            vec![
+                text("a ", PLAIN),
+                text("b", BOLD),
+                text(" ", PLAIN),
+                text("c", ITALIC),
+                text(" ", PLAIN),
+                text(
+                    "d",
+                    TextStyle {
+                        strikethrough: true,
+                        ..PLAIN
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_nested_styles() {
+        assert_eq!(
+            spans("**a _b_**"),
+            vec![
+                text("a ", BOLD),
+                text(
+                    "b",
+                    TextStyle {
+                        italic: true,
+                        ..BOLD
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_markers() {
+        assert_eq!(
+            spans(r"\*\*a\*\* \_b\_ \[c](d) \\"),
+            vec![text(r"**a** _b_ [c](d) \", PLAIN)]
+        );
+    }
+
+    #[test]
+    fn round_trips_escaped_text() {
+        let raw = r"**a** _b_ ~~c~~ `d` [e](f) \g";
+
+        assert_eq!(spans(&escape_markdown(raw)), vec![text(raw, PLAIN)]);
+    }
+
+    #[test]
+    fn keeps_unclosed_markers() {
+        assert_eq!(spans("**a"), vec![text("**a", PLAIN)]);
+        assert_eq!(spans("~~a"), vec![text("~~a", PLAIN)]);
+        assert_eq!(spans("_a"), vec![text("_a", PLAIN)]);
+        assert_eq!(spans("`a"), vec![text("`a", PLAIN)]);
+        assert_eq!(spans("```a"), vec![text("```a", PLAIN)]);
+    }
+
+    #[test]
+    fn keeps_underscores_within_words() {
+        assert_eq!(
+            spans("snake_case_name"),
+            vec![text("snake_case_name", PLAIN)]
+        );
+    }
+
+    #[test]
+    fn parses_links() {
+        assert_eq!(
+            spans("see **[docs](https://example.com)**"),
+            vec![
+                text("see ", PLAIN),
+                RichSpan::Link {
+                    text: "docs".into(),
+                    url: "https://example.com".into(),
+                    style: BOLD,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_malformed_links() {
+        for markdown in ["[a] (b)", "[a](b c)", "[](b)", "[a]()", "[a](b"] {
+            assert_eq!(spans(markdown), vec![text(markdown, PLAIN)]);
+        }
+    }
+
+    #[test]
+    fn parses_inline_code() {
+        assert_eq!(
+            spans("run `a **b**`"),
+            vec![text("run ", PLAIN), RichSpan::Code("a **b**".into())]
+        );
+    }
+
+    #[test]
+    fn parses_code_blocks() {
+        assert_eq!(
+            spans("```rust\nfn main() {}\n```"),
+            vec![RichSpan::Pre {
+                language: Some("rust".into()),
+                code: "fn main() {}".into(),
+            }]
+        );
+
+        assert_eq!(
+            spans("```\n**a**\n```"),
+            vec![RichSpan::Pre {
+                language: None,
+                code: "**a**".into(),
+            }]
+        );
+
+        assert_eq!(
+            spans("```a b\nc```"),
+            vec![RichSpan::Pre {
+                language: None,
+                code: "a b\nc".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn renders_plain_text() {
+        let text = RichText::parse_markdown(
+            "**a** [b](https://b.com) [https://c.com](https://c.com) `d`",
+        );
+
+        assert_eq!(text.to_plain_text(), "a b (https://b.com) https://c.com d");
+    }
+
+    #[test]
+    fn keeps_short_texts_whole() {
+        let text = RichText::plain("abc");
+
+        assert_eq!(text.clone().split(3), vec![text]);
+    }
+
+    #[test]
+    fn splits_at_line_breaks_then_spaces() {
+        let chunks = RichText::plain("ab cd\nef gh").split(8);
+
+        assert_eq!(
+            chunks,
+            vec![RichText::plain("ab cd\n"), RichText::plain("ef gh"),]
+        );
+
+        let chunks = RichText::plain("ab cd ef").split(6);
+
+        assert_eq!(
+            chunks,
+            vec![RichText::plain("ab cd "), RichText::plain("ef"),]
+        );
+    }
+
+    #[test]
+    fn splits_long_words() {
+        let chunks = RichText::plain("abcdefg").split(3);
+
+        assert_eq!(
+            chunks,
+            vec![
+                RichText::plain("abc"),
+                RichText::plain("def"),
+                RichText::plain("g"),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_multibyte_texts_by_characters() {
+        let chunks = RichText::plain("مرحبا بالعالم 👋🏽").split(6);
+
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 6));
+        assert_eq!(
+            chunks
+                .iter()
+                .map(RichText::to_plain_text)
+                .collect::<String>(),
+            "مرحبا بالعالم 👋🏽"
+        );
+    }
+
+    #[test]
+    fn moves_spans_that_fit_a_chunk_to_the_next_one() {
+        let chunks = RichText::parse_markdown("abcd `efgh`").split(6);
+
+        assert_eq!(
+            chunks,
+            vec![
+                RichText::plain("abcd "),
+                RichText {
+                    spans: vec![RichSpan::Code("efgh".into())],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use kernel_entities::{entities::link::Channel, traits::Key};
+use uuid::Uuid;
+
+use crate::{error::AppResult, Service};
+
+pub const BLOB_URI_PREFIX: &str = "blob://";
+pub const CHANNEL_BLOBS_PREFIX: &str = "channels";
+
+#[async_trait::async_trait]
+pub trait BlobStorageService: Service + Send + Sync {
+    async fn put(&self, key: &str, blob: Blob) -> AppResult<()>;
+    async fn get(&self, key: &str) -> AppResult<Blob>;
+    async fn remove(&self, key: &str) -> AppResult<()>;
+    async fn exists(&self, key: &str) -> AppResult<bool>;
+}
+
+#[derive(Clone, Debug)]
+pub struct Blob {
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+pub fn blob_uri_of(key: &str) -> String {
+    format!("{BLOB_URI_PREFIX}{key}")
+}
+
+pub fn blob_key_of(uri: &str) -> Option<&str> {
+    uri.strip_prefix(BLOB_URI_PREFIX)
+}
+
+/// Creates a unique key for a blob stored on behalf of a channel.
+pub fn new_channel_blob_key(channel_id: &Key<Channel>) -> String {
+    format!("{CHANNEL_BLOBS_PREFIX}/{channel_id}/{}", Uuid::new_v4())
+}
+
+/// Returns the channel a blob key was created for by `new_channel_blob_key`,
+/// or `None` for keys of any other form.
+pub fn channel_of_blob_key(key: &str) -> Option<Key<Channel>> {
+    let mut parts = key.split('/');
+
+    let (Some(CHANNEL_BLOBS_PREFIX), Some(channel_id), Some(name), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+
+    // both parts must be uuids, which rules out any path traversal
+    name.parse::<Uuid>().ok()?;
+
+    channel_id.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_channels_of_generated_keys() {
+        let channel_id = Key::new(Uuid::new_v4());
+        let key = new_channel_blob_key(&channel_id);
+
+        assert_eq!(channel_of_blob_key(&key), Some(channel_id));
+    }
+
+    #[test]
+    fn rejects_keys_of_other_forms() {
+        let channel_id = Uuid::new_v4();
+        let name = Uuid::new_v4();
+
+        for key in [
+            format!("other/{channel_id}/{name}"),
+            format!("channels/{channel_id}"),
+            format!("channels/{channel_id}/{name}/extra"),
+            format!("channels/{channel_id}/../{name}"),
+            format!("channels/{channel_id}/name"),
+            format!("channels/not-a-uuid/{name}"),
+        ] {
+            assert_eq!(channel_of_blob_key(&key), None, "{key}");
+        }
+    }
+}
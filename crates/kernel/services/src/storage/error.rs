@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("blob not found")]
+    NotFound,
+
+    #[error("invalid blob key: {0}")]
+    InvalidKey(String),
+
+    #[error("blob of {size} bytes exceeds the maximum of {max_size} bytes")]
+    TooLarge { size: usize, max_size: usize },
+
+    #[error("io error: {0}")]
+    Io(String),
+
+    #[error("storage backend error: {0}")]
+    Backend(anyhow::Error),
+}